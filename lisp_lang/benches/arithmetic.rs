@@ -0,0 +1,23 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use lisp_lang::{interpreter::Interpreter, parsing::parse};
+
+/// Programs dominated by integer arithmetic, the case calls to `+`, `-`,
+/// `*`, `/` and `%` on numbers skip the general call path for.
+const PROGRAMS: &[(&str, &str)] = &[
+    ("sum of squares", "(loop! ((x 0) (acc 0)) (if! (< x 10000) (recur (+ x 1) (+ acc (* x x))) acc))"),
+    (
+        "polynomial",
+        "(loop! ((x 0) (acc 0)) (if! (< x 10000) (recur (+ x 1) (+ acc (- (* 3 (* x x)) (+ (* 2 x) 7)))) acc))",
+    ),
+    ("fib", "(do! (defn! fib (n) (if! (< n 2) n (+ (fib (- n 1)) (fib (- n 2))))) (fib 15))"),
+];
+
+fn arithmetic(c: &mut Criterion) {
+    for (name, source) in PROGRAMS {
+        let (_, expr) = parse(source).unwrap();
+        c.bench_function(name, |b| b.iter(|| Interpreter::new().eval(&expr).unwrap()));
+    }
+}
+
+criterion_group!(benches, arithmetic);
+criterion_main!(benches);
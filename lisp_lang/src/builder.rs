@@ -0,0 +1,38 @@
+//! Functions for building expressions in Rust without going through the
+//! parser, e.g. `call("map", [sym("inc"), quote(list([1, 2, 3]))])` for
+//! `(map inc '(1 2 3))`.
+
+use crate::parsing::LispVal;
+
+pub fn sym(name: &str) -> LispVal {
+    LispVal::Symbol(name.into())
+}
+
+/// `:name`, without the colon.
+pub fn keyword(name: &str) -> LispVal {
+    LispVal::Keyword(name.into())
+}
+
+pub fn list<I>(values: I) -> LispVal
+where
+    I: IntoIterator,
+    I::Item: Into<LispVal>,
+{
+    values.into_iter().map(Into::into).collect()
+}
+
+/// A call to `name` with `arguments`, which are evaluated when the call is.
+pub fn call<I>(name: &str, arguments: I) -> LispVal
+where
+    I: IntoIterator,
+    I::Item: Into<LispVal>,
+{
+    std::iter::once(sym(name))
+        .chain(arguments.into_iter().map(Into::into))
+        .collect()
+}
+
+/// `'value`, which evaluates to `value` itself.
+pub fn quote(value: impl Into<LispVal>) -> LispVal {
+    LispVal::Unevaluated(Box::new(value.into()))
+}
@@ -0,0 +1,176 @@
+//! Error message templates in every supported language, keyed by error
+//! code. Templates name the parts of an error in braces, like `{name}`, so
+//! translations can put them where their grammar needs them.
+
+use crate::error_code::ErrorCode;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Language {
+    #[default]
+    English,
+    BrazilianPortuguese,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::BrazilianPortuguese];
+
+    /// The language's tag, like `pt-BR`.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::BrazilianPortuguese => "pt-BR",
+        }
+    }
+
+    /// Looks a language up by its tag, ignoring case.
+    pub fn parse(tag: &str) -> Option<Language> {
+        Self::ALL.into_iter().find(|language| language.tag().eq_ignore_ascii_case(tag))
+    }
+}
+
+/// The message template of an evaluation error, or `None` for codes raised
+/// by the parser and the REPL.
+pub fn template(code: ErrorCode, language: Language) -> Option<&'static str> {
+    let (english, portuguese) = match code {
+        ErrorCode::InvalidArgumentType => (
+            "Invalid argument type for {name} at position {position}, expected {expected}, got {got}",
+            "Tipo de argumento inválido para {name} na posição {position}, esperado {expected}, recebido {got}",
+        ),
+        ErrorCode::InvalidConcatenation => (
+            "Invalid argument types, cannot concat {left} and {right}",
+            "Tipos de argumento inválidos, não é possível concatenar {left} e {right}",
+        ),
+        ErrorCode::InvalidFunctionCall => (
+            "Invalid function call, got {head} of type {type}. \nIs this supposed to be a list? If so, use {list}",
+            "Chamada de função inválida, recebido {head} do tipo {type}. \nIsso deveria ser uma lista? Se sim, use {list}",
+        ),
+        ErrorCode::UnknownIdentifier => ("Unknown identifier {name}.", "Identificador desconhecido {name}."),
+        ErrorCode::InvalidRecord => (
+            "Expected a {expected} record, got {got}",
+            "Esperado um registro {expected}, recebido {got}",
+        ),
+        ErrorCode::NoMethod => (
+            "No method in {name} for dispatch value {value}",
+            "Nenhum método em {name} para o valor de despacho {value}",
+        ),
+        ErrorCode::ConstantRedefinition => (
+            "Cannot redefine constant {name}",
+            "Não é possível redefinir a constante {name}",
+        ),
+        ErrorCode::Interrupted => ("Evaluation interrupted", "Avaliação interrompida"),
+        ErrorCode::InvalidMatrix => ("Invalid matrix, {reason}", "Matriz inválida, {reason}"),
+        ErrorCode::IncomparableValues => (
+            "Cannot compare {left} with {right}",
+            "Não é possível comparar {left} com {right}",
+        ),
+        ErrorCode::EmptyList => (
+            "{name} expects a non-empty collection",
+            "{name} espera uma coleção não vazia",
+        ),
+        ErrorCode::CyclicGraph => (
+            "Cannot sort a graph that has a cycle",
+            "Não é possível ordenar um grafo que tem um ciclo",
+        ),
+        ErrorCode::InvalidModule => (
+            "Cannot load module {name}, {reason}",
+            "Não é possível carregar o módulo {name}, {reason}",
+        ),
+        ErrorCode::HostStore => (
+            "Cannot access {key} in the host store, {reason}",
+            "Não é possível acessar {key} no armazenamento do host, {reason}",
+        ),
+        ErrorCode::TaskBudget => (
+            "Task {task} evaluated more than {budget} expressions in one tick",
+            "A tarefa {task} avaliou mais de {budget} expressões em um tick",
+        ),
+        ErrorCode::InvalidBinding => (
+            "Invalid binding {binding} in {name}, expected a list of a name and a value",
+            "Associação inválida {binding} em {name}, esperada uma lista de um nome e um valor",
+        ),
+        ErrorCode::NoMatch => (
+            "No pattern in {name} matches {value}",
+            "Nenhum padrão em {name} corresponde a {value}",
+        ),
+        ErrorCode::MisplacedRecur => (
+            "{name} can only be used in tail position inside {loop}",
+            "{name} só pode ser usado na posição final dentro de {loop}",
+        ),
+        ErrorCode::RecurArity => (
+            "{name} was given {got} values for the {expected} bindings of {loop}",
+            "{name} recebeu {got} valores para as {expected} associações de {loop}",
+        ),
+        ErrorCode::DivisionByZero => (
+            "{name} can't divide an integer by zero",
+            "{name} não pode dividir um inteiro por zero",
+        ),
+        ErrorCode::CollectionTooLarge => (
+            "{name} can't build a list of {size} elements, the limit is {limit}",
+            "{name} não pode construir uma lista de {size} elementos, o limite é {limit}",
+        ),
+        ErrorCode::ArgumentCount => (
+            "{name} expects {expected} values, got {got}",
+            "{name} espera {expected} valores, recebeu {got}",
+        ),
+        _ => return None,
+    };
+
+    Some(match language {
+        Language::English => english,
+        Language::BrazilianPortuguese => portuguese,
+    })
+}
+
+/// The names in braces in `template`, in order.
+pub fn placeholders(template: &str) -> Vec<&str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{evaluation::error::EvalError, parsing::LispType};
+
+    #[test]
+    fn test_languages_are_parsed_by_tag() {
+        assert_eq!(Language::parse("pt-br"), Some(Language::BrazilianPortuguese));
+        assert_eq!(Language::parse("en"), Some(Language::English));
+        assert_eq!(Language::parse("fr"), None);
+    }
+
+    #[test]
+    fn test_messages_follow_the_template_of_their_language() {
+        let error = EvalError::InvalidArgumentType {
+            name: "+".to_string(),
+            expected: LispType::Number,
+            got: LispType::String,
+            position: 1,
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "Invalid argument type for `+` at position `1`, expected `number`, got `string`"
+        );
+        assert_eq!(
+            error.message_in(Language::BrazilianPortuguese).iter().map(ToString::to_string).collect::<String>(),
+            "Tipo de argumento inválido para `+` na posição `1`, esperado `number`, recebido `string`"
+        );
+    }
+
+    #[test]
+    fn test_translations_have_the_same_placeholders() {
+        for code in ErrorCode::ALL.into_iter().filter(|code| code.number() < 100) {
+            let english = template(code, Language::English).unwrap();
+            for language in Language::ALL {
+                let mut expected = placeholders(english);
+                let mut translated = placeholders(template(code, language).unwrap());
+                expected.sort();
+                translated.sort();
+                assert_eq!(translated, expected, "{code} in {}", language.tag());
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{parsing::{LispVal, error::LispValUnwrapError, LispType}};
+use crate::{parsing::{LispVal, error::LispValUnwrapError, LispType, sorted_elements}};
 
 impl FromIterator<LispVal> for LispVal {
     fn from_iter<T: IntoIterator<Item = LispVal>>(iter: T) -> Self {
@@ -12,6 +12,12 @@ impl From<i64> for LispVal {
     }
 }
 
+impl From<f64> for LispVal {
+    fn from(f: f64) -> Self {
+        Self::Float(f)
+    }
+}
+
 impl From<bool> for LispVal {
     fn from(b: bool) -> Self {
         Self::Boolean(b)
@@ -20,7 +26,13 @@ impl From<bool> for LispVal {
 
 impl From<String> for LispVal {
     fn from(s: String) -> Self {
-        Self::String(s)
+        Self::String(s.into())
+    }
+}
+
+impl From<&str> for LispVal {
+    fn from(s: &str) -> Self {
+        Self::String(s.into())
     }
 }
 
@@ -50,6 +62,22 @@ impl TryFrom<LispVal> for i64 {
     }
 }
 
+/// Integers are promoted, so builtins taking floats accept both.
+impl TryFrom<LispVal> for f64 {
+    type Error = LispValUnwrapError;
+
+    fn try_from(value: LispVal) -> Result<Self, Self::Error> {
+        match value {
+            LispVal::Float(f) => Ok(f),
+            LispVal::Number(n) => Ok(n as f64),
+            _ => Err(LispValUnwrapError {
+                expected: LispType::Float,
+                got: value.to_type(),
+            }),
+        }
+    }
+}
+
 impl TryFrom<LispVal> for bool {
     type Error = LispValUnwrapError;
 
@@ -69,7 +97,7 @@ impl TryFrom<LispVal> for String {
 
     fn try_from(value: LispVal) -> Result<Self, Self::Error> {
         match value {
-            LispVal::String(s) => Ok(s),
+            LispVal::String(s) => Ok(s.into()),
             _ => Err(LispValUnwrapError {
                 expected: LispType::String,
                 got: value.to_type(),
@@ -83,7 +111,8 @@ impl TryFrom<LispVal> for Vec<LispVal> {
 
     fn try_from(value: LispVal) -> Result<Self, Self::Error> {
         match value {
-            LispVal::List(v) => Ok(v),
+            LispVal::List(v) | LispVal::Vector(v) => Ok(v),
+            LispVal::Set(elements) => Ok(sorted_elements(&elements).into_iter().cloned().collect()),
             _ => Err(LispValUnwrapError {
                 expected: LispType::List,
                 got: value.to_type(),
@@ -1,23 +1,110 @@
-use std::fmt::Formatter;
+use std::{cell::Cell, collections::HashMap, fmt::Formatter};
 
-use crate::{parsing::LispVal, evaluation::{scope::Scope, error::EvalError}};
+use crate::{parsing::{escape, sorted_elements, sorted_entries, LispVal}, evaluation::scope::Scope};
+
+thread_local! {
+    static FLOAT_PRECISION: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Runs `f` with floats displayed rounded to `precision` significant
+/// digits on the current thread, or in full when it's `None`. Either way
+/// they read back as floats, and in full as the same float.
+pub fn with_float_precision<T>(precision: Option<usize>, f: impl FnOnce() -> T) -> T {
+    let previous = FLOAT_PRECISION.replace(precision);
+    let result = f();
+    FLOAT_PRECISION.set(previous);
+    result
+}
+
+fn round_to_digits(n: f64, digits: usize) -> f64 {
+    format!("{:.*e}", digits.max(1) - 1, n).parse().unwrap_or(n)
+}
 
 impl std::fmt::Display for LispVal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             LispVal::Void() => write!(f, "void"),
+            LispVal::Nil => write!(f, "nil"),
             LispVal::Symbol(atom) => write!(f, "{}", atom),
-            LispVal::Number(n) => write!(f, "{}", n.to_string()),
-            LispVal::String(s) => write!(f, "\"{}\"", s.to_string()),
-            LispVal::Unevaluated(expr) => write!(f, "'{}", expr.to_string()),
-            LispVal::Boolean(b) => write!(f, "{}", b.to_string()),
-            LispVal::Function { parameters: args, body, applied } => {
-                write!(f, "(fn '({}) '({}))", args.join(" "), body.to_string())?;
+            LispVal::Keyword(name) => write!(f, ":{}", name),
+            LispVal::Number(n) => write!(f, "{}", n),
+            // Debug formatting is the shortest text reading back as the same
+            // float, and keeps the point, so `2.0` reads back as a float
+            LispVal::Float(n) => match FLOAT_PRECISION.get() {
+                Some(digits) => write!(f, "{:?}", round_to_digits(*n, digits)),
+                None => write!(f, "{:?}", n),
+            },
+            // Printed as the call that builds it
+            LispVal::Complex(re, im) => write!(f, "(complex {} {})", LispVal::Float(*re), LispVal::Float(*im)),
+            LispVal::String(s) => write!(f, "\"{}\"", escape(s)),
+            LispVal::Unevaluated(expr) => write!(f, "'{}", expr),
+            LispVal::Boolean(b) => write!(f, "{}", b),
+            LispVal::Function { parameters: args, body, applied, .. } => {
+                write!(f, "(fn '({}) '({}))", args.join(" "), body)?;
                 if !applied.is_empty() {
                     write!(f, ", {}", std::convert::Into::<LispVal>::into(applied.clone()))?;
                 }
                 Ok(())
             }
+            LispVal::Record { name, fields } => write!(
+                f,
+                "#<{} {}>",
+                name,
+                fields
+                    .iter()
+                    .map(|(field, value)| format!("{field}: {value}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            LispVal::MultiMethod { name, methods, .. } => {
+                write!(f, "#<multimethod {} ({} methods)>", name, methods.len())
+            }
+            LispVal::Heap { entries, .. } => write!(
+                f,
+                "#<heap{}>",
+                entries
+                    .iter()
+                    .map(|((priority, _), value)| format!(" {value}: {priority}"))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            LispVal::Deque(values) => write!(
+                f,
+                "#<deque{}>",
+                values.iter().map(|value| format!(" {value}")).collect::<String>()
+            ),
+            LispVal::Vector(values) => write!(
+                f,
+                "[{}]",
+                values
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            LispVal::Map(entries) => write!(
+                f,
+                "{{{}}}",
+                sorted_entries(entries)
+                    .iter()
+                    .map(|(key, value)| format!("{key} {value}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            LispVal::Set(elements) => write!(
+                f,
+                "#{{{}}}",
+                sorted_elements(elements)
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            LispVal::Pair(..) => {
+                let (heads, tail) = self.pair_parts();
+                let heads: Vec<_> = heads.iter().map(ToString::to_string).collect();
+                write!(f, "({} . {tail})", heads.join(" "))
+            }
             LispVal::List(values) => write!(
                 f,
                 "({})",
@@ -31,9 +118,61 @@ impl std::fmt::Display for LispVal {
     }
 }
 
+impl LispVal {
+    /// Like `to_string`, but a list that appears more than once is printed
+    /// in full only the first time, labelled as in `#0=(1 2)`, and as `#0#`
+    /// after that. The reader accepts the labels, so the output parses back
+    /// to an equal value.
+    pub fn to_labeled_string(&self) -> String {
+        fn count<'a>(value: &'a LispVal, counts: &mut HashMap<&'a LispVal, usize>) {
+            match value {
+                LispVal::List(values) if !values.is_empty() => {
+                    let seen = counts.entry(value).or_default();
+                    *seen += 1;
+                    // Repeats are printed as a label, so their elements are only counted once
+                    if *seen == 1 {
+                        values.iter().for_each(|v| count(v, counts));
+                    }
+                }
+                LispVal::Unevaluated(value) => count(value, counts),
+                _ => {}
+            }
+        }
+
+        fn write<'a>(
+            value: &'a LispVal,
+            counts: &HashMap<&'a LispVal, usize>,
+            labels: &mut HashMap<&'a LispVal, usize>,
+        ) -> String {
+            match value {
+                LispVal::List(values) if counts.get(value).is_some_and(|n| *n > 1) => {
+                    if let Some(label) = labels.get(value) {
+                        return format!("#{label}#");
+                    }
+                    let label = labels.len();
+                    labels.insert(value, label);
+                    let items: Vec<_> = values.iter().map(|v| write(v, counts, labels)).collect();
+                    format!("#{label}=({})", items.join(" "))
+                }
+                LispVal::List(values) => {
+                    let items: Vec<_> = values.iter().map(|v| write(v, counts, labels)).collect();
+                    format!("({})", items.join(" "))
+                }
+                LispVal::Unevaluated(value) => format!("'{}", write(value, counts, labels)),
+                value => value.to_string(),
+            }
+        }
+
+        let mut counts = HashMap::new();
+        count(self, &mut counts);
+
+        write(self, &counts, &mut HashMap::new())
+    }
+}
+
 impl std::fmt::Display for Scope {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let values = self.bindings
+        let values = self.visible_bindings()
             .iter()
             .map(|(name, value)| format!("{name}: {value}"))
             .collect::<Vec<_>>().join(", ");
@@ -41,33 +180,3 @@ impl std::fmt::Display for Scope {
         write!(f, "{{ {values} }}")
     }
 }
-
-impl std::fmt::Display for EvalError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            EvalError::InvalidArgumentType {
-                name,
-                expected,
-                got,
-                position,
-            } => write!(
-                f,
-                "Invalid argument type for `{}` at position `{}`, expected `{}`, got `{}`",
-                name, position, expected, got
-            ),
-            EvalError::InvalidConcatenation { left, right } => write!(
-                f,
-                "Invalid argument types, cannot concat `{}` and `{}`",
-                left, right
-            ),
-            EvalError::InvalidFunctionCall { values } => {
-                let correct_expr = LispVal::Unevaluated(Box::new(LispVal::List(values.clone())));
-                let head = values.get(0).unwrap();
-                write!(f, "Invalid function call, got `{head}` of type `{}`. \nIs this supposed to be a list? If so, use `{}`", head.to_type(), correct_expr)
-            }
-            EvalError::UnknownIdentifier(identifier) => {
-                write!(f, "Unknown identifier `{}`.", identifier)
-            }
-        }
-    }
-}
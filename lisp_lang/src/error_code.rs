@@ -0,0 +1,364 @@
+/// Stable identifiers for every error the interpreter and the REPL report,
+/// printed as `E0004` so documentation and tooling can refer to them.
+///
+/// Codes are never reused: a removed error keeps its number reserved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    // Evaluation
+    InvalidArgumentType = 1,
+    InvalidConcatenation = 2,
+    InvalidFunctionCall = 3,
+    UnknownIdentifier = 4,
+    InvalidRecord = 5,
+    NoMethod = 6,
+    ConstantRedefinition = 7,
+    Interrupted = 8,
+    InvalidMatrix = 9,
+    IncomparableValues = 10,
+    EmptyList = 11,
+    CyclicGraph = 12,
+    InvalidModule = 13,
+    HostStore = 14,
+    TaskBudget = 15,
+    InvalidBinding = 16,
+    NoMatch = 17,
+    MisplacedRecur = 18,
+    RecurArity = 19,
+    DivisionByZero = 20,
+    CollectionTooLarge = 21,
+    ArgumentCount = 22,
+
+    // Parsing
+    InvalidSyntax = 101,
+    UnexpectedInput = 102,
+
+    // REPL
+    UnknownCommand = 201,
+    UnreadableFile = 202,
+    EvaluationThread = 203,
+    UnknownErrorCode = 204,
+    InternalError = 205,
+    NothingToPin = 206,
+    Recording = 207,
+    UnknownLanguage = 208,
+    UnknownLanguageVersion = 209,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 33] = [
+        ErrorCode::InvalidArgumentType,
+        ErrorCode::InvalidConcatenation,
+        ErrorCode::InvalidFunctionCall,
+        ErrorCode::UnknownIdentifier,
+        ErrorCode::InvalidRecord,
+        ErrorCode::NoMethod,
+        ErrorCode::ConstantRedefinition,
+        ErrorCode::Interrupted,
+        ErrorCode::InvalidMatrix,
+        ErrorCode::IncomparableValues,
+        ErrorCode::EmptyList,
+        ErrorCode::CyclicGraph,
+        ErrorCode::InvalidModule,
+        ErrorCode::HostStore,
+        ErrorCode::TaskBudget,
+        ErrorCode::InvalidBinding,
+        ErrorCode::NoMatch,
+        ErrorCode::MisplacedRecur,
+        ErrorCode::RecurArity,
+        ErrorCode::DivisionByZero,
+        ErrorCode::CollectionTooLarge,
+        ErrorCode::ArgumentCount,
+        ErrorCode::InvalidSyntax,
+        ErrorCode::UnexpectedInput,
+        ErrorCode::UnknownCommand,
+        ErrorCode::UnreadableFile,
+        ErrorCode::EvaluationThread,
+        ErrorCode::UnknownErrorCode,
+        ErrorCode::InternalError,
+        ErrorCode::NothingToPin,
+        ErrorCode::Recording,
+        ErrorCode::UnknownLanguage,
+        ErrorCode::UnknownLanguageVersion,
+    ];
+
+    pub fn number(self) -> u16 {
+        self as u16
+    }
+
+    /// Looks a code up by its printed form, e.g. `E0004`.
+    pub fn parse(code: &str) -> Option<ErrorCode> {
+        let number: u16 = code.strip_prefix('E')?.parse().ok()?;
+
+        Self::ALL.into_iter().find(|code| code.number() == number)
+    }
+}
+
+/// The long form of an error, shown by `:explain` in the REPL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    pub code: ErrorCode,
+    pub summary: &'static str,
+    /// Input that raises the error.
+    pub example: &'static str,
+    pub fix: &'static str,
+}
+
+impl ErrorCode {
+    pub fn explain(self) -> Explanation {
+        let (summary, example, fix) = match self {
+            ErrorCode::InvalidArgumentType => (
+                "A builtin was called with an argument of the wrong type.",
+                "(+ 1 \"2\")",
+                "Pass a value of the expected type, converting it first if needed. The \
+                 message names the builtin and the position of the argument, counting from 0.",
+            ),
+            ErrorCode::InvalidConcatenation => (
+                "Two values could not be concatenated. `concat` now accepts any pair of \
+                 values, so this code is no longer raised.",
+                "(concat '(1) 2)",
+                "Nothing to fix; the code is kept so it is never reused.",
+            ),
+            ErrorCode::InvalidFunctionCall => (
+                "The head of a list being evaluated is not something that can be called.",
+                "(1 2 3)",
+                "Quote the list if it's meant as data: `'(1 2 3)`. Otherwise make sure \
+                 the first element is a function or the name of one.",
+            ),
+            ErrorCode::UnknownIdentifier => (
+                "A symbol was evaluated but nothing is bound to that name.",
+                "(+ 1 count)",
+                "Define the name with `def!`, `defn!` or `const!` before using it, or fix \
+                 its spelling. Definitions made inside a function body are not visible \
+                 outside of it.",
+            ),
+            ErrorCode::InvalidRecord => (
+                "A record accessor was given a value that isn't a record of its type.",
+                "(list (defrecord! point (x y)) (point-x 1))",
+                "Pass a record built by the matching constructor, e.g. `(point-x (point 1 2))`.",
+            ),
+            ErrorCode::NoMethod => (
+                "A multimethod was called, but none of its methods matches the value \
+                 returned by its dispatch function.",
+                "(list (defmulti! speak record_name) (speak (make_record 'cat '() '())))",
+                "Add a method for that dispatch value with `defmethod!`.",
+            ),
+            ErrorCode::ConstantRedefinition => (
+                "A name bound with `const!` was defined again.",
+                "(list (const! LIMIT 3) (def! LIMIT 4))",
+                "Pick another name; constants can't be redefined or shadowed.",
+            ),
+            ErrorCode::Interrupted => (
+                "Evaluation was cancelled, usually by pressing Ctrl-C in the REPL.",
+                "(fold '+ 0 (list 1 2 3)) ; interrupted while running",
+                "Nothing to fix. If the expression never finishes, look for unbounded \
+                 recursion.",
+            ),
+            ErrorCode::InvalidMatrix => (
+                "A matrix builtin was given lists that don't form a valid matrix, or \
+                 matrices of incompatible sizes.",
+                "(matrix '((1 2) (3)))",
+                "Make every row the same length, and check sizes and indexes against the \
+                 dimensions given in the message.",
+            ),
+            ErrorCode::IncomparableValues => (
+                "Values of different types were compared while sorting.",
+                "(sort '(1 \"a\"))",
+                "Sort lists holding a single type, or use `sort-by` with a key function \
+                 that maps every element to the same type.",
+            ),
+            ErrorCode::EmptyList => (
+                "A builtin that needs at least one element was given an empty list, heap \
+                 or deque.",
+                "(max-by (fn! (n) n) '())",
+                "Check the collection isn't empty before the call.",
+            ),
+            ErrorCode::CyclicGraph => (
+                "`topo-sort` was given a graph with a cycle, which has no topological order.",
+                "(topo-sort (graph '((a b) (b a))))",
+                "Remove one of the edges forming the cycle. `bfs` from a node on the \
+                 cycle lists the nodes involved.",
+            ),
+            ErrorCode::InvalidModule => (
+                "`load` or `require` could not find a module, or its source doesn't parse.",
+                "(load \"missing\")",
+                "Check the module name. Embedders must set a module resolver for modules to \
+                 be found at all; the REPL resolves names against the script's directory, or the current one.",
+            ),
+            ErrorCode::HostStore => (
+                "`store-get` or `store-set!` was used without a host store, or the host \
+                 refused the value.",
+                "(store-set! \"key\" 1)",
+                "Embedders must register a host store for scripts to use it; the REPL has none.",
+            ),
+            ErrorCode::TaskBudget => (
+                "A task defined with `deftask!` evaluated more expressions in one tick than \
+                 its budget allows, so it was unscheduled.",
+                "(do! (deftask! squares (map (fn! (x) (* x x)) (repeat 1000 2))) (tick))",
+                "Split the work across ticks, carrying progress in `state`, or have the host \
+                 raise the budget.",
+            ),
+            ErrorCode::InvalidBinding => (
+                "A binding of `let!` or `letrec!` is not a list of a name and the expression \
+                 giving its value.",
+                "(let! ((x 1) (y)) (+ x y))",
+                "Write each binding as `(name value)`, like `(y 2)`.",
+            ),
+            ErrorCode::NoMatch => (
+                "None of the patterns of a `match!` matches the value it was given.",
+                "(match! 3 (1 \"one\") (2 \"two\"))",
+                "Add a clause for the value, or end with a `_` clause that matches anything.",
+            ),
+            ErrorCode::MisplacedRecur => (
+                "`recur` was called without a `loop!` around it to start over, or where its \
+                 value would still be used, like an argument or inside a function.",
+                "(do! (defn! count-down (n) (if! (= n 0) 0 (recur (- n 1)))) (count-down 3))",
+                "Call `recur` only as the last thing a `loop!` body does, or call the \
+                 function by name to recurse.",
+            ),
+            ErrorCode::RecurArity => (
+                "`recur` was given a different number of values than its `loop!` has bindings.",
+                "(loop! ((i 0)) (recur 1 2))",
+                "Pass `recur` one value for each binding of the `loop!`, in the same order.",
+            ),
+            ErrorCode::DivisionByZero => (
+                "An integer was divided by zero, or the remainder of dividing by zero was \
+                 asked for.",
+                "(/ 1 0)",
+                "Check the divisor before dividing, or divide floats, where dividing by zero \
+                 gives infinity.",
+            ),
+            ErrorCode::CollectionTooLarge => (
+                "A builtin was asked to build a list with more elements than it allows.",
+                "(repeat 10000000000 0)",
+                "Build the list in smaller pieces, or find a way to avoid needing it all at once.",
+            ),
+            ErrorCode::ArgumentCount => (
+                "A builtin was given a list with a different number of values than another \
+                 list it pairs them with, like the fields of a record.",
+                "(make_record 'point '(x y) '(1))",
+                "Give one value for each field, in the same order.",
+            ),
+            ErrorCode::InvalidSyntax => (
+                "The input is not a valid expression, for example an unclosed list.",
+                "(+ 1 2",
+                "Balance the parentheses and quotes.",
+            ),
+            ErrorCode::UnexpectedInput => (
+                "Something follows a complete expression on the same REPL line.",
+                "(+ 1 2) 3",
+                "Enter one expression per line, or wrap several in `(list ...)`.",
+            ),
+            ErrorCode::UnknownCommand => (
+                "A REPL line starting with `:` names a command that doesn't exist.",
+                ":tpye (+ 1 2)",
+                "Check the spelling; commands include `:type`, `:dry-run` and `:explain`.",
+            ),
+            ErrorCode::UnreadableFile => (
+                "The script file passed to the REPL could not be read.",
+                "lisp_repl missing.lisp",
+                "Check the path exists and is readable.",
+            ),
+            ErrorCode::EvaluationThread => (
+                "The thread evaluating the input could not be started or stopped \
+                 without a result, for example when the system is out of memory.",
+                "(+ 1 2)",
+                "Free up memory or threads and try again.",
+            ),
+            ErrorCode::UnknownErrorCode => (
+                "`:explain` was given something that isn't a known error code.",
+                ":explain E9999",
+                "Use the code printed in brackets next to an error, e.g. `E0004`.",
+            ),
+            ErrorCode::InternalError => (
+                "The interpreter crashed while evaluating the input. This is a bug in the \
+                 interpreter, not in the input.",
+                "(/ 1 0)",
+                "Please report it with the input that caused it. The REPL keeps running \
+                 with the definitions it had before that input.",
+            ),
+            ErrorCode::NothingToPin => (
+                "`:pin` binds the last printed result, but nothing has been printed yet.",
+                ":pin answer",
+                "Evaluate an expression that has a value first, then pin it.",
+            ),
+            ErrorCode::Recording => (
+                "The file given to `--record` could not be created, or the file given to \
+                 `--replay` is not a recorded session.",
+                "lisp_repl --replay notes.txt",
+                "Replay a file written by `--record`, unedited.",
+            ),
+            ErrorCode::UnknownLanguage => (
+                "`--lang` was given a language error messages aren't translated to.",
+                "lisp_repl --lang fr",
+                "Use `en` or `pt-BR`.",
+            ),
+            ErrorCode::UnknownLanguageVersion => (
+                "`--lang-version` or `repl-lang-version` was given a language version that \
+                 doesn't exist.",
+                "lisp_repl --lang-version 3",
+                "Use `v2`, the default, or `v1` to pin the original semantics.",
+            ),
+        };
+
+        Explanation {
+            code: self,
+            summary,
+            example,
+            fix,
+        }
+    }
+}
+
+impl std::fmt::Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {}", self.code, self.summary)?;
+        writeln!(f)?;
+        writeln!(f, "Erroneous example:")?;
+        writeln!(f)?;
+        writeln!(f, "    {}", self.example)?;
+        writeln!(f)?;
+        write!(f, "{}", self.fix)
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "E{:04}", self.number())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{interpreter::Interpreter, parsing::parse_complete};
+
+    use super::ErrorCode;
+
+    #[test]
+    fn test_codes_are_unique_and_round_trip() {
+        let numbers: HashSet<_> = ErrorCode::ALL.iter().map(|code| code.number()).collect();
+        assert_eq!(numbers.len(), ErrorCode::ALL.len());
+
+        for code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::parse(&code.to_string()), Some(code));
+        }
+        assert_eq!(ErrorCode::UnknownIdentifier.to_string(), "E0004");
+    }
+
+    #[test]
+    fn test_evaluation_examples_raise_their_code() {
+        let reproducible = ErrorCode::ALL
+            .into_iter()
+            .filter(|code| code.number() < 100)
+            .filter(|code| ![ErrorCode::InvalidConcatenation, ErrorCode::Interrupted].contains(code));
+
+        for code in reproducible {
+            let example = code.explain().example;
+            let expr = parse_complete(example).unwrap();
+            let error = Interpreter::new().eval(&expr).unwrap_err();
+
+            assert_eq!(error.code(), code, "{example}");
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::parsing::{Environment, LispVal};
+
+use super::{bind, error::EvalError, eval, scope::Scope, with_group, EvalResult};
+
+/// The name and value expression of each `(name value)` pair in the
+/// bindings of a `let!`-like form, which may be quoted.
+fn binding_pairs(scope: &Scope, bindings: &LispVal) -> Result<Vec<(String, LispVal)>, EvalError> {
+    let bindings: Vec<LispVal> = bindings
+        .unquoted()
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(0, &scope.context))?;
+
+    bindings
+        .into_iter()
+        .map(|binding| match &binding {
+            LispVal::List(pair) => match pair.as_slice() {
+                [LispVal::Symbol(name), value] => Some((name.to_string(), value.clone())),
+                _ => None,
+            },
+            _ => None,
+        }
+        .ok_or_else(|| EvalError::InvalidBinding { name: scope.context.to_string(), binding }))
+        .collect()
+}
+
+/// `(let! ((x 1) (y (+ x 1))) body)` evaluates `body` in a new frame with
+/// each name bound to its value, in order, so later values can use earlier
+/// names. The bindings can be quoted too, as in `(let! '((x 1)) body)`.
+/// The frame is dropped afterwards: neither the names nor anything the
+/// body defines outlive the form. With several body expressions, the value
+/// of the last one is returned.
+pub fn eval_let(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let (frame, _) = bind_in_order(&scope, values.first().unwrap())?;
+
+    eval_body(scope, frame, &values[1..])
+}
+
+/// `(letrec! ((even? (fn! (n) ...)) (odd? (fn! (n) ...))) body)` is like
+/// `let!`, except the functions it binds see every name it binds, their own
+/// included, wherever they are called from. That lets local helpers recurse
+/// and call each other without defining them for the rest of the scope.
+pub fn eval_letrec(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let (frame, bound) = bind_in_order(&scope, values.first().unwrap())?;
+
+    let group: Environment = bound.into_iter().collect();
+    let frame = group
+        .iter()
+        .try_fold(frame, |frame, (name, value)| bind(&frame, name.clone(), with_group(value, &group)))?;
+
+    eval_body(scope, frame, &values[1..])
+}
+
+/// `(loop! ((i 0) (total 0)) body...)` binds its names like `let!` and
+/// evaluates the body. When the body calls `(recur next-i next-total)`,
+/// it's evaluated again with the names bound to those values instead, as
+/// many times as it takes, without using up any stack. The value of the
+/// first run of the body that doesn't `recur` is the value of the loop.
+pub fn eval_loop(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let (_, bound) = bind_in_order(&scope, values.first().unwrap())?;
+    let (names, mut current): (Vec<_>, Vec<_>) = bound.into_iter().unzip();
+
+    loop {
+        let frame = names
+            .iter()
+            .zip(current)
+            .try_fold(scope.child(), |frame, (name, value)| bind(&frame, name.clone(), value))?;
+
+        match eval_body(scope.clone(), frame, &values[1..]) {
+            Err(EvalError::Recur { arguments }) if arguments.len() == names.len() => current = arguments,
+            Err(EvalError::Recur { arguments }) => {
+                return Err(EvalError::RecurArity { expected: names.len(), got: arguments.len() })
+            }
+            result => return result,
+        }
+    }
+}
+
+/// `(recur a b)` starts the innermost `loop!` it's in over, with its names
+/// bound to `a` and `b`. It gets there by unwinding like an error, so it
+/// ends whatever it's called from, like `return` would.
+pub fn eval_recur(_: Scope, values: &[LispVal]) -> EvalResult {
+    Err(EvalError::Recur { arguments: values.to_vec() })
+}
+
+/// Evaluates and binds each `(name value)` pair of `bindings` in a new
+/// frame, returning it and the values bound.
+fn bind_in_order(scope: &Scope, bindings: &LispVal) -> Result<(Scope, Vec<(String, LispVal)>), EvalError> {
+    binding_pairs(scope, bindings)?.into_iter().try_fold(
+        (scope.child(), Vec::new()),
+        |(frame, mut bound), (name, value)| {
+            let (frame, value) = eval(frame, &value).map_err(EvalError::outside_tail)?;
+            let frame = bind(&frame, name.clone(), value.clone())?;
+            bound.push((name, value));
+            Ok((frame, bound))
+        },
+    )
+}
+
+/// Evaluates `body` in `frame`, returning the value of its last expression
+/// and `scope` as it was before the frame.
+pub fn eval_body(scope: Scope, frame: Scope, body: &[LispVal]) -> EvalResult {
+    let (_, value) = eval_sequence(frame, body)?;
+
+    Ok((scope, value))
+}
+
+/// Evaluates `body` in order in `scope`, returning the value of its last
+/// expression. Only the last one is in tail position, where it may `recur`.
+pub fn eval_sequence(scope: Scope, body: &[LispVal]) -> EvalResult {
+    match body.split_last() {
+        Some((last, init)) => {
+            let scope = init
+                .iter()
+                .try_fold(scope, |scope, value| Ok(eval(scope, value).map_err(EvalError::outside_tail)?.0))?;
+            eval(scope, last)
+        }
+        None => Ok((scope, LispVal::Void())),
+    }
+}
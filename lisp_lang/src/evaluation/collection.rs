@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use crate::parsing::LispVal;
+
+use super::{error::EvalError, list_argument, scope::Scope, EvalResult};
+
+/// The most elements a builtin will build a list of from a count it was
+/// given, so a huge count fails instead of exhausting memory.
+pub const MAX_COLLECTION_SIZE: usize = 1 << 20;
+
+fn number_argument(values: &[LispVal], position: usize, context: &str) -> Result<i64, EvalError> {
+    values
+        .get(position)
+        .unwrap()
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(position, context))
+}
+
+fn flatten(list: Vec<LispVal>, depth: Option<i64>) -> Vec<LispVal> {
+    list.into_iter()
+        .flat_map(|value| match value {
+            LispVal::List(inner) if depth.is_none_or(|depth| depth > 0) => {
+                flatten(inner, depth.map(|depth| depth - 1))
+            }
+            value => vec![value],
+        })
+        .collect()
+}
+
+/// `(flatten list)` splices nested lists at any depth into one list;
+/// `(flatten list depth)` only splices `depth` levels, so `1` removes a
+/// single level and `0` (or less) leaves the list as it is.
+pub fn eval_flatten(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let list = list_argument(values, 0, &scope.context)?;
+    let depth = match values.get(1) {
+        Some(_) => Some(number_argument(values, 1, &scope.context)?),
+        None => None,
+    };
+
+    Ok((scope, flatten(list, depth).into()))
+}
+
+/// `(distinct list)` drops repeated values, keeping the first occurrence of
+/// each in its original position.
+pub fn eval_distinct(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let list = list_argument(values, 0, &scope.context)?;
+    let mut seen = HashSet::new();
+    let distinct: LispVal = list.into_iter().filter(|value| seen.insert(value.clone())).collect();
+
+    Ok((scope, distinct))
+}
+
+/// `(interleave a b)` alternates the elements of both lists, starting with
+/// `a` and stopping when the shorter one runs out.
+pub fn eval_interleave(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let left = list_argument(values, 0, &scope.context)?;
+    let right = list_argument(values, 1, &scope.context)?;
+    let interleaved: LispVal = left.into_iter().zip(right).flat_map(|(a, b)| [a, b]).collect();
+
+    Ok((scope, interleaved))
+}
+
+/// `(interpose separator list)` puts `separator` between every two
+/// elements. Lists with fewer than two elements are returned unchanged.
+pub fn eval_interpose(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let separator = values.first().unwrap();
+    let list = list_argument(values, 1, &scope.context)?;
+
+    let mut interposed = Vec::with_capacity(list.len() * 2);
+    for (i, value) in list.into_iter().enumerate() {
+        if i > 0 {
+            interposed.push(separator.clone());
+        }
+        interposed.push(value);
+    }
+
+    Ok((scope, interposed.into()))
+}
+
+/// `(repeat n value)` is a list of `n` copies of `value`, empty when `n` is
+/// zero or negative. `n` can be at most [`MAX_COLLECTION_SIZE`].
+pub fn eval_repeat(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let count = number_argument(values, 0, &scope.context)?;
+    let value = values.get(1).unwrap();
+    let count = usize::try_from(count).unwrap_or(0);
+    if count > MAX_COLLECTION_SIZE {
+        return Err(EvalError::CollectionTooLarge {
+            name: scope.context.to_string(),
+            size: count,
+            limit: MAX_COLLECTION_SIZE,
+        });
+    }
+    let repeated = vec![value.clone(); count];
+
+    Ok((scope, repeated.into()))
+}
+
+/// `(cons x tail)` puts `x` in front of `tail`: a list when `tail` is a list
+/// or `nil`, and the pair `(x . tail)` otherwise.
+pub fn eval_cons(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let head = values.first().unwrap().clone();
+    let tail = values.get(1).unwrap().clone();
+
+    Ok((scope, LispVal::cons(head, tail)))
+}
+
+/// `(car cell)` is the first element of a list or pair, and `nil` for `()`
+/// and `nil`.
+pub fn eval_car(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let head = match values.first().unwrap() {
+        LispVal::Pair(head, _) => head.as_ref().clone(),
+        LispVal::Nil => LispVal::Nil,
+        _ => list_argument(values, 0, &scope.context)?.first().cloned().unwrap_or(LispVal::Nil),
+    };
+
+    Ok((scope, head))
+}
+
+/// `(cdr cell)` is everything after the first element: the rest of a list,
+/// or the tail of a pair. It is `nil` for `()` and `nil`.
+pub fn eval_cdr(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let tail = match values.first().unwrap() {
+        LispVal::Pair(_, tail) => tail.as_ref().clone(),
+        LispVal::Nil => LispVal::Nil,
+        _ => match list_argument(values, 0, &scope.context)?.split_first() {
+            Some((_, rest)) => rest.to_vec().into(),
+            None => LispVal::Nil,
+        },
+    };
+
+    Ok((scope, tail))
+}
@@ -0,0 +1,94 @@
+use crate::parsing::{LispType, LispVal};
+
+use super::{error::EvalError, scope::Scope, EvalFn, EvalResult};
+
+/// The real and imaginary parts of a complex number.
+pub type Complex = (f64, f64);
+
+pub fn add((a, b): Complex, (c, d): Complex) -> Complex {
+    (a + c, b + d)
+}
+
+pub fn sub((a, b): Complex, (c, d): Complex) -> Complex {
+    (a - c, b - d)
+}
+
+pub fn mul((a, b): Complex, (c, d): Complex) -> Complex {
+    (a * c - b * d, a * d + b * c)
+}
+
+/// Like float division, dividing by zero gives infinities and NaN.
+pub fn div((a, b): Complex, (c, d): Complex) -> Complex {
+    let denominator = c * c + d * d;
+    ((a * c + b * d) / denominator, (b * c - a * d) / denominator)
+}
+
+/// The argument at `position` as a complex number. Integers and floats are
+/// promoted to complex numbers with no imaginary part.
+fn complex_argument(values: &[LispVal], position: usize, context: &str) -> Result<Complex, EvalError> {
+    match values.get(position).unwrap() {
+        LispVal::Complex(re, im) => Ok((*re, *im)),
+        LispVal::Number(n) => Ok((*n as f64, 0.0)),
+        LispVal::Float(x) => Ok((*x, 0.0)),
+        value => Err(EvalError::InvalidArgumentType {
+            name: context.to_string(),
+            expected: LispType::Complex,
+            got: value.to_type(),
+            position,
+        }),
+    }
+}
+
+/// Extends an arithmetic builtin to complex numbers: when either operand is
+/// one, both are promoted and `operation` gives the result. Otherwise
+/// `math` computes it as usual.
+pub(super) fn promoting<C>(operation: C, math: impl EvalFn) -> impl EvalFn
+where
+    C: Fn(Complex, Complex) -> Complex,
+{
+    move |scope: Scope, values: &[LispVal]| {
+        if !values.iter().take(2).any(|value| matches!(value, LispVal::Complex(..))) {
+            return math(scope, values);
+        }
+
+        let a = complex_argument(values, 0, &scope.context)?;
+        let b = complex_argument(values, 1, &scope.context)?;
+        let (re, im) = operation(a, b);
+
+        Ok((scope, LispVal::Complex(re, im)))
+    }
+}
+
+/// `(complex 1 2)` is the complex number with real part `1` and imaginary
+/// part `2`, both as floats.
+pub fn eval_complex(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let part = |position: usize| {
+        f64::try_from(values.get(position).unwrap().clone()).map_err(EvalError::from_arg(position, &scope.context))
+    };
+    let value = LispVal::Complex(part(0)?, part(1)?);
+
+    Ok((scope, value))
+}
+
+/// `(real z)` is the real part of `z`, which is the number itself for
+/// integers and floats.
+pub fn eval_real(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let (re, _) = complex_argument(values, 0, &scope.context)?;
+
+    Ok((scope, LispVal::Float(re)))
+}
+
+/// `(imag z)` is the imaginary part of `z`, `0.0` for integers and floats.
+pub fn eval_imag(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let (_, im) = complex_argument(values, 0, &scope.context)?;
+
+    Ok((scope, LispVal::Float(im)))
+}
+
+/// `(magnitude z)` is the distance from `z` to zero, the absolute value for
+/// integers and floats.
+pub fn eval_magnitude(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let (re, im) = complex_argument(values, 0, &scope.context)?;
+
+    Ok((scope, LispVal::Float(re.hypot(im))))
+}
@@ -0,0 +1,164 @@
+use crate::parsing::{sorted_entries, LispVal, MapEntries};
+
+use super::{scope::Scope, EvalResult};
+
+/// Where a change happened: list indexes, record field names and map keys,
+/// outermost first.
+type Path = Vec<LispVal>;
+
+fn change(kind: &str, path: &Path, fields: Vec<(&str, LispVal)>) -> LispVal {
+    LispVal::Record {
+        name: kind.to_string(),
+        fields: std::iter::once(("path".to_string(), path.clone().into()))
+            .chain(fields.into_iter().map(|(name, value)| (name.to_string(), value)))
+            .collect(),
+    }
+}
+
+fn added(path: &Path, value: &LispVal) -> LispVal {
+    change("added", path, vec![("value", value.clone())])
+}
+
+fn removed(path: &Path, value: &LispVal) -> LispVal {
+    change("removed", path, vec![("value", value.clone())])
+}
+
+fn at(path: &Path, step: LispVal) -> Path {
+    let mut path = path.clone();
+    path.push(step);
+    path
+}
+
+/// Pairs of indexes of a longest common subsequence of `left` and `right`.
+fn common_subsequence(left: &[LispVal], right: &[LispVal]) -> Vec<(usize, usize)> {
+    let mut lengths = vec![vec![0usize; right.len() + 1]; left.len() + 1];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            lengths[i][j] = if left[i] == right[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j, mut pairs) = (0, 0, Vec::new());
+    while i < left.len() && j < right.len() {
+        if left[i] == right[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Elements kept in both lists are matched first; between them, elements
+/// at the same offset are compared in depth and the rest are additions or
+/// removals.
+fn diff_lists(left: &[LispVal], right: &[LispVal], path: &Path, changes: &mut Vec<LispVal>) {
+    let anchors = common_subsequence(left, right)
+        .into_iter()
+        .chain(std::iter::once((left.len(), right.len())));
+
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in anchors {
+        let (gap_left, gap_right) = (i..next_i, j..next_j);
+        let paired = gap_left.len().min(gap_right.len());
+
+        for (a, b) in gap_left.clone().zip(gap_right.clone()) {
+            diff(&left[a], &right[b], &at(path, LispVal::Number(b as i64)), changes);
+        }
+        for a in gap_left.skip(paired) {
+            changes.push(removed(&at(path, LispVal::Number(a as i64)), &left[a]));
+        }
+        for b in gap_right.skip(paired) {
+            changes.push(added(&at(path, LispVal::Number(b as i64)), &right[b]));
+        }
+
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+}
+
+/// Values under the same key are compared in depth; keys on one side only
+/// are additions or removals. The keys are the steps of the paths.
+fn diff_keyed(
+    left: &[(LispVal, &LispVal)],
+    right: &[(LispVal, &LispVal)],
+    path: &Path,
+    changes: &mut Vec<LispVal>,
+) {
+    fn find<'a>(entries: &[(LispVal, &'a LispVal)], key: &LispVal) -> Option<&'a LispVal> {
+        entries.iter().find(|(other, _)| other == key).map(|(_, value)| *value)
+    }
+
+    for (key, value) in left {
+        let key_path = at(path, key.clone());
+        match find(right, key) {
+            Some(other) => diff(value, other, &key_path, changes),
+            None => changes.push(removed(&key_path, value)),
+        }
+    }
+    for (key, value) in right {
+        if find(left, key).is_none() {
+            changes.push(added(&at(path, key.clone()), value));
+        }
+    }
+}
+
+fn diff_fields(
+    left: &[(String, LispVal)],
+    right: &[(String, LispVal)],
+    path: &Path,
+    changes: &mut Vec<LispVal>,
+) {
+    fn keyed(fields: &[(String, LispVal)]) -> Vec<(LispVal, &LispVal)> {
+        fields.iter().map(|(name, value)| (LispVal::Symbol(name.into()), value)).collect()
+    }
+
+    diff_keyed(&keyed(left), &keyed(right), path, changes)
+}
+
+/// Maps are compared key by key, in the order they're printed in.
+fn diff_maps(left: &MapEntries, right: &MapEntries, path: &Path, changes: &mut Vec<LispVal>) {
+    fn keyed(entries: &MapEntries) -> Vec<(LispVal, &LispVal)> {
+        sorted_entries(entries).into_iter().map(|(key, value)| (key.clone(), value)).collect()
+    }
+
+    diff_keyed(&keyed(left), &keyed(right), path, changes)
+}
+
+fn diff(left: &LispVal, right: &LispVal, path: &Path, changes: &mut Vec<LispVal>) {
+    match (left, right) {
+        _ if left == right => {}
+        (LispVal::List(a), LispVal::List(b)) => diff_lists(a, b, path, changes),
+        (LispVal::Map(a), LispVal::Map(b)) => diff_maps(a, b, path, changes),
+        (LispVal::Unevaluated(a), LispVal::Unevaluated(b)) => diff(a, b, path, changes),
+        (
+            LispVal::Record { name, fields },
+            LispVal::Record {
+                name: other_name,
+                fields: other_fields,
+            },
+        ) if name == other_name => diff_fields(fields, other_fields, path, changes),
+        _ => changes.push(change(
+            "changed",
+            path,
+            vec![("from", left.clone()), ("to", right.clone())],
+        )),
+    }
+}
+
+/// `(diff a b)` lists the changes turning `a` into `b` as `added`, `removed`
+/// and `changed` records, each with the `path` of list indexes, record
+/// fields and map keys leading to it. Equal values have no changes.
+pub fn eval_diff(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let mut changes = Vec::new();
+    diff(values.first().unwrap(), values.get(1).unwrap(), &Vec::new(), &mut changes);
+
+    Ok((scope, changes.into()))
+}
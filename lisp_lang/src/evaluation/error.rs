@@ -1,4 +1,10 @@
-use crate::parsing::{error::LispValUnwrapError, LispType, LispVal};
+use crate::{
+    catalog::{self, Language},
+    error_code::ErrorCode,
+    parsing::{error::LispValUnwrapError, LispType, LispVal},
+};
+
+use super::scope::Scope;
 
 #[derive(Debug)]
 pub enum EvalError {
@@ -16,8 +22,103 @@ pub enum EvalError {
         values: Vec<LispVal>,
     },
     UnknownIdentifier(String),
+    InvalidRecord {
+        expected: String,
+        got: LispVal,
+    },
+    NoMethod {
+        name: String,
+        dispatch_value: LispVal,
+    },
+    ConstantRedefinition(String),
+    Interrupted,
+    InvalidMatrix {
+        reason: String,
+    },
+    IncomparableValues {
+        left: LispType,
+        right: LispType,
+    },
+    EmptyList {
+        name: String,
+    },
+    CyclicGraph,
+    InvalidModule {
+        name: String,
+        reason: String,
+    },
+    HostStore {
+        key: String,
+        reason: String,
+    },
+    TaskBudget {
+        task: String,
+        budget: usize,
+    },
+    InvalidBinding {
+        name: String,
+        binding: LispVal,
+    },
+    NoMatch {
+        name: String,
+        value: LispVal,
+    },
+    DivisionByZero {
+        name: String,
+    },
+    CollectionTooLarge {
+        name: String,
+        size: usize,
+        limit: usize,
+    },
+    /// Raised by `recur` to start its `loop!` over with `arguments`, and
+    /// only reported when there's no `loop!` around it.
+    Recur {
+        arguments: Vec<LispVal>,
+    },
+    /// A `recur` whose value would still be used, like an argument or a
+    /// call made from a function, so it can't start its `loop!` over.
+    MisplacedRecur,
+    RecurArity {
+        expected: usize,
+        got: usize,
+    },
+    /// Two lists that go together, like the fields and values of a record,
+    /// have different lengths.
+    ArgumentCount {
+        name: String,
+        expected: usize,
+        got: usize,
+    },
 }
 
+/// A piece of an error message. Everything but text is what the error is
+/// about, and is shown quoted so hosts can highlight it without parsing the
+/// message back.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessagePart {
+    Text(String),
+    Name(String),
+    Type(LispType),
+    Value(LispVal),
+}
+
+impl std::fmt::Display for MessagePart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MessagePart::Text(text) => write!(f, "{text}"),
+            MessagePart::Name(name) => write!(f, "`{name}`"),
+            MessagePart::Type(lisp_type) => write!(f, "`{lisp_type}`"),
+            MessagePart::Value(value) => write!(f, "`{value}`"),
+        }
+    }
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.message().iter().try_for_each(|part| write!(f, "{part}"))
+    }
+}
 
 impl std::error::Error for EvalError {}
 
@@ -30,4 +131,159 @@ impl EvalError {
             position,
         }
     }
+
+    /// Names the calls leading to the builtin that failed, instead of only
+    /// the builtin itself.
+    pub fn traced(self, scope: &Scope) -> Self {
+        let trace = |name: String| if name == scope.context { scope.trace() } else { name };
+
+        match self {
+            EvalError::InvalidArgumentType { name, expected, got, position } => EvalError::InvalidArgumentType {
+                name: trace(name),
+                expected,
+                got,
+                position,
+            },
+            EvalError::EmptyList { name } => EvalError::EmptyList { name: trace(name) },
+            EvalError::InvalidBinding { name, binding } => EvalError::InvalidBinding { name: trace(name), binding },
+            EvalError::NoMatch { name, value } => EvalError::NoMatch { name: trace(name), value },
+            EvalError::DivisionByZero { name } => EvalError::DivisionByZero { name: trace(name) },
+            EvalError::CollectionTooLarge { name, size, limit } => {
+                EvalError::CollectionTooLarge { name: trace(name), size, limit }
+            }
+            EvalError::ArgumentCount { name, expected, got } => {
+                EvalError::ArgumentCount { name: trace(name), expected, got }
+            }
+            e => e,
+        }
+    }
+
+    /// Marks a `recur` evaluated where it isn't the last thing its `loop!`
+    /// does, so no `loop!` further out takes it for its own.
+    pub fn outside_tail(self) -> Self {
+        match self {
+            EvalError::Recur { .. } => EvalError::MisplacedRecur,
+            e => e,
+        }
+    }
+
+    /// The parts of the message, each under the name its template uses.
+    fn fields(&self) -> Vec<(&'static str, MessagePart)> {
+        use MessagePart::{Name, Text, Type, Value};
+
+        match self {
+            EvalError::InvalidArgumentType { name, expected, got, position } => vec![
+                ("name", Name(name.clone())),
+                ("position", Value(LispVal::Number(*position as i64))),
+                ("expected", Type(*expected)),
+                ("got", Type(*got)),
+            ],
+            EvalError::InvalidConcatenation { left, right } | EvalError::IncomparableValues { left, right } => {
+                vec![("left", Type(*left)), ("right", Type(*right))]
+            }
+            EvalError::InvalidFunctionCall { values } => {
+                let head = values.first().unwrap();
+                vec![
+                    ("head", Value(head.clone())),
+                    ("type", Type(head.to_type())),
+                    ("list", Value(LispVal::Unevaluated(Box::new(LispVal::List(values.clone()))))),
+                ]
+            }
+            EvalError::UnknownIdentifier(name) | EvalError::ConstantRedefinition(name) => {
+                vec![("name", Name(name.clone()))]
+            }
+            EvalError::InvalidRecord { expected, got } => {
+                vec![("expected", Name(expected.clone())), ("got", Value(got.clone()))]
+            }
+            EvalError::NoMethod { name, dispatch_value } => {
+                vec![("name", Name(name.clone())), ("value", Value(dispatch_value.clone()))]
+            }
+            EvalError::Interrupted | EvalError::CyclicGraph => vec![],
+            EvalError::InvalidMatrix { reason } => vec![("reason", Text(reason.clone()))],
+            EvalError::EmptyList { name } => vec![("name", Name(name.clone()))],
+            EvalError::InvalidModule { name, reason } => {
+                vec![("name", Name(name.clone())), ("reason", Text(reason.clone()))]
+            }
+            EvalError::HostStore { key, reason } => {
+                vec![("key", Name(key.clone())), ("reason", Text(reason.clone()))]
+            }
+            EvalError::TaskBudget { task, budget } => {
+                vec![("task", Name(task.clone())), ("budget", Text(budget.to_string()))]
+            }
+            EvalError::InvalidBinding { name, binding } => {
+                vec![("name", Name(name.clone())), ("binding", Value(binding.clone()))]
+            }
+            EvalError::NoMatch { name, value } => vec![("name", Name(name.clone())), ("value", Value(value.clone()))],
+            EvalError::DivisionByZero { name } => vec![("name", Name(name.clone()))],
+            EvalError::CollectionTooLarge { name, size, limit } => vec![
+                ("name", Name(name.clone())),
+                ("size", Text(size.to_string())),
+                ("limit", Text(limit.to_string())),
+            ],
+            EvalError::Recur { .. } | EvalError::MisplacedRecur => {
+                vec![("name", Name("recur".to_string())), ("loop", Name("loop!".to_string()))]
+            }
+            EvalError::RecurArity { expected, got } => vec![
+                ("name", Name("recur".to_string())),
+                ("loop", Name("loop!".to_string())),
+                ("expected", Text(expected.to_string())),
+                ("got", Text(got.to_string())),
+            ],
+            EvalError::ArgumentCount { name, expected, got } => vec![
+                ("name", Name(name.clone())),
+                ("expected", Text(expected.to_string())),
+                ("got", Text(got.to_string())),
+            ],
+        }
+    }
+
+    pub fn message(&self) -> Vec<MessagePart> {
+        self.message_in(Language::English)
+    }
+
+    /// The message in `language`, from its template in the catalog.
+    pub fn message_in(&self, language: Language) -> Vec<MessagePart> {
+        let template = catalog::template(self.code(), language).unwrap_or_default();
+        let fields = self.fields();
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while let Some((text, after)) = rest.split_once('{') {
+            let (name, after) = after.split_once('}').unwrap_or((after, ""));
+            parts.push(MessagePart::Text(text.to_string()));
+            parts.extend(fields.iter().find(|(field, _)| *field == name).map(|(_, part)| part.clone()));
+            rest = after;
+        }
+        parts.push(MessagePart::Text(rest.to_string()));
+
+        parts.retain(|part| !matches!(part, MessagePart::Text(text) if text.is_empty()));
+        parts
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            EvalError::InvalidArgumentType { .. } => ErrorCode::InvalidArgumentType,
+            EvalError::InvalidConcatenation { .. } => ErrorCode::InvalidConcatenation,
+            EvalError::InvalidFunctionCall { .. } => ErrorCode::InvalidFunctionCall,
+            EvalError::UnknownIdentifier(_) => ErrorCode::UnknownIdentifier,
+            EvalError::InvalidRecord { .. } => ErrorCode::InvalidRecord,
+            EvalError::NoMethod { .. } => ErrorCode::NoMethod,
+            EvalError::ConstantRedefinition(_) => ErrorCode::ConstantRedefinition,
+            EvalError::Interrupted => ErrorCode::Interrupted,
+            EvalError::InvalidMatrix { .. } => ErrorCode::InvalidMatrix,
+            EvalError::IncomparableValues { .. } => ErrorCode::IncomparableValues,
+            EvalError::EmptyList { .. } => ErrorCode::EmptyList,
+            EvalError::CyclicGraph => ErrorCode::CyclicGraph,
+            EvalError::InvalidModule { .. } => ErrorCode::InvalidModule,
+            EvalError::HostStore { .. } => ErrorCode::HostStore,
+            EvalError::TaskBudget { .. } => ErrorCode::TaskBudget,
+            EvalError::InvalidBinding { .. } => ErrorCode::InvalidBinding,
+            EvalError::NoMatch { .. } => ErrorCode::NoMatch,
+            EvalError::DivisionByZero { .. } => ErrorCode::DivisionByZero,
+            EvalError::CollectionTooLarge { .. } => ErrorCode::CollectionTooLarge,
+            EvalError::Recur { .. } | EvalError::MisplacedRecur => ErrorCode::MisplacedRecur,
+            EvalError::RecurArity { .. } => ErrorCode::RecurArity,
+            EvalError::ArgumentCount { .. } => ErrorCode::ArgumentCount,
+        }
+    }
 }
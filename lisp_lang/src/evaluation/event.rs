@@ -0,0 +1,32 @@
+use crate::parsing::{LispType, LispVal};
+
+use super::{error::EvalError, runtime::{intercept, Effect}, scope::Scope, EvalResult};
+
+/// `(on-event! "name" handler)` calls `handler` with the payload of every
+/// `name` event the host emits from then on. Handlers run when the host
+/// drives them with `Interpreter::run_pending`, not when the event is
+/// emitted.
+pub fn eval_on_event(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let event: String = values
+        .first()
+        .unwrap()
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(0, &scope.context))?;
+
+    let handler = values.get(1).unwrap();
+    if !matches!(handler, LispVal::Function { .. } | LispVal::MultiMethod { .. } | LispVal::Symbol(_)) {
+        return Err(EvalError::InvalidArgumentType {
+            name: scope.context.to_string(),
+            expected: LispType::Function,
+            got: handler.to_type(),
+            position: 1,
+        });
+    }
+
+    if !intercept(Effect::Subscribe(event.clone())) {
+        scope.runtime.subscribe(event, handler.clone());
+    }
+
+    Ok((scope, LispVal::Void()))
+}
@@ -0,0 +1,138 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::parsing::{LispType, LispVal};
+
+use super::{error::EvalError, list_argument, scope::Scope, EvalResult};
+
+/// Graphs are adjacency lists, `((a (b c)) (b (c)) (c ()))`, holding every
+/// node in the order it first appeared with the nodes its edges lead to.
+type Adjacency = Vec<(LispVal, Vec<LispVal>)>;
+
+fn invalid_graph(context: &str, got: LispType) -> EvalError {
+    EvalError::InvalidArgumentType {
+        name: context.to_string(),
+        expected: LispType::List,
+        got,
+        position: 0,
+    }
+}
+
+fn graph_argument(values: &[LispVal], context: &str) -> Result<Adjacency, EvalError> {
+    list_argument(values, 0, context)?
+        .into_iter()
+        .map(|entry| match entry {
+            LispVal::List(pair) => match pair.as_slice() {
+                [node, LispVal::List(neighbors)] => Ok((node.clone(), neighbors.clone())),
+                _ => Err(invalid_graph(context, LispType::List)),
+            },
+            other => Err(invalid_graph(context, other.to_type())),
+        })
+        .collect()
+}
+
+fn to_value(graph: Adjacency) -> LispVal {
+    graph
+        .into_iter()
+        .map(|(node, neighbors)| LispVal::List(vec![node, neighbors.into()]))
+        .collect()
+}
+
+fn neighbors<'a>(graph: &'a Adjacency, node: &LispVal) -> &'a [LispVal] {
+    graph
+        .iter()
+        .find(|(candidate, _)| candidate == node)
+        .map_or(&[], |(_, neighbors)| neighbors.as_slice())
+}
+
+/// Index of `node` in `graph`, adding it without edges if it's new.
+fn add_node(graph: &mut Adjacency, node: &LispVal) -> usize {
+    match graph.iter().position(|(candidate, _)| candidate == node) {
+        Some(index) => index,
+        None => {
+            graph.push((node.clone(), Vec::new()));
+            graph.len() - 1
+        }
+    }
+}
+
+/// `(graph edges)` builds a directed graph from `(from to)` pairs.
+pub fn eval_graph(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let edges = list_argument(values, 0, &scope.context)?;
+    let mut graph: Adjacency = Vec::new();
+
+    for edge in edges {
+        let (from, to) = match &edge {
+            LispVal::List(pair) if pair.len() == 2 => (&pair[0], &pair[1]),
+            other => return Err(invalid_graph(&scope.context, other.to_type())),
+        };
+        let index = add_node(&mut graph, from);
+        add_node(&mut graph, to);
+        graph[index].1.push(to.clone());
+    }
+
+    Ok((scope, to_value(graph)))
+}
+
+/// `(neighbors graph node)` lists the nodes `node` has edges to.
+pub fn eval_neighbors(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let graph = graph_argument(values, &scope.context)?;
+    let neighbors: LispVal = neighbors(&graph, values.get(1).unwrap()).to_vec().into();
+
+    Ok((scope, neighbors))
+}
+
+/// `(bfs graph start)` lists the nodes reachable from `start`, breadth
+/// first, starting with `start` itself.
+pub fn eval_bfs(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let graph = graph_argument(values, &scope.context)?;
+    let start = values.get(1).unwrap().clone();
+
+    let mut visited = vec![start.clone()];
+    let mut seen = HashSet::from([start.clone()]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for next in neighbors(&graph, &node) {
+            if seen.insert(next.clone()) {
+                visited.push(next.clone());
+                queue.push_back(next.clone());
+            }
+        }
+    }
+
+    Ok((scope, visited.into()))
+}
+
+/// `(topo-sort graph)` orders the nodes so every edge goes from an earlier
+/// node to a later one. Nodes without an order between them keep their
+/// order in the graph.
+pub fn eval_topological_sort(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let graph = graph_argument(values, &scope.context)?;
+
+    let mut incoming: HashMap<&LispVal, usize> = graph.iter().map(|(node, _)| (node, 0)).collect();
+    for next in graph.iter().flat_map(|(_, neighbors)| neighbors) {
+        *incoming.entry(next).or_default() += 1;
+    }
+
+    let mut ready: VecDeque<&LispVal> = graph
+        .iter()
+        .map(|(node, _)| node)
+        .filter(|node| incoming[node] == 0)
+        .collect();
+    let mut sorted = Vec::new();
+    while let Some(node) = ready.pop_front() {
+        sorted.push(node.clone());
+        for next in neighbors(&graph, node) {
+            let count = incoming.get_mut(next).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                ready.push_back(next);
+            }
+        }
+    }
+
+    if sorted.len() < incoming.len() {
+        return Err(EvalError::CyclicGraph);
+    }
+
+    Ok((scope, sorted.into()))
+}
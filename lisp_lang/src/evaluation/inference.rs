@@ -0,0 +1,193 @@
+use crate::parsing::{LispType, LispVal};
+
+use super::{
+    builtin_arity, deprecated_builtin,
+    scope::Scope,
+    special_form::{special_form, Arguments},
+};
+
+/// The result type of an expression, as far as it can be known without
+/// evaluating it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InferredType {
+    Any,
+    Of(LispType),
+    ListOf(Box<InferredType>),
+    FunctionReturning(Box<InferredType>),
+}
+
+impl std::fmt::Display for InferredType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InferredType::Any => write!(f, "any"),
+            InferredType::Of(t) => write!(f, "{}", t),
+            InferredType::ListOf(t) => write!(f, "list of {}", t),
+            InferredType::FunctionReturning(t) => write!(f, "function returning {}", t),
+        }
+    }
+}
+
+fn unify(a: InferredType, b: InferredType) -> InferredType {
+    if a == b {
+        a
+    } else {
+        InferredType::Any
+    }
+}
+
+fn unify_all<I: IntoIterator<Item = InferredType>>(types: I) -> InferredType {
+    types.into_iter().reduce(unify).unwrap_or(InferredType::Any)
+}
+
+fn element_type(list: InferredType) -> InferredType {
+    match list {
+        InferredType::ListOf(element) => *element,
+        _ => InferredType::Any,
+    }
+}
+
+/// Type of an already evaluated value.
+fn value_type(value: &LispVal) -> InferredType {
+    match value {
+        LispVal::List(values) | LispVal::Vector(values) => {
+            InferredType::ListOf(Box::new(unify_all(values.iter().map(value_type))))
+        }
+        LispVal::Unevaluated(value) => value_type(value),
+        value => InferredType::Of(value.to_type()),
+    }
+}
+
+/// What calling `callable` returns, for arguments like the `(+ 1)` in
+/// `(map (+ 1) '(1 2))`.
+fn return_type(scope: &Scope, callable: &LispVal) -> InferredType {
+    match infer(scope, callable) {
+        InferredType::FunctionReturning(returns) => *returns,
+        _ => match callable {
+            LispVal::Symbol(name) => builtin_return_type(scope, name, &[]),
+            LispVal::Unevaluated(value) => return_type(scope, value),
+            _ => InferredType::Any,
+        },
+    }
+}
+
+/// Result type of a fully applied builtin called with `args`.
+fn builtin_return_type(scope: &Scope, name: &str, args: &[LispVal]) -> InferredType {
+    use InferredType::*;
+
+    let arg = |i: usize| args.get(i).map_or(Any, |arg| infer(scope, arg));
+
+    // Forms like `if!` and `do!` return the value of one of their tail arguments
+    if let Some(form) = special_form(name).filter(|form| form.tail != Arguments::None) {
+        let tail = (0..args.len()).filter(|&i| form.tail.includes(i, args.len()));
+        return unify_all(tail.map(arg));
+    }
+
+    match name {
+        "+" | "-" | "*" | "/" | "%" | "add" | "sub" | "mul" | "div" | "mod" | "max" | "min" => {
+            // Mixed with a complex number or a float, integers are promoted
+            if [arg(0), arg(1)].contains(&Of(LispType::Complex)) {
+                Of(LispType::Complex)
+            } else if [arg(0), arg(1)].contains(&Of(LispType::Float)) {
+                Of(LispType::Float)
+            } else {
+                Of(LispType::Number)
+            }
+        }
+        "len" | "mat-get" => Of(LispType::Number),
+        "complex" => Of(LispType::Complex),
+        "real" | "imag" | "magnitude" => Of(LispType::Float),
+        "matrix" | "transpose" | "identity" | "mat-mul" => {
+            ListOf(Box::new(ListOf(Box::new(Of(LispType::Number)))))
+        }
+        "<" | ">" | "<=" | ">=" | "=" | "lt" | "gt" | "lte" | "gte" | "eq" | "and" | "or"
+        | "not" | "is_record" | "is_list" | "nil?" | "empty?" | "contains?" => Of(LispType::Boolean),
+        "to_string" | "lowercase" | "uppercase" => Of(LispType::String),
+        "diff" => ListOf(Box::new(Of(LispType::Record))),
+        "source" => Of(LispType::List),
+        "graph" => ListOf(Box::new(Of(LispType::List))),
+        "neighbors" | "bfs" | "topo-sort" => Of(LispType::List),
+        "sort" | "distinct" | "sorted-map-put" => arg(0),
+        "heap" | "heap-push" | "heap-pop" => Of(LispType::Heap),
+        "deque" | "push-front" | "push-back" | "pop-front" | "pop-back" => Of(LispType::Deque),
+        "assoc" | "dissoc" | "merge" => Of(LispType::Map),
+        "set" | "union" | "intersection" | "difference" => Of(LispType::Set),
+        "keys" | "vals" => Of(LispType::List),
+        "bsearch" | "tick" => Of(LispType::Number),
+        "sorted-insert" => ListOf(Box::new(unify(element_type(arg(0)), arg(1)))),
+        "interpose" => ListOf(Box::new(unify(arg(0), element_type(arg(1))))),
+        "interleave" => ListOf(Box::new(unify(element_type(arg(0)), element_type(arg(1))))),
+        "repeat" => ListOf(Box::new(arg(1))),
+        "sort-by" => arg(1),
+        "max-by" | "min-by" => element_type(arg(1)),
+        "record_name" => Of(LispType::Symbol),
+        "make_record" | "stats" | "describe" => Of(LispType::Record),
+        "fn!" => FunctionReturning(Box::new(args.get(1).map_or(Any, |body| infer(scope, body)))),
+        "print" | "pprint" | "def!" | "const!" | "defn!" | "defrecord!" | "defmulti!" | "defmethod!"
+        | "print_scope" | "clear_scope" | "load" | "require" | "store-set!" | "on-event!"
+        | "deftask!" => Of(LispType::Void),
+        "store-keys" => ListOf(Box::new(Of(LispType::String))),
+        "list" => ListOf(Box::new(unify_all(args.iter().map(|arg| infer(scope, arg))))),
+        "map" => ListOf(Box::new(args.first().map_or(Any, |f| return_type(scope, f)))),
+        "fold" => args.first().map_or(Any, |f| return_type(scope, f)),
+        "head" => element_type(arg(0)),
+        "tail" => arg(0),
+        "push" => ListOf(Box::new(unify(element_type(arg(0)), arg(1)))),
+        "concat" => match (arg(0), arg(1)) {
+            (ListOf(a), ListOf(b)) => ListOf(Box::new(unify(*a, *b))),
+            _ => Of(LispType::List),
+        },
+        "debug" => arg(0),
+        "eval" => match args.first() {
+            Some(LispVal::Unevaluated(expr)) => infer(scope, expr),
+            _ => Any,
+        },
+        _ => Any,
+    }
+}
+
+/// Infers the type `expr` would evaluate to in `scope`, without evaluating
+/// it. Unknown calls and user functions infer as `any`.
+pub fn infer(scope: &Scope, expr: &LispVal) -> InferredType {
+    match expr {
+        LispVal::Symbol(name) => scope.get(name).map_or(InferredType::Any, value_type),
+        LispVal::List(values) => match values.split_first() {
+            None => InferredType::ListOf(Box::new(InferredType::Any)),
+            Some((LispVal::Symbol(name), args)) => {
+                let name = deprecated_builtin(name).unwrap_or(name);
+                match builtin_arity(name) {
+                    Some(arity) if args.len() < arity => InferredType::FunctionReturning(Box::new(
+                        builtin_return_type(scope, name, &[]),
+                    )),
+                    Some(_) => builtin_return_type(scope, name, args),
+                    None => InferredType::Any,
+                }
+            }
+            Some(_) => InferredType::Any,
+        },
+        LispVal::Vector(values) => InferredType::ListOf(Box::new(unify_all(values.iter().map(|v| infer(scope, v))))),
+        value => value_type(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{evaluation::scope::Scope, parse_it};
+
+    use super::infer;
+
+    fn infer_it(input: &str) -> String {
+        infer(&Scope::default(), &parse_it!(input)).to_string()
+    }
+
+    #[test]
+    fn test_infer() {
+        assert_eq!(infer_it("(map (+ 1) '(1 2))"), "list of number");
+        assert_eq!(infer_it("(if! (> 1 2) \"a\" \"b\")"), "string");
+        assert_eq!(infer_it("(do! (def! x 1) \"a\")"), "string");
+        assert_eq!(infer_it("(+ 2)"), "function returning number");
+        assert_eq!(infer_it("(head '(true false))"), "boolean");
+        assert_eq!(infer_it("(list 1 \"a\")"), "list of any");
+        assert_eq!(infer_it("[1 (+ 1 2)]"), "list of number");
+        assert_eq!(infer_it("(unknown 1)"), "any");
+    }
+}
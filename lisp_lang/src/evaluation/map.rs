@@ -0,0 +1,83 @@
+use crate::parsing::{sorted_entries, LispType, LispVal, MapEntries};
+
+use super::{error::EvalError, eval, scope::Scope, EvalResult};
+
+fn map_argument(values: &[LispVal], position: usize, context: &str) -> Result<MapEntries, EvalError> {
+    match values.get(position).unwrap() {
+        LispVal::Map(entries) => Ok(entries.clone()),
+        value => Err(EvalError::InvalidArgumentType {
+            name: context.to_string(),
+            expected: LispType::Map,
+            got: value.to_type(),
+            position,
+        }),
+    }
+}
+
+/// A `{key value ...}` literal evaluates its keys and values, in the order
+/// the map is printed in.
+pub fn eval_map_literal(scope: Scope, entries: &MapEntries) -> EvalResult {
+    let (scope, evaluated) = sorted_entries(entries).into_iter().try_fold(
+        (scope, MapEntries::new()),
+        |(scope, evaluated), (key, value)| {
+            let (scope, key) = eval(scope, key).map_err(EvalError::outside_tail)?;
+            let (scope, value) = eval(scope, value).map_err(EvalError::outside_tail)?;
+            Ok::<_, EvalError>((scope, evaluated.update(key, value)))
+        },
+    )?;
+
+    Ok((scope, LispVal::Map(evaluated)))
+}
+
+/// `(get map key)` is the value under `key`, or `void` when there is none;
+/// `(get map key default)` is `default` instead.
+pub fn eval_get(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let entries = map_argument(values, 0, &scope.context)?;
+    let value = entries
+        .get(values.get(1).unwrap())
+        .or(values.get(2))
+        .cloned()
+        .unwrap_or(LispVal::Void());
+
+    Ok((scope, value))
+}
+
+/// `(assoc map key value)` is `map` with `key` set to `value`.
+pub fn eval_assoc(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let entries = map_argument(values, 0, &scope.context)?;
+    let entries = entries.update(values.get(1).unwrap().clone(), values.get(2).unwrap().clone());
+
+    Ok((scope, LispVal::Map(entries)))
+}
+
+/// `(dissoc map key)` is `map` without `key`.
+pub fn eval_dissoc(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let entries = map_argument(values, 0, &scope.context)?;
+
+    Ok((scope, LispVal::Map(entries.without(values.get(1).unwrap()))))
+}
+
+/// `(keys map)` lists the keys, in the order the map is printed in.
+pub fn eval_keys(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let entries = map_argument(values, 0, &scope.context)?;
+    let keys: LispVal = sorted_entries(&entries).into_iter().map(|(key, _)| key.clone()).collect();
+
+    Ok((scope, keys))
+}
+
+/// `(vals map)` lists the values, in the same order as `keys`.
+pub fn eval_vals(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let entries = map_argument(values, 0, &scope.context)?;
+    let vals: LispVal = sorted_entries(&entries).into_iter().map(|(_, value)| value.clone()).collect();
+
+    Ok((scope, vals))
+}
+
+/// `(merge a b)` has the entries of both maps; where both have a key, the
+/// value from `b` wins.
+pub fn eval_merge(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let left = map_argument(values, 0, &scope.context)?;
+    let right = map_argument(values, 1, &scope.context)?;
+
+    Ok((scope, LispVal::Map(right.union(left))))
+}
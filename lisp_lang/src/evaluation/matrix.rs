@@ -0,0 +1,161 @@
+use crate::parsing::LispVal;
+
+use super::{error::EvalError, scope::Scope, warning::Warning, EvalResult};
+
+/// A matrix of integers and floats.
+type Matrix = Vec<Vec<LispVal>>;
+
+fn invalid(reason: String) -> EvalError {
+    EvalError::InvalidMatrix { reason }
+}
+
+/// Reads a rectangular list of lists of integers and floats.
+fn to_matrix(value: &LispVal, position: usize, context: &str) -> Result<Matrix, EvalError> {
+    let rows: Vec<LispVal> = value
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(position, context))?;
+
+    let matrix = rows
+        .into_iter()
+        .map(|row| {
+            Vec::<LispVal>::try_from(row)
+                .map_err(EvalError::from_arg(position, context))?
+                .into_iter()
+                .map(|n| match n {
+                    LispVal::Float(_) => Ok(n),
+                    n => i64::try_from(n).map(LispVal::Number).map_err(EvalError::from_arg(position, context)),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Matrix, _>>()?;
+
+    match matrix.first() {
+        Some(first) if matrix.iter().any(|row| row.len() != first.len()) => Err(invalid(
+            "all rows of a matrix must have the same length".to_string(),
+        )),
+        _ => Ok(matrix),
+    }
+}
+
+fn from_matrix(matrix: Matrix) -> LispVal {
+    matrix.into_iter().map(LispVal::from).collect()
+}
+
+/// `sum + a * b`. Like the arithmetic builtins, integers are promoted when
+/// any of them is a float, and integer overflow wraps around, setting
+/// `overflowed`.
+fn multiply_add(sum: &LispVal, a: &LispVal, b: &LispVal, overflowed: &mut bool) -> LispVal {
+    match (sum, a, b) {
+        (LispVal::Number(sum), LispVal::Number(a), LispVal::Number(b)) => {
+            let (term, term_overflowed) = a.overflowing_mul(*b);
+            let (sum, sum_overflowed) = sum.overflowing_add(term);
+            *overflowed |= term_overflowed || sum_overflowed;
+            LispVal::Number(sum)
+        }
+        _ => {
+            let float = |value: &LispVal| f64::try_from(value.clone()).unwrap();
+            LispVal::Float(float(sum) + float(a) * float(b))
+        }
+    }
+}
+
+fn dimensions(matrix: &Matrix) -> (usize, usize) {
+    (matrix.len(), matrix.first().map_or(0, Vec::len))
+}
+
+fn transpose(matrix: &Matrix) -> Matrix {
+    let (rows, columns) = dimensions(matrix);
+
+    (0..columns)
+        .map(|j| (0..rows).map(|i| matrix[i][j].clone()).collect())
+        .collect()
+}
+
+pub fn eval_matrix(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let matrix = to_matrix(values.first().unwrap(), 0, &scope.context)?;
+
+    Ok((scope, from_matrix(matrix)))
+}
+
+pub fn eval_transpose(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let matrix = to_matrix(values.first().unwrap(), 0, &scope.context)?;
+
+    Ok((scope, from_matrix(transpose(&matrix))))
+}
+
+pub fn eval_identity(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let size: i64 = values
+        .first()
+        .unwrap()
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(0, &scope.context))?;
+    let size = usize::try_from(size).map_err(|_| invalid(format!("invalid size {size}")))?;
+
+    let matrix = (0..size)
+        .map(|i| (0..size).map(|j| LispVal::Number(i64::from(i == j))).collect())
+        .collect();
+
+    Ok((scope, from_matrix(matrix)))
+}
+
+pub fn eval_matrix_multiplication(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let left = to_matrix(values.first().unwrap(), 0, &scope.context)?;
+    let right = to_matrix(values.get(1).unwrap(), 1, &scope.context)?;
+    let (rows, inner) = dimensions(&left);
+    let (right_rows, columns) = dimensions(&right);
+
+    if inner != right_rows {
+        return Err(invalid(format!(
+            "cannot multiply a {rows}x{inner} matrix by a {right_rows}x{columns} matrix"
+        )));
+    }
+
+    // Like the arithmetic builtins, overflow wraps around with a warning
+    let mut overflowed = false;
+    let mut product = Matrix::new();
+    let right = transpose(&right);
+    for row in &left {
+        let mut product_row = Vec::new();
+        for column in &right {
+            let sum = row
+                .iter()
+                .zip(column)
+                .fold(LispVal::Number(0), |sum, (a, b)| multiply_add(&sum, a, b, &mut overflowed));
+            product_row.push(sum);
+        }
+        product.push(product_row);
+    }
+
+    if overflowed {
+        scope.runtime.warn(Warning::Overflow { operation: scope.context.to_string() });
+    }
+
+    Ok((scope, from_matrix(product)))
+}
+
+pub fn eval_matrix_get(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let matrix = to_matrix(values.first().unwrap(), 0, &scope.context)?;
+    let index = |position: usize| -> Result<i64, EvalError> {
+        values
+            .get(position)
+            .unwrap()
+            .clone()
+            .try_into()
+            .map_err(EvalError::from_arg(position, &scope.context))
+    };
+    let (i, j) = (index(1)?, index(2)?);
+
+    let value = usize::try_from(i)
+        .ok()
+        .and_then(|i| matrix.get(i))
+        .zip(usize::try_from(j).ok())
+        .and_then(|(row, j)| row.get(j))
+        .ok_or_else(|| {
+            let (rows, columns) = dimensions(&matrix);
+            invalid(format!("index ({i}, {j}) is out of bounds for a {rows}x{columns} matrix"))
+        })?;
+
+    Ok((scope, value.clone()))
+}
@@ -1,14 +1,45 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use lazy_static::lazy_static;
 
-use crate::parsing::{error::LispValUnwrapError, LispVal};
+use crate::parsing::{error::LispValUnwrapError, Captured, Environment, LispType, LispVal};
 use error::EvalError;
 
+use self::complex::promoting;
+use self::runtime::{intercept, Effect};
 use self::scope::{Scope, INITIAL_SCOPE};
-
+use self::special_form::{special_form, Arguments};
+use self::version::Feature;
+use self::warning::Warning;
+
+mod binding;
+mod collection;
+mod complex;
+mod diff;
 pub mod error;
+mod event;
+mod graph;
+mod map;
+pub mod inference;
+mod matrix;
+pub mod module;
+mod multimethod;
+mod pattern;
+mod quasiquote;
+mod queue;
+mod record;
+mod reflection;
+pub mod runtime;
 pub mod scope;
+mod set;
+mod sort;
+pub mod special_form;
+pub mod store;
+pub mod suggestion;
+pub mod task;
+pub mod version;
+mod walk;
+pub mod warning;
 
 type EvalResult = Result<(Scope, LispVal), EvalError>;
 
@@ -24,7 +55,7 @@ where
     move |scope: Scope, values: &[LispVal]| -> EvalResult {
         let name = scope.context.clone();
         let a1 = values
-            .get(0)
+            .first()
             .unwrap()
             .clone()
             .try_into()
@@ -34,34 +65,9 @@ where
     }
 }
 
-fn eval_op2<F: Fn(A1, A2) -> R, A1, A2, R>(operation: F) -> impl EvalFn
-where
-    A1: std::convert::TryFrom<LispVal, Error = LispValUnwrapError>,
-    A2: std::convert::TryFrom<LispVal, Error = LispValUnwrapError>,
-    R: std::convert::Into<LispVal>,
-{
-    move |scope: Scope, values: &[LispVal]| {
-        let name = scope.context.clone();
-        let a1 = values
-            .get(0)
-            .unwrap()
-            .clone()
-            .try_into()
-            .map_err(EvalError::from_arg(0, &name))?;
-        let a2 = values
-            .get(1)
-            .unwrap()
-            .clone()
-            .try_into()
-            .map_err(EvalError::from_arg(1, &name))?;
-
-        Ok((scope, operation(a1, a2).into()))
-    }
-}
-
 fn eval_fold(scope: Scope, values: &[LispVal]) -> EvalResult {
     let name = scope.context.clone();
-    let operation = values.get(0).unwrap();
+    let operation = values.first().unwrap();
     let initial = values.get(1).unwrap().clone();
     let list: Vec<LispVal> = values
         .get(2)
@@ -70,16 +76,14 @@ fn eval_fold(scope: Scope, values: &[LispVal]) -> EvalResult {
         .try_into()
         .map_err(EvalError::from_arg(1, &name))?;
 
-    list.iter()
-        .try_fold((scope, initial), |(scope, acc), value| {
-            eval(scope, &vec![operation.clone(), acc, value.clone()].into())
-        })
+    list.into_iter()
+        .try_fold((scope, initial), |(scope, acc), value| call(scope, operation, vec![acc, value]))
 }
 
 fn eval_map(scope: Scope, values: &[LispVal]) -> EvalResult {
     let name = scope.context.clone();
 
-    let operation = values.get(0).unwrap().clone();
+    let operation = values.first().unwrap();
 
     let list: Vec<LispVal> = values
         .get(1)
@@ -91,86 +95,307 @@ fn eval_map(scope: Scope, values: &[LispVal]) -> EvalResult {
     let (scope, list) = list
         .into_iter()
         .try_fold((scope, Vec::new()), |(scope, mut acc), value| {
-            let (scope, result) = eval(scope, &vec![operation.clone(), value.clone()].into())?;
+            let (scope, result) = call(scope, operation, vec![value])?;
             acc.push(result);
             Ok((scope, acc))
         })?;
 
-    return Ok((scope, list.into()));
+    Ok((scope, list.into()))
+}
+
+/// Whether `value` counts as true where any value is accepted: everything
+/// but `false`, `nil` and void does, including `()` and `0`.
+fn is_truthy(value: &LispVal) -> bool {
+    !matches!(value, LispVal::Boolean(false) | LispVal::Nil | LispVal::Void())
 }
 
 fn eval_if(scope: Scope, values: &[LispVal]) -> EvalResult {
     let name = scope.context.clone();
-    let (scope, condition) = eval(scope, &values.get(0).unwrap())?;
-    let condition = condition
-        .try_into()
-        .map_err(EvalError::from_arg(0, &name))?;
+    let condition = values.first().unwrap();
+    let condition = if scope.settings.version.supports(Feature::Truthiness) {
+        is_truthy(condition)
+    } else {
+        condition.clone().try_into().map_err(EvalError::from_arg(0, &name))?
+    };
 
     if condition {
-        eval(scope, &values.get(1).unwrap())
+        eval(scope, values.get(1).unwrap())
     } else {
-        eval(scope, &values.get(2).unwrap())
+        eval(scope, values.get(2).unwrap())
     }
 }
 
+/// `(do! a b c)` evaluates its arguments in order, keeping the definitions
+/// they make, and returns the value of the last one.
+fn eval_do(scope: Scope, values: &[LispVal]) -> EvalResult {
+    if scope.settings.version.supports(Feature::ScopedDo) {
+        return binding::eval_body(scope.clone(), scope.child(), values);
+    }
+
+    binding::eval_sequence(scope, values)
+}
+
+fn eval_list_value(scope: Scope, values: &[LispVal]) -> EvalResult {
+    Ok((scope, values.to_vec().into()))
+}
+
 fn eval_concat(scope: Scope, values: &[LispVal]) -> EvalResult {
-    let (scope, left) = eval(scope, &values.get(0).unwrap())?;
-    let (scope, right) = eval(scope, &values.get(1).unwrap())?;
+    let left = values.first().unwrap();
+    let right = values.get(1).unwrap();
 
-    Ok((scope, left.concat(&right)))
+    Ok((scope, left.concat(right)))
 }
 
 fn eval_unevaluated(scope: Scope, values: &[LispVal]) -> EvalResult {
-    eval(scope, &values.get(0).unwrap())
+    eval(scope, values.first().unwrap())
 }
 
 fn eval_value_definition(scope: Scope, values: &[LispVal]) -> EvalResult {
     let name = scope.context.clone();
     let name = values
-        .get(0)
+        .first()
+        .unwrap()
+        .as_symbol()
+        .map_err(EvalError::from_arg(0, &name))?;
+    let value = values.get(1).unwrap().clone();
+
+    Ok((define(&scope, name.to_string(), value)?, LispVal::Void()))
+}
+
+fn eval_constant_definition(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let name = scope.context.clone();
+    let name = values
+        .first()
         .unwrap()
         .as_symbol()
         .map_err(EvalError::from_arg(0, &name))?;
-    let (scope, value) = eval(scope, values.get(1).unwrap())?;
+    let value = values.get(1).unwrap().clone();
+
+    if scope.is_constant(name) {
+        return Err(EvalError::ConstantRedefinition(name.to_string()));
+    }
+
+    intercept(Effect::Bind(name.to_string()));
 
-    Ok((scope.bind(name.to_string(), value), LispVal::Void()))
+    Ok((scope.bind_constant(name.to_string(), value), LispVal::Void()))
 }
 
+/// Binds `name` in the current frame, refusing to shadow or redefine a
+/// `const!` binding.
+fn bind(scope: &Scope, name: String, value: LispVal) -> Result<Scope, EvalError> {
+    if scope.is_constant(&name) {
+        return Err(EvalError::ConstantRedefinition(name));
+    }
+
+    Ok(scope.bind(name, value))
+}
+
+/// Binds a definition made by the user, reporting it during a dry run and
+/// warning when it hides another name.
+fn define(scope: &Scope, name: String, value: LispVal) -> Result<Scope, EvalError> {
+    intercept(Effect::Bind(name.clone()));
+
+    if builtin_names().any(|builtin| builtin == name) {
+        scope.runtime.warn(Warning::ShadowedBuiltin { name: name.clone() });
+    } else if !scope.bindings.contains_key(&name)
+        && scope.parent().is_some_and(|parent| parent.get(&name).is_some())
+    {
+        scope.runtime.warn(Warning::Shadowing { name: name.clone() });
+    }
+
+    bind(scope, name, value)
+}
+
+#[cfg(feature = "io")]
+fn eval_print(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let name = scope.context.clone();
+    let text: String = values
+        .first()
+        .unwrap()
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(0, &name))?;
+
+    if !intercept(Effect::Print(text.clone())) {
+        println!("{}", text);
+    }
+
+    Ok((scope, LispVal::Void()))
+}
+
+/// `(pprint x)` prints `x` indented over several lines where it doesn't fit
+/// in 80 columns; `(pprint x width)` fits it in `width` instead.
+#[cfg(feature = "io")]
+fn eval_pprint(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let width = match values.get(1) {
+        Some(width) => i64::try_from(width.clone()).map_err(EvalError::from_arg(1, &scope.context))?,
+        None => 80,
+    };
+    let text = crate::display::with_float_precision(scope.float_precision(), || {
+        values.first().unwrap().pretty(width.max(0) as usize)
+    });
+
+    if !intercept(Effect::Print(text.clone())) {
+        println!("{}", text);
+    }
+
+    Ok((scope, LispVal::Void()))
+}
+
+#[cfg(feature = "io")]
 fn eval_print_scope(scope: Scope, _: &[LispVal]) -> EvalResult {
-    println!("{}", scope);
+    if !intercept(Effect::Print(scope.to_string())) {
+        println!("{}", scope);
+    }
 
     Ok((scope, LispVal::Void()))
 }
 
-fn eval_clear_scope(_: Scope, _: &[LispVal]) -> EvalResult {
-    Ok((INITIAL_SCOPE.clone(), LispVal::Void()))
+fn eval_clear_scope(scope: Scope, _: &[LispVal]) -> EvalResult {
+    if intercept(Effect::ClearScope) {
+        return Ok((scope, LispVal::Void()));
+    }
+
+    let cleared = INITIAL_SCOPE
+        .with_settings(scope.settings)
+        .with_runtime(scope.runtime.clone());
+
+    Ok((cleared, LispVal::Void()))
+}
+
+/// The two arguments of an arithmetic or comparison builtin. When either is
+/// a float, both are.
+enum Operands {
+    Integers(i64, i64),
+    Floats(f64, f64),
 }
 
-fn eval_math<F>(operation: F) -> impl EvalFn
+fn numeric_operands(values: &[LispVal], name: &str) -> Result<Operands, EvalError> {
+    let is_float = |position: usize| matches!(values.get(position), Some(LispVal::Float(_)));
+    let operand = |position: usize| values.get(position).unwrap().clone();
+
+    if is_float(0) || is_float(1) {
+        let float = |position: usize| f64::try_from(operand(position)).map_err(EvalError::from_arg(position, name));
+        Ok(Operands::Floats(float(0)?, float(1)?))
+    } else {
+        let integer = |position: usize| i64::try_from(operand(position)).map_err(EvalError::from_arg(position, name));
+        Ok(Operands::Integers(integer(0)?, integer(1)?))
+    }
+}
+
+/// Arithmetic builtins take overflowing integer operations, wrapping the
+/// result around and warning instead of failing when it doesn't fit. Mixed
+/// with a float, integers are promoted and `float_operation` is used.
+fn eval_math<F, G>(operation: F, float_operation: G) -> impl EvalFn
 where
-    F: Fn(i64, i64) -> i64,
+    F: Fn(i64, i64) -> (i64, bool),
+    G: Fn(f64, f64) -> f64,
 {
-    eval_op2(operation)
+    move |scope: Scope, values: &[LispVal]| {
+        let name = scope.context.clone();
+        let (a, b) = match numeric_operands(values, &name)? {
+            Operands::Floats(a, b) => return Ok((scope, LispVal::Float(float_operation(a, b)))),
+            Operands::Integers(a, b) => (a, b),
+        };
+        let (result, overflowed) = operation(a, b);
+
+        if overflowed {
+            scope.runtime.warn(Warning::Overflow { operation: name.to_string() });
+        }
+
+        Ok((scope, LispVal::Number(result)))
+    }
 }
 
-fn eval_logic<F>(operation: F) -> impl EvalFn
+/// Like [`eval_math`] for division and remainder, failing instead of
+/// panicking when an integer is divided by zero.
+fn eval_division<F, G>(operation: F, float_operation: G) -> impl EvalFn
 where
-    F: Fn(bool, bool) -> bool,
+    F: Fn(i64, i64) -> (i64, bool),
+    G: Fn(f64, f64) -> f64,
 {
-    eval_op2(operation)
+    let math = eval_math(operation, float_operation);
+    move |scope: Scope, values: &[LispVal]| {
+        if let Operands::Integers(_, 0) = numeric_operands(values, &scope.context)? {
+            return Err(EvalError::DivisionByZero { name: scope.context.to_string() });
+        }
+        math(scope, values)
+    }
 }
 
+/// `and` when `deciding` is `false`, `or` when it's `true`, over any number
+/// of arguments: the first one equal to `deciding` decides the result, and
+/// without one the result is the opposite, so `(and)` is `true` and `(or)`
+/// is `false`. The arguments after the first arrive unevaluated and are
+/// evaluated left to right until one decides.
+fn eval_logic(deciding: bool) -> impl EvalFn {
+    move |scope: Scope, values: &[LispVal]| {
+        let name = scope.context.clone();
+        let boolean = |position: usize, value: LispVal| -> Result<bool, EvalError> {
+            value.try_into().map_err(EvalError::from_arg(position, &name))
+        };
+
+        // Only the first argument is evaluated before the call
+        let mut scope = scope;
+        for (position, value) in values.iter().enumerate() {
+            let (next, value) = if position == 0 {
+                (scope, value.clone())
+            } else {
+                eval(scope, value).map_err(EvalError::outside_tail)?
+            };
+            scope = next;
+            if boolean(position, value)? == deciding {
+                return Ok((scope, deciding.into()));
+            }
+        }
+
+        Ok((scope, (!deciding).into()))
+    }
+}
+
+/// `(= a b)` compares two numbers like the other comparisons, so `2` equals
+/// `2.0`. Any other values are equal when they're structurally the same,
+/// so keywords, strings and lists compare by value.
+fn eval_equality(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let is_number = |value: &LispVal| matches!(value, LispVal::Number(_) | LispVal::Float(_));
+    if values[..2].iter().all(is_number) {
+        return eval_comparison(std::cmp::Ordering::is_eq)(scope, values);
+    }
+
+    let equal = values[0] == values[1];
+    Ok((scope, equal.into()))
+}
+
+/// Comparisons between an integer and a float promote the integer. Nothing
+/// compares to `NaN`, so every comparison with it is false.
 fn eval_comparison<F>(operation: F) -> impl EvalFn
 where
-    F: Fn(i64, i64) -> bool,
+    F: Fn(std::cmp::Ordering) -> bool,
 {
-    eval_op2(operation)
+    move |scope: Scope, values: &[LispVal]| {
+        let ordering = match numeric_operands(values, &scope.context)? {
+            Operands::Integers(a, b) => Some(a.cmp(&b)),
+            Operands::Floats(a, b) => a.partial_cmp(&b),
+        };
+
+        Ok((scope, ordering.is_some_and(&operation).into()))
+    }
+}
+
+/// The list argument at `position`, for natives taking lists.
+fn list_argument(values: &[LispVal], position: usize, context: &str) -> Result<Vec<LispVal>, EvalError> {
+    values
+        .get(position)
+        .unwrap()
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(position, context))
 }
 
 fn eval_push(scope: Scope, values: &[LispVal]) -> Result<(Scope, LispVal), EvalError> {
     let name = scope.context.clone();
     let mut list: Vec<LispVal> = values
-        .get(0)
+        .first()
         .unwrap()
         .clone()
         .try_into()
@@ -185,8 +410,9 @@ fn eval_push(scope: Scope, values: &[LispVal]) -> Result<(Scope, LispVal), EvalE
 fn eval_function_value(scope: Scope, values: &[LispVal]) -> Result<(Scope, LispVal), EvalError> {
     let name = scope.context.clone();
     let args_values: Vec<LispVal> = values
-        .get(0)
+        .first()
         .unwrap()
+        .unquoted()
         .clone()
         .try_into()
         .map_err(EvalError::from_arg(0, &name))?;
@@ -201,19 +427,77 @@ fn eval_function_value(scope: Scope, values: &[LispVal]) -> Result<(Scope, LispV
         })
         .collect::<Result<Vec<_>, _>>()?;
 
+    let captured = Arc::new(Captured { bindings: scope.captured(), group: Environment::new() });
     Ok((
         scope,
         LispVal::Function {
             parameters: args,
             body,
             applied: Vec::new(),
+            doc: None,
+            captured,
         },
     ))
 }
 
+fn eval_stats(scope: Scope, _: &[LispVal]) -> EvalResult {
+    let metrics = scope.runtime.metrics();
+    let field = |name: &str, value: usize| (name.to_string(), LispVal::Number(value as i64));
+    let stats = LispVal::Record {
+        name: "stats".to_string(),
+        fields: vec![
+            field("evaluated_nodes", metrics.evaluated_nodes),
+            field("function_calls", metrics.function_calls),
+            field("max_depth", metrics.max_depth),
+            field("list_allocations", metrics.list_allocations),
+        ],
+    };
+
+    Ok((scope, stats))
+}
+
+fn eval_is_list(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let is_list = matches!(values.first().unwrap(), LispVal::List(_));
+
+    Ok((scope, LispVal::Boolean(is_list)))
+}
+
+/// `(nil? x)` is true only for `nil`; `()`, `false` and void are not nil.
+fn eval_is_nil(scope: Scope, values: &[LispVal]) -> EvalResult {
+    Ok((scope, LispVal::Boolean(values.first().unwrap().is_nil())))
+}
+
+/// `(empty? x)` is whether a collection or string has no elements. `nil`
+/// is empty, as it stands for a missing collection.
+fn eval_is_empty(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let is_empty = match values.first().unwrap() {
+        LispVal::List(values) | LispVal::Vector(values) => values.is_empty(),
+        LispVal::String(s) => s.is_empty(),
+        LispVal::Map(entries) => entries.is_empty(),
+        LispVal::Set(elements) => elements.is_empty(),
+        LispVal::Deque(values) => values.is_empty(),
+        LispVal::Heap { entries, .. } => entries.is_empty(),
+        LispVal::Nil => true,
+        value => {
+            return Err(EvalError::InvalidArgumentType {
+                name: scope.context.to_string(),
+                expected: LispType::List,
+                got: value.to_type(),
+                position: 0,
+            })
+        }
+    };
+
+    Ok((scope, LispVal::Boolean(is_empty)))
+}
+
+#[cfg(feature = "io")]
 fn eval_debug(scope: Scope, values: &[LispVal]) -> Result<(Scope, LispVal), EvalError> {
-    let value = values.get(0).unwrap().clone();
-    println!("{:#?}", value);
+    let value = values.first().unwrap().clone();
+    let text = format!("{:#?}", value);
+    if !intercept(Effect::Print(text.clone())) {
+        println!("{}", text);
+    }
     Ok((scope, value))
 }
 
@@ -223,14 +507,27 @@ fn eval_function_definition(
 ) -> Result<(Scope, LispVal), EvalError> {
     let name = scope.context.clone();
     let function_name = values
-        .get(0)
+        .first()
         .unwrap()
         .as_symbol()
         .map_err(EvalError::from_arg(0, &name))?;
-    let (scope, function) = eval_function_value(scope, &values[1..])?;
+    // A string between the parameters and the body is the docstring
+    let (doc, function_values) = match &values[1..] {
+        [parameters, LispVal::String(doc), body, ..] => (Some(doc.to_string()), vec![parameters.clone(), body.clone()]),
+        values => (None, values.to_vec()),
+    };
+    let (scope, mut function) = eval_function_value(scope, &function_values)?;
+    if let LispVal::Function { doc: function_doc, .. } = &mut function {
+        *function_doc = doc;
+    }
+    // A function defined inside another one doesn't see its own name when
+    // called from elsewhere, unless it carries it along
+    if scope.parent().is_some() {
+        function = with_group(&function, &Environment::unit(function_name.to_string(), function.clone()));
+    }
 
     Ok((
-        scope.bind(function_name.to_string(), function),
+        define(&scope, function_name.to_string(), function)?,
         LispVal::Void(),
     ))
 }
@@ -253,7 +550,6 @@ impl NativeFunction {
 
     fn to_function(&self, name: String, applied: Vec<LispVal>) -> LispVal {
         let args: Vec<_> = (0..self.required_arguments_count)
-            .into_iter()
             .map(|n| format!("a{n}"))
             .collect();
 
@@ -264,10 +560,12 @@ impl NativeFunction {
             parameters: args,
             body: Box::new(LispVal::List(
                 expr.iter()
-                    .map(|v| LispVal::Symbol(v.to_string()))
+                    .map(|v| LispVal::Symbol(v.into()))
                     .collect(),
             )),
             applied,
+            doc: None,
+            captured: Default::default(),
         }
     }
 
@@ -278,7 +576,8 @@ impl NativeFunction {
             return Ok((scope, self.to_function(name.to_string(), values.to_vec())));
         }
 
-        (self.implementation)(scope, &values)
+        scope.runtime.count_call();
+        (self.implementation)(scope, values)
     }
 }
 
@@ -286,10 +585,11 @@ lazy_static! {
     static ref INTERNAL_SYMBOLS_TABLE: HashMap::<&'static str, NativeFunction> = {
         let mut s = HashMap::<&'static str, NativeFunction>::new();
         s.insert("eval", NativeFunction::new(1, eval_unevaluated));
-        s.insert(
-            "print",
-            NativeFunction::new(1, eval_op1(|s: String| println!("{}", s))),
-        );
+        #[cfg(feature = "io")]
+        s.insert("print", NativeFunction::new(1, eval_print));
+        #[cfg(feature = "io")]
+        s.insert("pprint", NativeFunction::new(1, eval_pprint));
+        #[cfg(feature = "io")]
         s.insert("debug", NativeFunction::new(1, eval_debug));
         s.insert(
             "to_string",
@@ -301,66 +601,229 @@ lazy_static! {
         s.insert("push", NativeFunction::new(2, eval_push));
         s.insert("fn!", NativeFunction::new(2, eval_function_value));
         s.insert("def!", NativeFunction::new(2, eval_value_definition));
+        s.insert("const!", NativeFunction::new(2, eval_constant_definition));
         s.insert("defn!", NativeFunction::new(3, eval_function_definition));
+        s.insert("defrecord!", NativeFunction::new(2, record::eval_record_definition));
+        s.insert("make_record", NativeFunction::new(3, record::eval_make_record));
+        s.insert("is_record", NativeFunction::new(2, record::eval_is_record));
+        s.insert("record_field", NativeFunction::new(3, record::eval_record_field));
+        s.insert("record_name", NativeFunction::new(1, record::eval_record_name));
+        s.insert("defmulti!", NativeFunction::new(2, multimethod::eval_multi_definition));
+        s.insert("defmethod!", NativeFunction::new(4, multimethod::eval_method_definition));
+        #[cfg(feature = "io")]
         s.insert("print_scope", NativeFunction::new(0, eval_print_scope));
+        s.insert("stats", NativeFunction::new(0, eval_stats));
         s.insert("clear_scope", NativeFunction::new(0, eval_clear_scope));
+        s.insert("load", NativeFunction::new(1, module::eval_load));
+        s.insert("require", NativeFunction::new(1, module::eval_require));
+        s.insert("store-get", NativeFunction::new(1, store::eval_store_get));
+        s.insert("store-set!", NativeFunction::new(2, store::eval_store_set));
+        s.insert("store-keys", NativeFunction::new(0, store::eval_store_keys));
+        s.insert("on-event!", NativeFunction::new(2, event::eval_on_event));
+        s.insert("deftask!", NativeFunction::new(2, task::eval_task_definition));
+        s.insert("tick", NativeFunction::new(0, task::eval_tick));
+        s.insert("current-file", NativeFunction::new(0, reflection::eval_current_file));
+        s.insert("current-line", NativeFunction::new(0, reflection::eval_current_line));
+        s.insert("source", NativeFunction::new(1, reflection::eval_source));
+        s.insert("doc", NativeFunction::new(1, reflection::eval_doc));
+        s.insert("describe", NativeFunction::new(1, reflection::eval_describe));
         s.insert(
             "head",
-            NativeFunction::new(1, eval_op1(|l: Vec<LispVal>| l.get(0).unwrap().clone())),
+            NativeFunction::new(1, eval_op1(|l: Vec<LispVal>| l.first().cloned().unwrap_or(LispVal::Nil))),
         );
         s.insert(
             "tail",
-            NativeFunction::new(1, eval_op1(|l: Vec<LispVal>| l[1..].to_vec())),
+            NativeFunction::new(1, eval_op1(|l: Vec<LispVal>| l.get(1..).unwrap_or_default().to_vec())),
         );
         s.insert(
             "len",
             NativeFunction::new(1, eval_op1(|l: Vec<LispVal>| l.len() as i64)),
         );
         s.insert("if!", NativeFunction::new(3, eval_if));
-
-        s.insert("+", NativeFunction::new(2, eval_math(|a, b| a + b)));
-        s.insert("-", NativeFunction::new(2, eval_math(|a, b| a - b)));
-        s.insert("*", NativeFunction::new(2, eval_math(|a, b| a * b)));
-        s.insert("/", NativeFunction::new(2, eval_math(|a, b| a / b)));
-        s.insert("%", NativeFunction::new(2, eval_math(|a, b| a % b)));
-
-        s.insert("add", NativeFunction::new(2, eval_math(|a, b| a + b)));
-        s.insert("sub", NativeFunction::new(2, eval_math(|a, b| a - b)));
-        s.insert("mul", NativeFunction::new(2, eval_math(|a, b| a * b)));
-        s.insert("div", NativeFunction::new(2, eval_math(|a, b| a / b)));
-        s.insert("mod", NativeFunction::new(2, eval_math(|a, b| a % b)));
-        s.insert("max", NativeFunction::new(2, eval_math(|a, b| a.max(b))));
-        s.insert("min", NativeFunction::new(2, eval_math(|a, b| a.min(b))));
-
-        s.insert("<", NativeFunction::new(2, eval_comparison(|a, b| a < b)));
-        s.insert(">", NativeFunction::new(2, eval_comparison(|a, b| a > b)));
-        s.insert("<=", NativeFunction::new(2, eval_comparison(|a, b| a <= b)));
-        s.insert(">=", NativeFunction::new(2, eval_comparison(|a, b| a >= b)));
-        s.insert("=", NativeFunction::new(2, eval_comparison(|a, b| a == b)));
-
-        s.insert("lt", NativeFunction::new(2, eval_comparison(|a, b| a < b)));
-        s.insert("gt", NativeFunction::new(2, eval_comparison(|a, b| a > b)));
-        s.insert(
-            "ltq",
-            NativeFunction::new(2, eval_comparison(|a, b| a <= b)),
-        );
-        s.insert(
-            "gtq",
-            NativeFunction::new(2, eval_comparison(|a, b| a >= b)),
-        );
-        s.insert("eq", NativeFunction::new(2, eval_comparison(|a, b| a == b)));
-
-        s.insert("and", NativeFunction::new(2, eval_logic(|a, b| a & b)));
-        s.insert("or", NativeFunction::new(2, eval_logic(|a, b| a | b)));
+        s.insert("do!", NativeFunction::new(0, eval_do));
+        s.insert("let!", NativeFunction::new(2, binding::eval_let));
+        s.insert("letrec!", NativeFunction::new(2, binding::eval_letrec));
+        s.insert("loop!", NativeFunction::new(2, binding::eval_loop));
+        s.insert("recur", NativeFunction::new(0, binding::eval_recur));
+        s.insert("match!", NativeFunction::new(2, pattern::eval_match));
+        s.insert("list", NativeFunction::new(0, eval_list_value));
+
+        s.insert("flatten", NativeFunction::new(1, collection::eval_flatten));
+        s.insert("distinct", NativeFunction::new(1, collection::eval_distinct));
+        s.insert("interleave", NativeFunction::new(2, collection::eval_interleave));
+        s.insert("interpose", NativeFunction::new(2, collection::eval_interpose));
+        s.insert("repeat", NativeFunction::new(2, collection::eval_repeat));
+        s.insert("cons", NativeFunction::new(2, collection::eval_cons));
+        s.insert("car", NativeFunction::new(1, collection::eval_car));
+        s.insert("cdr", NativeFunction::new(1, collection::eval_cdr));
+
+        s.insert("heap", NativeFunction::new(0, queue::eval_heap));
+        s.insert("heap-push", NativeFunction::new(3, queue::eval_heap_push));
+        s.insert("heap-peek", NativeFunction::new(1, queue::eval_heap_peek));
+        s.insert("heap-pop", NativeFunction::new(1, queue::eval_heap_pop));
+        s.insert("deque", NativeFunction::new(1, queue::eval_deque));
+        s.insert("push-front", NativeFunction::new(2, queue::eval_push_front));
+        s.insert("push-back", NativeFunction::new(2, queue::eval_push_back));
+        s.insert("peek-front", NativeFunction::new(1, queue::eval_peek_front));
+        s.insert("peek-back", NativeFunction::new(1, queue::eval_peek_back));
+        s.insert("pop-front", NativeFunction::new(1, queue::eval_pop_front));
+        s.insert("pop-back", NativeFunction::new(1, queue::eval_pop_back));
+        s.insert("to_list", NativeFunction::new(1, queue::eval_to_list));
+
+        s.insert("quasiquote", NativeFunction::new(1, quasiquote::eval_quasiquote));
+
+        s.insert("get", NativeFunction::new(2, map::eval_get));
+        s.insert("assoc", NativeFunction::new(3, map::eval_assoc));
+        s.insert("dissoc", NativeFunction::new(2, map::eval_dissoc));
+        s.insert("keys", NativeFunction::new(1, map::eval_keys));
+        s.insert("vals", NativeFunction::new(1, map::eval_vals));
+        s.insert("merge", NativeFunction::new(2, map::eval_merge));
+
+        s.insert("set", NativeFunction::new(1, set::eval_set));
+        s.insert("union", NativeFunction::new(2, set::eval_union));
+        s.insert("intersection", NativeFunction::new(2, set::eval_intersection));
+        s.insert("difference", NativeFunction::new(2, set::eval_difference));
+        s.insert("contains?", NativeFunction::new(2, set::eval_contains));
+
+        s.insert("graph", NativeFunction::new(1, graph::eval_graph));
+        s.insert("neighbors", NativeFunction::new(2, graph::eval_neighbors));
+        s.insert("bfs", NativeFunction::new(2, graph::eval_bfs));
+        s.insert("topo-sort", NativeFunction::new(1, graph::eval_topological_sort));
+
+        s.insert("diff", NativeFunction::new(2, diff::eval_diff));
+        s.insert("is_list", NativeFunction::new(1, eval_is_list));
+        s.insert("nil?", NativeFunction::new(1, eval_is_nil));
+        s.insert("empty?", NativeFunction::new(1, eval_is_empty));
+        s.insert("postwalk", NativeFunction::new(2, walk::eval_postwalk));
+        s.insert("prewalk", NativeFunction::new(2, walk::eval_prewalk));
+
+        s.insert("sort", NativeFunction::new(1, sort::eval_sort));
+        s.insert("sort-by", NativeFunction::new(2, sort::eval_sort_by));
+        s.insert("max-by", NativeFunction::new(2, sort::eval_max_by));
+        s.insert("min-by", NativeFunction::new(2, sort::eval_min_by));
+        s.insert("bsearch", NativeFunction::new(2, sort::eval_binary_search));
+        s.insert("sorted-insert", NativeFunction::new(2, sort::eval_sorted_insert));
+        s.insert("sorted-map-put", NativeFunction::new(3, sort::eval_sorted_map_put));
+        s.insert("sorted-map-get", NativeFunction::new(3, sort::eval_sorted_map_get));
+        s.insert("lowercase", NativeFunction::new(1, eval_op1(|s: String| s.to_lowercase())));
+        s.insert("uppercase", NativeFunction::new(1, eval_op1(|s: String| s.to_uppercase())));
+
+        s.insert("matrix", NativeFunction::new(1, matrix::eval_matrix));
+        s.insert("transpose", NativeFunction::new(1, matrix::eval_transpose));
+        s.insert("identity", NativeFunction::new(1, matrix::eval_identity));
+        s.insert("mat-mul", NativeFunction::new(2, matrix::eval_matrix_multiplication));
+        s.insert("mat-get", NativeFunction::new(3, matrix::eval_matrix_get));
+
+        s.insert("+", NativeFunction::new(2, promoting(complex::add, eval_math(i64::overflowing_add, |a, b| a + b))));
+        s.insert("-", NativeFunction::new(2, promoting(complex::sub, eval_math(i64::overflowing_sub, |a, b| a - b))));
+        s.insert("*", NativeFunction::new(2, promoting(complex::mul, eval_math(i64::overflowing_mul, |a, b| a * b))));
+        s.insert("/", NativeFunction::new(2, promoting(complex::div, eval_division(i64::overflowing_div, |a, b| a / b))));
+        s.insert("%", NativeFunction::new(2, eval_division(i64::overflowing_rem, |a, b| a % b)));
+
+        s.insert("add", NativeFunction::new(2, promoting(complex::add, eval_math(i64::overflowing_add, |a, b| a + b))));
+        s.insert("sub", NativeFunction::new(2, promoting(complex::sub, eval_math(i64::overflowing_sub, |a, b| a - b))));
+        s.insert("mul", NativeFunction::new(2, promoting(complex::mul, eval_math(i64::overflowing_mul, |a, b| a * b))));
+        s.insert("div", NativeFunction::new(2, promoting(complex::div, eval_division(i64::overflowing_div, |a, b| a / b))));
+        s.insert("mod", NativeFunction::new(2, eval_division(i64::overflowing_rem, |a, b| a % b)));
+        s.insert("max", NativeFunction::new(2, eval_math(|a, b| (a.max(b), false), f64::max)));
+        s.insert("min", NativeFunction::new(2, eval_math(|a, b| (a.min(b), false), f64::min)));
+
+        s.insert("complex", NativeFunction::new(2, complex::eval_complex));
+        s.insert("real", NativeFunction::new(1, complex::eval_real));
+        s.insert("imag", NativeFunction::new(1, complex::eval_imag));
+        s.insert("magnitude", NativeFunction::new(1, complex::eval_magnitude));
+
+        s.insert("<", NativeFunction::new(2, eval_comparison(std::cmp::Ordering::is_lt)));
+        s.insert(">", NativeFunction::new(2, eval_comparison(std::cmp::Ordering::is_gt)));
+        s.insert("<=", NativeFunction::new(2, eval_comparison(std::cmp::Ordering::is_le)));
+        s.insert(">=", NativeFunction::new(2, eval_comparison(std::cmp::Ordering::is_ge)));
+        s.insert("=", NativeFunction::new(2, eval_equality));
+
+        s.insert("lt", NativeFunction::new(2, eval_comparison(std::cmp::Ordering::is_lt)));
+        s.insert("gt", NativeFunction::new(2, eval_comparison(std::cmp::Ordering::is_gt)));
+        s.insert("lte", NativeFunction::new(2, eval_comparison(std::cmp::Ordering::is_le)));
+        s.insert("gte", NativeFunction::new(2, eval_comparison(std::cmp::Ordering::is_ge)));
+        s.insert("eq", NativeFunction::new(2, eval_equality));
+
+        s.insert("and", NativeFunction::new(0, eval_logic(false)));
+        s.insert("or", NativeFunction::new(0, eval_logic(true)));
         s.insert("not", NativeFunction::new(1, eval_op1(|a: bool| !a)));
         s
     };
 }
 
+/// Builtins that were renamed, with the name that replaced them. The old
+/// names keep working but warn when called.
+const DEPRECATED_BUILTINS: &[(&str, &str)] = &[("ltq", "lte"), ("gtq", "gte")];
+
+/// The builtin that replaced `name`, if `name` is deprecated.
+pub fn deprecated_builtin(name: &str) -> Option<&'static str> {
+    DEPRECATED_BUILTINS
+        .iter()
+        .find(|(deprecated, _)| *deprecated == name)
+        .map(|(_, replacement)| *replacement)
+}
+
+/// Names of every builtin, special forms included.
+pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+    INTERNAL_SYMBOLS_TABLE.keys().copied()
+}
+
+/// Number of arguments a native builtin needs before it runs.
+pub fn builtin_arity(name: &str) -> Option<usize> {
+    INTERNAL_SYMBOLS_TABLE
+        .get(name)
+        .map(|native_function| native_function.required_arguments_count)
+}
+
+/// Maps `atom` to the builtin it refers to. Unless the scope enables
+/// case-insensitive lookup, only exact names and aliases resolve.
+/// Deprecated names resolve to their replacement, with a warning.
+fn resolve_builtin(scope: &Scope, atom: &str) -> Option<&'static str> {
+    let exact = builtin_names().find(|name| *name == atom);
+    if exact.is_some() || scope.get(atom).is_some() {
+        return exact;
+    }
+
+    if let Some(replacement) = deprecated_builtin(atom) {
+        scope.runtime.warn(Warning::Deprecated {
+            name: atom.to_string(),
+            replacement: replacement.to_string(),
+        });
+        return Some(replacement);
+    }
+
+    if let Some(builtin) = scope.runtime.alias(atom) {
+        return Some(builtin);
+    }
+
+    if !scope.settings.case_insensitive {
+        return None;
+    }
+
+    builtin_names().find(|name| name.eq_ignore_ascii_case(atom))
+}
+
+/// `function` made a member of `group`: the functions bound with it, which
+/// it sees wherever it's called from.
+fn with_group(function: &LispVal, group: &Environment) -> LispVal {
+    match function {
+        LispVal::Function { parameters, body, applied, doc, captured } => LispVal::Function {
+            parameters: parameters.clone(),
+            body: body.clone(),
+            applied: applied.clone(),
+            doc: doc.clone(),
+            captured: Arc::new(Captured { bindings: captured.bindings.clone(), group: group.clone() }),
+        },
+        value => value.clone(),
+    }
+}
+
 fn eval_function(
     scope: Scope,
     parameters: &[String],
     body: &LispVal,
+    captured: &Arc<Captured>,
     arguments: Vec<LispVal>,
 ) -> EvalResult {
     // Partial Function Application
@@ -371,24 +834,122 @@ fn eval_function(
                 parameters: parameters.to_vec(),
                 body: Box::new(body.clone()),
                 applied: arguments,
+                doc: None,
+                captured: captured.clone(),
             },
         ));
     }
 
-    let scope_before = scope.clone();
+    scope.runtime.count_call();
 
-    // Bind arguments to scope
-    let scope = parameters
+    // Bind arguments in a new frame over the captured bindings and the
+    // functions of its group, discarded once the body is evaluated
+    let frame = captured.group.iter().fold(scope.closure(&captured.bindings), |frame, (name, member)| {
+        frame.bind(name.clone(), with_group(member, &captured.group))
+    });
+    let frame = parameters
         .iter()
         .zip(arguments)
-        .fold(scope_before.clone(), |scope, (arg, value)| {
-            scope.bind(arg.clone(), value.clone())
-        });
+        .try_fold(frame, |frame, (arg, value)| bind(&frame, arg.clone(), value))?;
 
-    // Ignore the scope returned by the function
-    let (_, result) = eval(scope, body)?;
+    // A `recur` can't start over a `loop!` the function was called from
+    let (_, result) = eval(frame, body).map_err(EvalError::outside_tail)?;
 
-    return Ok((scope_before, result));
+    Ok((scope, result))
+}
+
+/// Calls `callable` with arguments that are already evaluated, so natives
+/// can call back into Lisp. `callable` can be a function value (partially
+/// applied or not), a multimethod or the name of a builtin or bound
+/// function. Passing fewer arguments than required returns a partially
+/// applied function, like a call written in source would.
+pub fn call(scope: Scope, callable: &LispVal, arguments: Vec<LispVal>) -> EvalResult {
+    match callable {
+        LispVal::Function { parameters, body, applied, captured, .. } => eval_function(
+            scope,
+            parameters,
+            body,
+            captured,
+            applied.iter().cloned().chain(arguments).collect(),
+        ),
+        LispVal::MultiMethod { name, dispatch, methods } => {
+            multimethod::call_multimethod(scope, name, dispatch, methods, arguments)
+        }
+        // Builtins are resolved by name, with the arguments quoted so they aren't evaluated twice
+        LispVal::Symbol(_) => {
+            let call = std::iter::once(callable.clone())
+                .chain(arguments.iter().map(LispVal::to_unevaluated))
+                .collect();
+            // Passing `recur` to another function doesn't make it a tail call
+            eval(scope, &call).map_err(EvalError::outside_tail)
+        }
+        _ => Err(EvalError::InvalidFunctionCall {
+            values: std::iter::once(callable.clone()).chain(arguments).collect(),
+        }),
+    }
+}
+
+/// A checked integer operation, returning whether it overflowed.
+type Arithmetic = fn(i64, i64) -> (i64, bool);
+
+fn arithmetic_operation(op: &LispVal) -> Option<Arithmetic> {
+    match op {
+        LispVal::Symbol(op) => match op.as_str() {
+            "+" => Some(i64::overflowing_add),
+            "-" => Some(i64::overflowing_sub),
+            "*" => Some(i64::overflowing_mul),
+            "/" => Some(i64::overflowing_div),
+            "%" => Some(i64::overflowing_rem),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The operation and operands of `values` when it's `(op a b)` for an
+/// arithmetic builtin, where both arguments are numbers or names bound to
+/// numbers. Nested calls take the general path, through [`eval`].
+fn arithmetic_call(scope: &Scope, values: &[LispVal]) -> Option<(Arithmetic, i64, i64)> {
+    let operand = |value: &LispVal| match value {
+        LispVal::Number(n) => Some(*n),
+        LispVal::Symbol(name) => match scope.get(name) {
+            Some(LispVal::Number(n)) => Some(*n),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    match values {
+        [op, a, b] => Some((arithmetic_operation(op)?, operand(a)?, operand(b)?)),
+        _ => None,
+    }
+}
+
+/// Computes a call that [`arithmetic_call`] accepted without building
+/// argument lists or call scopes. The runtime counters, task budget,
+/// overflow warnings and division by zero errors are the same as for the
+/// general path.
+fn eval_arithmetic(scope: &Scope, op: &LispVal, (operation, a, b): (Arithmetic, i64, i64)) -> Result<i64, EvalError> {
+    let runtime = &scope.runtime;
+
+    runtime.count_list();
+    for _ in 0..2 {
+        runtime.check_budget()?;
+        runtime.enter();
+        runtime.exit();
+    }
+    runtime.count_call();
+
+    if b == 0 && matches!(op, LispVal::Symbol(op) if op == "/" || op == "%") {
+        return Err(EvalError::DivisionByZero { name: op.to_string() });
+    }
+
+    let (result, overflowed) = operation(a, b);
+    if overflowed {
+        runtime.warn(Warning::Overflow { operation: op.to_string() });
+    }
+
+    Ok(result)
 }
 
 fn eval_list(scope: Scope, values: &[LispVal]) -> EvalResult {
@@ -396,74 +957,118 @@ fn eval_list(scope: Scope, values: &[LispVal]) -> EvalResult {
         return Ok((scope, vec![].into()));
     }
 
-    let (heads, tail) = values.clone().split_at(1);
-    let head = heads.get(0).unwrap();
+    if let Some(call) = arithmetic_call(&scope, values) {
+        let value = LispVal::Number(eval_arithmetic(&scope, &values[0], call)?);
+        return Ok((scope, value));
+    }
+
+    let (heads, tail) = values.split_at(1);
+    let head = heads.first().unwrap();
     let invoke_error = || EvalError::InvalidFunctionCall {
         values: values.to_vec(),
     };
 
     if let LispVal::Symbol(atom) = head {
-        let scope = scope.with_context(atom.clone());
-
-        let (scope, tail) = if head.is_macro() {
-            (scope, tail.to_vec())
-        } else {
-            eval_tail(scope, tail)?
-        };
-
-        if atom == "list" {
-            return Ok((scope, tail.into()));
-        }
-
-        // Internal functions
-        if let Some(native_function) = INTERNAL_SYMBOLS_TABLE.get(atom.as_str()) {
-            return native_function.call(scope, &tail);
-        };
-
-        if let Some(value) = scope.get(atom.as_str()) {
+        let builtin = resolve_builtin(&scope, atom);
+        let evaluated = builtin
+            .and_then(special_form)
+            .map_or(Arguments::All, |form| form.evaluated);
+
+        // Arguments are evaluated by the caller, before the call is entered
+        let (caller, tail) = eval_arguments(scope, tail, evaluated)?;
+        let scope = caller.enter(atom.clone());
+
+        let result = if let Some(native_function) = builtin.and_then(|name| INTERNAL_SYMBOLS_TABLE.get(name)) {
+            // Internal functions
+            native_function.call(scope.clone(), &tail).map_err(|e| e.traced(&scope))
+        } else if let Some(value) = scope.get(atom.as_str()) {
             if let LispVal::Function {
                 parameters,
                 body,
                 applied,
+                captured,
+                ..
             } = value
             {
-                return eval_function(
+                eval_function(
                     scope.clone(),
                     parameters,
                     body,
+                    captured,
                     applied.iter().chain(tail.iter()).cloned().collect(),
-                );
+                )
+            } else if let LispVal::MultiMethod {
+                name,
+                dispatch,
+                methods,
+            } = value
+            {
+                multimethod::call_multimethod(scope.clone(), name, dispatch, methods, tail)
             } else {
-                return Err(EvalError::InvalidFunctionCall {
+                Err(EvalError::InvalidFunctionCall {
                     values: values.to_vec(),
-                });
+                })
             }
+        } else {
+            Err(EvalError::UnknownIdentifier(atom.to_string()))
         };
 
-        return Err(EvalError::UnknownIdentifier(atom.clone()));
+        return result.map(|(scope, value)| (scope.leave(&caller), value));
     };
 
     if let LispVal::Function {
         parameters,
         body,
         applied,
+        captured,
+        ..
     } = head
     {
         return eval_function(
-            scope.with_context("anonymous".to_string()),
+            scope.enter("anonymous"),
             parameters,
             body,
+            captured,
             applied.iter().chain(tail).cloned().collect(),
-        );
+        )
+        .map(|(result_scope, value)| (result_scope.leave(&scope), value));
     };
 
     Err(invoke_error())
 }
 
+/// Evaluates the arguments in `evaluated`, passing the others as written.
+fn eval_arguments(
+    scope: Scope,
+    tail: &[LispVal],
+    evaluated: Arguments,
+) -> Result<(Scope, Vec<LispVal>), EvalError> {
+    match evaluated {
+        Arguments::None => Ok((scope, tail.to_vec())),
+        Arguments::All => eval_tail(scope, tail),
+        _ => {
+            scope.runtime.count_list();
+            tail.iter().enumerate().try_fold(
+                (scope, Vec::new()),
+                |(scope, mut acc), (position, value)| {
+                    let (scope, value) = if evaluated.includes(position, tail.len()) {
+                        eval(scope, value).map_err(EvalError::outside_tail)?
+                    } else {
+                        (scope, value.clone())
+                    };
+                    acc.push(value);
+                    Ok((scope, acc))
+                },
+            )
+        }
+    }
+}
+
 fn eval_tail(scope: Scope, tail: &[LispVal]) -> Result<(Scope, Vec<LispVal>), EvalError> {
-    tail.into_iter()
+    scope.runtime.count_list();
+    tail.iter()
         .try_fold((scope, Vec::<LispVal>::new()), |(scope, mut acc), value| {
-            let (scope, value) = eval(scope, &value)?;
+            let (scope, value) = eval(scope, value).map_err(EvalError::outside_tail)?;
             Ok((scope, {
                 acc.push(value);
                 acc
@@ -471,22 +1076,74 @@ fn eval_tail(scope: Scope, tail: &[LispVal]) -> Result<(Scope, Vec<LispVal>), Ev
         })
 }
 
+/// Evaluates `expr`, returning the scope left behind by any definitions.
+///
+/// Definitions (`def!`, `defn!`, ...) follow one rule: they are visible to
+/// everything evaluated after them in the same frame. Arguments are evaluated
+/// left to right, so a definition made in one argument is seen by the later
+/// arguments and by the enclosing form, up to the top level. Function bodies
+/// run in their own frame, which is dropped when the call returns, so
+/// definitions made inside a function never leak to the caller.
+///
+/// Evaluation recurses on the native stack, which is extended on the heap
+/// whenever it runs low, so deeply nested expressions and deep recursion
+/// can't overflow it and crash the host.
 pub fn eval(scope: Scope, expr: &LispVal) -> EvalResult {
-    match expr {
+    stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || eval_expression(scope, expr))
+}
+
+/// How much stack must be left for `eval` to run on the current one. It
+/// covers the natives called between one `eval` and the next.
+const STACK_RED_ZONE: usize = 256 * 1024;
+/// The size of each stack segment allocated once the stack runs low.
+const STACK_SEGMENT_SIZE: usize = 4 * 1024 * 1024;
+
+fn eval_expression(scope: Scope, expr: &LispVal) -> EvalResult {
+    if scope.runtime.is_interrupted() {
+        return Err(EvalError::Interrupted);
+    }
+    scope.runtime.check_budget()?;
+
+    let runtime = scope.runtime.clone();
+    runtime.enter();
+
+    let result = match expr {
         LispVal::Symbol(atom) => match scope.get(atom.as_str()) {
             Some(value) => Ok((scope.clone(), value.clone())),
-            None => Err(EvalError::UnknownIdentifier(atom.clone())),
+            None if scope.settings.version.supports(Feature::FirstClassNatives)
+                && resolve_builtin(&scope, atom).is_some() =>
+            {
+                Ok((scope, expr.clone()))
+            }
+            None => Err(EvalError::UnknownIdentifier(atom.to_string())),
         },
         LispVal::List(elements) => eval_list(scope, elements),
-        LispVal::Unevaluated(value) => Ok((scope, *value.clone())),
+        LispVal::Vector(elements) => {
+            let (scope, values) = eval_tail(scope, elements)?;
+            Ok((scope, LispVal::List(values)))
+        }
+        LispVal::Map(entries) => map::eval_map_literal(scope, entries),
+        LispVal::Set(elements) => set::eval_set_literal(scope, elements),
+        // A quoted vector is data too: the list of its elements, unevaluated
+        LispVal::Unevaluated(value) => match value.as_ref() {
+            LispVal::Vector(values) => Ok((scope, LispVal::List(values.clone()))),
+            value => Ok((scope, value.clone())),
+        },
         _ => Ok((scope, expr.clone())),
+    };
+
+    // A `recur` is caught by its `loop!`, so it isn't where evaluation failed
+    if result.as_ref().is_err_and(|e| !matches!(e, EvalError::Recur { .. })) {
+        runtime.record_failure(expr);
     }
+    runtime.exit();
+    result
 }
 
 #[macro_export]
 macro_rules! eval_it {
     ($expr:expr) => {
-        crate::evaluation::eval(
+        $crate::evaluation::eval(
             $crate::evaluation::scope::Scope::default(),
             &parse_it!($expr),
         )
@@ -494,7 +1151,7 @@ macro_rules! eval_it {
         .1
     };
     ($expr:expr, $scope:expr) => {
-        crate::evaluation::eval($scope, &parse_it!($expr))
+        $crate::evaluation::eval($scope, &parse_it!($expr))
             .unwrap()
             .1
     };
@@ -502,13 +1159,32 @@ macro_rules! eval_it {
 
 #[cfg(test)]
 mod tests {
-    use crate::{parse_it, parsing::LispVal};
+    use crate::{
+        builder::{list, sym},
+        evaluation::{
+            call, eval,
+            scope::{Scope, Settings},
+        },
+        parse_it,
+        parsing::LispVal,
+    };
 
     #[test]
     fn test_math_expression() {
         assert_eq!(eval_it!("(+ 1 2)"), LispVal::Number(3));
     }
 
+    #[test]
+    fn test_initial_constants() {
+        assert_eq!(eval_it!("MIN_INT"), LispVal::Number(i64::MIN));
+        assert_eq!(eval_it!("MAX_INT"), LispVal::Number(i64::MAX));
+        assert_eq!(eval_it!("INT_BITS"), LispVal::Number(64));
+        assert_eq!(eval_it!("PI"), LispVal::Float(std::f64::consts::PI));
+        assert_eq!(eval_it!("E"), LispVal::Float(std::f64::consts::E));
+        assert_eq!(eval_it!("TAU"), LispVal::Float(std::f64::consts::TAU));
+        assert_eq!(eval_it!("(* 2 PI)"), eval_it!("TAU"));
+    }
+
     #[test]
     fn test_binding() {
         assert_eq!(
@@ -517,6 +1193,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_definitions_leak_to_later_siblings() {
+        let (scope, _) = eval(
+            Scope::default(),
+            &parse_it!("(list (def! x 1) (if! true (def! y (+ x 1)) (def! y 0)))"),
+        )
+        .unwrap();
+
+        assert_eq!(scope.get("x"), Some(&LispVal::Number(1)));
+        assert_eq!(scope.get("y"), Some(&LispVal::Number(2)));
+    }
+
+    #[test]
+    fn test_definitions_inside_functions_stay_local() {
+        let (scope, result) = eval(
+            Scope::default(),
+            &parse_it!("(list (defn! f (a) (list (def! inner a) inner)) (f 5))"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            result,
+            vec![LispVal::Void(), vec![LispVal::Void(), LispVal::Number(5)].into()].into()
+        );
+        assert_eq!(scope.get("inner"), None);
+        assert!(scope.get("f").is_some());
+    }
+
+    #[test]
+    fn test_constants_cannot_be_redefined() {
+        let (scope, _) = eval(Scope::default(), &parse_it!("(const! LIMIT 3)")).unwrap();
+
+        assert_eq!(eval_it!("(+ LIMIT 1)", scope.clone()), LispVal::Number(4));
+        assert!(eval(scope.clone(), &parse_it!("(def! LIMIT 4)")).is_err());
+        assert!(eval(scope.clone(), &parse_it!("(const! LIMIT 4)")).is_err());
+        assert!(eval(scope, &parse_it!("(list (defn! f (LIMIT) LIMIT) (f 1))")).is_err());
+    }
+
+    #[test]
+    fn test_errors_name_the_calls_leading_to_them() {
+        let (scope, _) = eval(
+            Scope::default(),
+            &parse_it!("(defn! my-fn (xs) (map (fn! (x) (+ x \"a\")) xs))"),
+        )
+        .unwrap();
+
+        let error = eval(scope.clone(), &parse_it!("(my-fn '(1 2))")).unwrap_err();
+        assert!(error.to_string().contains("`my-fn → map → +`"));
+
+        // Arguments are evaluated before the call they are passed to
+        let error = eval(scope.clone(), &parse_it!("(+ 1 (* 2 \"a\"))")).unwrap_err();
+        assert!(error.to_string().contains("`*`"));
+
+        let (after, _) = eval(scope.clone(), &parse_it!("(do! (def! a 1) (my-fn '()))")).unwrap();
+        assert_eq!(after.context, scope.context);
+        assert!(after.callers.is_empty());
+    }
+
+    #[test]
+    fn test_arithmetic_matches_general_path() {
+        let (scope, _) = eval(Scope::default(), &parse_it!("(def! x 5)")).unwrap();
+
+        assert_eq!(eval_it!("(+ x (* 2 (- x 1)))", scope.clone()), LispVal::Number(13));
+        assert_eq!(eval_it!("(+ x (len '(1 2)))", scope.clone()), LispVal::Number(7));
+        assert!(eval(scope.clone(), &parse_it!("(+ x \"a\")")).is_err());
+
+        let stats = eval_it!("(list (+ 1 (* 2 3)) (stats))", scope.with_runtime(Default::default()));
+        assert_eq!(
+            stats.to_string(),
+            "(7 #<stats evaluated_nodes: 7, function_calls: 3, max_depth: 4, list_allocations: 4>)"
+        );
+    }
+
+    #[test]
+    fn test_interrupted_evaluation() {
+        let scope = Scope::default();
+        scope.runtime.interrupt();
+
+        assert!(matches!(
+            eval(scope, &parse_it!("(+ 1 2)")),
+            Err(crate::evaluation::error::EvalError::Interrupted)
+        ));
+    }
+
     #[test]
     fn test_fold() {
         assert_eq!(eval_it!("(fold '+ 1 '(1 2 3))"), LispVal::Number(7));
@@ -542,6 +1302,139 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_closures_see_their_definition_scope() {
+        let scope = eval(
+            Scope::default(),
+            &parse_it!("(defn! counter-from (n) (fn! (step) (+ n step)))"),
+        )
+        .unwrap()
+        .0;
+
+        // The caller's `n` doesn't shadow the captured one
+        assert_eq!(
+            eval_it!("(do! (def! from-5 (counter-from 5)) (defn! run (n) (from-5 n)) (run 100))", scope.clone()),
+            LispVal::Number(105)
+        );
+        // Top-level names are looked up when called, so functions can recurse
+        assert_eq!(
+            eval_it!("(do! (defn! fact (n) (if! (< n 2) 1 (* n (fact (- n 1))))) (fact 5))", scope.clone()),
+            LispVal::Number(120)
+        );
+        // A function defined inside another one can still call itself
+        assert_eq!(
+            eval_it!("(do! (defn! outer (x) (do! (defn! down (n) (if! (< n 1) x (down (- n 1)))) (down 3))) (outer 7))", scope.clone()),
+            LispVal::Number(7)
+        );
+        // The caller's locals aren't visible to the function it calls
+        let result = eval(
+            scope,
+            &parse_it!("(do! (defn! f (z) (+ z y)) (defn! g (y) (f 1)) (g 5))"),
+        );
+        assert!(matches!(result, Err(crate::evaluation::error::EvalError::UnknownIdentifier(name)) if name == "y"));
+    }
+
+    #[test]
+    fn test_deep_recursion_does_not_overflow_the_stack() {
+        let scope = eval(
+            Scope::default(),
+            &parse_it!("(defn! depth (n) (if! (= n 0) 0 (+ 1 (depth (- n 1)))))"),
+        )
+        .unwrap()
+        .0;
+
+        assert_eq!(eval_it!("(depth 10000)", scope), LispVal::Number(10000));
+    }
+
+    #[test]
+    fn test_call() {
+        let scope = Scope::default();
+        let add2 = eval_it!("(+ 2)");
+        let len = sym("len");
+
+        assert_eq!(
+            call(scope.clone(), &add2, vec![LispVal::Number(1)]).unwrap().1,
+            LispVal::Number(3)
+        );
+        assert_eq!(call(scope.clone(), &add2, vec![]).unwrap().1, add2);
+        // List arguments are passed as values, not evaluated as calls
+        assert_eq!(
+            call(scope.clone(), &len, vec![list([1, 2])]).unwrap().1,
+            LispVal::Number(2)
+        );
+        assert!(call(scope, &LispVal::Number(1), vec![]).is_err());
+    }
+
+    #[test]
+    fn test_map_over_lists() {
+        assert_eq!(eval_it!("(map 'len '((1) (1 2)))"), list([1, 2]));
+        assert_eq!(eval_it!("(fold 'concat '() '((1) (2)))"), list([1, 2]));
+    }
+
+    #[test]
+    fn test_case_insensitive_builtins() {
+        let scope = Scope::default().with_settings(Settings {
+            case_insensitive: true,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            eval_it!("(MAp (ADD 1) '(1 2))", scope),
+            vec![LispVal::Number(2), LispVal::Number(3)].into()
+        );
+        assert!(eval(Scope::default(), &parse_it!("(MAp (+ 1) '(1 2))")).is_err());
+    }
+
+    #[test]
+    fn test_builtin_aliases() {
+        let scope = Scope::default();
+        scope.runtime.set_alias("≤".to_string(), "<=");
+        scope.runtime.set_alias("×".to_string(), "*");
+
+        assert_eq!(
+            eval_it!("(list (≤ 1 2) (fold '× 1 '(2 3 4)))", scope),
+            vec![LispVal::Boolean(true), LispVal::Number(24)].into()
+        );
+        assert!(eval(Scope::default(), &parse_it!("(≤ 1 2)")).is_err());
+    }
+
+    #[test]
+    fn test_records() {
+        assert_eq!(
+            eval_it!("(list (defrecord! point (x y)) (def! p (point 1 2)) (point-y p) (point? p) (point? 3))"),
+            vec![
+                LispVal::Void(),
+                LispVal::Void(),
+                LispVal::Number(2),
+                LispVal::Boolean(true),
+                LispVal::Boolean(false),
+            ]
+            .into()
+        );
+        assert!(eval(Scope::default(), &parse_it!("(list (defrecord! point (x y)) (point-x 1))")).is_err());
+    }
+
+    #[test]
+    fn test_multimethods() {
+        let scope = eval(
+            Scope::default(),
+            &parse_it!(
+                "(list
+                    (defrecord! square (side))
+                    (defrecord! rect (w h))
+                    (defmulti! area record_name)
+                    (defmethod! area 'square (s) (* (square-side s) (square-side s)))
+                    (defmethod! area 'rect (r) (* (rect-w r) (rect-h r))))"
+            ),
+        )
+        .unwrap()
+        .0;
+
+        assert_eq!(eval_it!("(area (square 3))", scope.clone()), LispVal::Number(9));
+        assert_eq!(eval_it!("(area (rect 2 5))", scope.clone()), LispVal::Number(10));
+        assert!(eval(scope, &parse_it!("(area 1)")).is_err());
+    }
+
     #[test]
     fn test_branching() {
         assert_eq!(
@@ -0,0 +1,128 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use crate::parsing::{parse_program, LispVal};
+
+use super::{error::EvalError, eval, scope::Scope, EvalResult};
+
+/// Finds the source of the module `load` and `require` are asked for, so
+/// hosts decide where modules come from: the disk, memory or the network.
+pub trait ModuleResolver: std::fmt::Debug + Send + Sync {
+    /// The source of the module `name`, or why it can't be found.
+    fn resolve(&self, name: &str) -> Result<String, String>;
+}
+
+/// Reads modules from files under `root`. Names without an extension get
+/// `.lisp` appended, so `(load "lib/math")` reads `root/lib/math.lisp`.
+#[derive(Debug)]
+pub struct FileResolver {
+    pub root: PathBuf,
+}
+
+impl FileResolver {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl ModuleResolver for FileResolver {
+    fn resolve(&self, name: &str) -> Result<String, String> {
+        let mut path = self.root.join(name);
+        if path.extension().is_none() {
+            path.set_extension("lisp");
+        }
+
+        std::fs::read_to_string(&path).map_err(|e| format!("cannot read {}: {e}", path.display()))
+    }
+}
+
+/// Asks each resolver in turn, returning the first module found, so a
+/// host can search several places.
+#[derive(Debug)]
+pub struct ChainResolver {
+    pub resolvers: Vec<Arc<dyn ModuleResolver>>,
+}
+
+impl ModuleResolver for ChainResolver {
+    fn resolve(&self, name: &str) -> Result<String, String> {
+        let mut reasons = Vec::new();
+        for resolver in &self.resolvers {
+            match resolver.resolve(name) {
+                Ok(source) => return Ok(source),
+                Err(reason) => reasons.push(reason),
+            }
+        }
+
+        Err(reasons.join("; "))
+    }
+}
+
+/// Serves modules from sources registered up front.
+#[derive(Debug, Default)]
+pub struct MemoryResolver {
+    modules: HashMap<String, String>,
+}
+
+impl MemoryResolver {
+    pub fn with_module(mut self, name: impl Into<String>, source: impl Into<String>) -> Self {
+        self.modules.insert(name.into(), source.into());
+        self
+    }
+}
+
+impl ModuleResolver for MemoryResolver {
+    fn resolve(&self, name: &str) -> Result<String, String> {
+        self.modules
+            .get(name)
+            .cloned()
+            .ok_or_else(|| "no such module".to_string())
+    }
+}
+
+fn module_name(scope: &Scope, values: &[LispVal]) -> Result<String, EvalError> {
+    values
+        .first()
+        .unwrap()
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(0, &scope.context))
+}
+
+fn invalid(name: &str, reason: String) -> EvalError {
+    EvalError::InvalidModule {
+        name: name.to_string(),
+        reason,
+    }
+}
+
+/// Evaluates every top-level form of the module in `scope`, keeping the
+/// definitions it makes.
+fn load(scope: Scope, name: &str) -> Result<Scope, EvalError> {
+    let resolver: Option<Arc<dyn ModuleResolver>> = scope.runtime.resolver();
+    let resolver = resolver.ok_or_else(|| invalid(name, "no module resolver is set".to_string()))?;
+    let source = resolver.resolve(name).map_err(|reason| invalid(name, reason))?;
+
+    let forms = parse_program(&source).map_err(|e| invalid(name, e.to_string()))?;
+
+    forms.iter().try_fold(scope, |scope, expr| Ok(eval(scope, expr)?.0))
+}
+
+/// `(load "name")` evaluates the module every time it is called.
+pub fn eval_load(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let name = module_name(&scope, values)?;
+
+    Ok((load(scope, &name)?, LispVal::Void()))
+}
+
+/// `(require "name")` evaluates the module only the first time it is
+/// required from the same runtime.
+pub fn eval_require(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let name = module_name(&scope, values)?;
+    if scope.runtime.is_loaded(&name) {
+        return Ok((scope, LispVal::Void()));
+    }
+
+    let scope = load(scope, &name)?;
+    scope.runtime.mark_loaded(name);
+
+    Ok((scope, LispVal::Void()))
+}
@@ -0,0 +1,100 @@
+use crate::parsing::LispVal;
+
+use super::{call, define, error::EvalError, eval, eval_function, scope::Scope, EvalResult};
+
+/// `(defmulti! area record_name)` binds `area` to a multimethod without
+/// methods, dispatching on the result of calling `record_name` with the
+/// call arguments. With a keyword, like `(defmulti! area :shape)`, it
+/// dispatches on that key of the first argument, a map or record.
+pub fn eval_multi_definition(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let context = scope.context.clone();
+    let name = values
+        .first()
+        .unwrap()
+        .as_symbol()
+        .map_err(EvalError::from_arg(0, &context))?
+        .to_string();
+
+    // Builtins are not bound in scope, so their names are kept as symbols
+    let (scope, dispatch) = match values.get(1).unwrap() {
+        symbol @ LispVal::Symbol(_) => (scope, symbol.clone()),
+        expr => eval(scope, expr).map_err(EvalError::outside_tail)?,
+    };
+
+    let multimethod = LispVal::MultiMethod {
+        name: name.clone(),
+        dispatch: Box::new(dispatch),
+        methods: Vec::new(),
+    };
+
+    Ok((define(&scope, name, multimethod)?, LispVal::Void()))
+}
+
+/// `(defmethod! area 'circle (c) (* 3 (circle-r c)))` adds (or replaces) the
+/// method used when the dispatch function returns `circle`.
+pub fn eval_method_definition(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let context = scope.context.clone();
+    let name = values
+        .first()
+        .unwrap()
+        .as_symbol()
+        .map_err(EvalError::from_arg(0, &context))?
+        .to_string();
+    let dispatch_value = values.get(1).unwrap().clone();
+    let (scope, method) = super::eval_function_value(scope, &values[2..])?;
+
+    let (dispatch, mut methods) = match scope.get(&name) {
+        Some(LispVal::MultiMethod {
+            dispatch, methods, ..
+        }) => (dispatch.clone(), methods.clone()),
+        Some(value) => {
+            return Err(EvalError::InvalidArgumentType {
+                name: context.to_string(),
+                expected: crate::parsing::LispType::Function,
+                got: value.to_type(),
+                position: 0,
+            })
+        }
+        None => return Err(EvalError::UnknownIdentifier(name)),
+    };
+
+    methods.retain(|(value, _)| *value != dispatch_value);
+    methods.push((dispatch_value, method));
+
+    let multimethod = LispVal::MultiMethod {
+        name: name.clone(),
+        dispatch,
+        methods,
+    };
+
+    Ok((define(&scope, name, multimethod)?, LispVal::Void()))
+}
+
+pub fn call_multimethod(
+    scope: Scope,
+    name: &str,
+    dispatch: &LispVal,
+    methods: &[(LispVal, LispVal)],
+    arguments: Vec<LispVal>,
+) -> EvalResult {
+    let (scope, dispatch_value) = match dispatch {
+        LispVal::Keyword(key) => {
+            let value = arguments.first().and_then(|argument| argument.get(key)).cloned();
+            (scope, value.unwrap_or(LispVal::Void()))
+        }
+        _ => call(scope.enter(name.to_string()), dispatch, arguments.clone())?,
+    };
+
+    match methods.iter().find(|(value, _)| *value == dispatch_value) {
+        Some((
+            _,
+            LispVal::Function {
+                parameters, body, captured, ..
+            },
+        )) => eval_function(scope, parameters, body, captured, arguments),
+        _ => Err(EvalError::NoMethod {
+            name: name.to_string(),
+            dispatch_value,
+        }),
+    }
+}
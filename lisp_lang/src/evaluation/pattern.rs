@@ -0,0 +1,98 @@
+use crate::parsing::{LispType, LispVal};
+
+use super::{binding::eval_body, bind, error::EvalError, scope::Scope, EvalResult};
+
+/// The first `count` elements of a list or dotted pair, and what follows
+/// them, or `None` when it has fewer elements.
+fn split_elements(value: &LispVal, count: usize) -> Option<(Vec<&LispVal>, LispVal)> {
+    match value {
+        LispVal::List(values) if values.len() >= count => {
+            Some((values[..count].iter().collect(), LispVal::List(values[count..].to_vec())))
+        }
+        LispVal::Pair(..) => {
+            let (heads, tail) = value.pair_parts();
+            if heads.len() < count {
+                return None;
+            }
+            let rest = heads[count..]
+                .iter()
+                .rev()
+                .fold(tail.clone(), |rest, head| LispVal::cons((*head).clone(), rest));
+            Some((heads[..count].to_vec(), rest))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `value` matches `pattern`, adding the names the pattern binds to
+/// `bound`. `bound` may have names from a partial match when it doesn't.
+fn matches(pattern: &LispVal, value: &LispVal, bound: &mut Vec<(String, LispVal)>) -> bool {
+    match pattern {
+        LispVal::Symbol(name) if name == "_" => true,
+        LispVal::Symbol(name) => {
+            bound.push((name.to_string(), value.clone()));
+            true
+        }
+        LispVal::Unevaluated(literal) => **literal == *value,
+        LispVal::List(patterns) | LispVal::Vector(patterns) => match value {
+            LispVal::List(values) if values.len() == patterns.len() => {
+                patterns.iter().zip(values).all(|(pattern, value)| matches(pattern, value, bound))
+            }
+            _ => false,
+        },
+        LispVal::Pair(..) => {
+            let (patterns, rest_pattern) = pattern.pair_parts();
+            match split_elements(value, patterns.len()) {
+                Some((values, rest)) => {
+                    patterns.iter().zip(values).all(|(pattern, value)| matches(pattern, value, bound))
+                        && matches(rest_pattern, &rest, bound)
+                }
+                None => false,
+            }
+        }
+        literal => literal == value,
+    }
+}
+
+/// `(match! value (pattern body...) ...)` evaluates the body of the first
+/// clause whose pattern matches `value`, with the names in the pattern
+/// bound to the parts of `value` they matched. Patterns are:
+///
+/// - `_`, which matches anything;
+/// - a name, which matches anything and binds it;
+/// - a number, string, keyword, boolean or `nil`, or a quoted value like
+///   `'done`, which match values equal to them;
+/// - a list of patterns, which matches lists of as many elements, each
+///   matching its pattern, and `(first second . rest)`, which matches lists
+///   of at least as many elements, with `rest` matching the rest.
+pub fn eval_match(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let name = scope.context.clone();
+    let value = values.first().unwrap();
+
+    for (position, clause) in values.iter().enumerate().skip(1) {
+        let (pattern, body) = match clause {
+            LispVal::List(clause) if !clause.is_empty() => (&clause[0], &clause[1..]),
+            clause => {
+                return Err(EvalError::InvalidArgumentType {
+                    name: name.to_string(),
+                    expected: LispType::List,
+                    got: clause.to_type(),
+                    position,
+                })
+            }
+        };
+
+        let mut bound = Vec::new();
+        if !matches(pattern, value, &mut bound) {
+            continue;
+        }
+
+        let frame = bound
+            .into_iter()
+            .try_fold(scope.child(), |frame, (name, value)| bind(&frame, name, value))?;
+
+        return eval_body(scope, frame, body);
+    }
+
+    Err(EvalError::NoMatch { name: name.to_string(), value: value.clone() })
+}
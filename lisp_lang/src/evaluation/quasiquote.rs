@@ -0,0 +1,113 @@
+use crate::{
+    builder::call,
+    parsing::{LispVal, MapEntries, SetElements},
+};
+
+use super::{error::EvalError, eval, scope::Scope, EvalResult};
+
+/// The name and argument of a form like `(unquote x)`.
+fn form(value: &LispVal) -> Option<(&str, &LispVal)> {
+    match value {
+        LispVal::List(values) => match values.as_slice() {
+            [LispVal::Symbol(name), argument] => Some((name.as_str(), argument)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The elements of a template list or vector, with `(unquote-splicing x)`
+/// at the current level replaced by the elements of `x`.
+fn expand_elements(scope: Scope, elements: &[LispVal], depth: usize) -> Result<(Scope, Vec<LispVal>), EvalError> {
+    elements.iter().try_fold((scope, Vec::new()), |(scope, mut expanded), element| {
+        match form(element) {
+            Some(("unquote-splicing", spliced)) if depth == 1 => {
+                let (scope, value) = eval(scope, spliced).map_err(EvalError::outside_tail)?;
+                let values: Vec<LispVal> = value.try_into().map_err(EvalError::from_arg(0, "unquote-splicing"))?;
+                expanded.extend(values);
+                Ok((scope, expanded))
+            }
+            _ => {
+                let (scope, value) = expand(scope, element, depth)?;
+                expanded.push(value);
+                Ok((scope, expanded))
+            }
+        }
+    })
+}
+
+/// Copies `template`, evaluating what is unquoted at `depth` 1. Nested
+/// quasiquotes go one level deeper and their unquotes are kept as written,
+/// so templates can write templates. Splicing outside a list evaluates
+/// like an unquote.
+fn expand(scope: Scope, template: &LispVal, depth: usize) -> EvalResult {
+    match form(template) {
+        Some(("unquote" | "unquote-splicing", value)) if depth == 1 => {
+            return eval(scope, value).map_err(EvalError::outside_tail)
+        }
+        Some((name @ ("unquote" | "unquote-splicing"), value)) => {
+            let (scope, value) = expand(scope, value, depth - 1)?;
+            return Ok((scope, call(name, [value])));
+        }
+        Some(("quasiquote", inner)) => {
+            let (scope, value) = expand(scope, inner, depth + 1)?;
+            return Ok((scope, call("quasiquote", [value])));
+        }
+        _ => {}
+    }
+
+    match template {
+        LispVal::List(elements) => {
+            let (scope, elements) = expand_elements(scope, elements, depth)?;
+            Ok((scope, LispVal::List(elements)))
+        }
+        LispVal::Vector(elements) => {
+            let (scope, elements) = expand_elements(scope, elements, depth)?;
+            Ok((scope, LispVal::Vector(elements)))
+        }
+        LispVal::Map(entries) => {
+            let (scope, entries) = entries.iter().try_fold(
+                (scope, MapEntries::new()),
+                |(scope, entries), (key, value)| {
+                    let (scope, key) = expand(scope, key, depth)?;
+                    let (scope, value) = expand(scope, value, depth)?;
+                    Ok::<_, EvalError>((scope, entries.update(key, value)))
+                },
+            )?;
+            Ok((scope, LispVal::Map(entries)))
+        }
+        LispVal::Set(elements) => {
+            let (scope, elements) = elements.iter().try_fold(
+                (scope, SetElements::new()),
+                |(scope, elements), element| {
+                    let (scope, element) = expand(scope, element, depth)?;
+                    Ok::<_, EvalError>((scope, elements.update(element)))
+                },
+            )?;
+            Ok((scope, LispVal::Set(elements)))
+        }
+        LispVal::Pair(head, tail) => {
+            let (scope, head) = expand(scope, head, depth)?;
+            let (scope, tail) = expand(scope, tail, depth)?;
+            Ok((scope, LispVal::cons(head, tail)))
+        }
+        LispVal::Unevaluated(value) => {
+            let (scope, value) = expand(scope, value, depth)?;
+            Ok((scope, value.to_unevaluated()))
+        }
+        _ => Ok((scope, template.clone())),
+    }
+}
+
+/// `` `template `` is `template` as written, like `'template`, except for
+/// the parts marked `,value`, which are evaluated, and `,@list`, whose
+/// elements are spliced into the surrounding list. A template that is a
+/// vector is a list, like a quoted vector.
+pub fn eval_quasiquote(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let (scope, value) = expand(scope, values.first().unwrap(), 1)?;
+
+    match value {
+        LispVal::Vector(elements) => Ok((scope, LispVal::List(elements))),
+        value => Ok((scope, value)),
+    }
+}
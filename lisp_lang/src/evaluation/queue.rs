@@ -0,0 +1,149 @@
+use crate::parsing::{LispType, LispVal};
+
+use super::{error::EvalError, list_argument, scope::Scope, EvalResult};
+
+type Entries = im::OrdMap<(i64, u64), LispVal>;
+
+fn expected(values: &[LispVal], position: usize, context: &str, expected: LispType) -> EvalError {
+    EvalError::InvalidArgumentType {
+        name: context.to_string(),
+        expected,
+        got: values.get(position).unwrap().to_type(),
+        position,
+    }
+}
+
+fn heap_argument(values: &[LispVal], position: usize, context: &str) -> Result<(Entries, u64), EvalError> {
+    match values.get(position).unwrap() {
+        LispVal::Heap { entries, next } => Ok((entries.clone(), *next)),
+        _ => Err(expected(values, position, context, LispType::Heap)),
+    }
+}
+
+fn deque_argument(values: &[LispVal], position: usize, context: &str) -> Result<im::Vector<LispVal>, EvalError> {
+    match values.get(position).unwrap() {
+        LispVal::Deque(deque) => Ok(deque.clone()),
+        _ => Err(expected(values, position, context, LispType::Deque)),
+    }
+}
+
+fn empty(context: &str) -> EvalError {
+    EvalError::EmptyList {
+        name: context.to_string(),
+    }
+}
+
+/// `(heap)` is an empty min-priority queue.
+pub fn eval_heap(scope: Scope, _: &[LispVal]) -> EvalResult {
+    let heap = LispVal::Heap {
+        entries: im::OrdMap::new(),
+        next: 0,
+    };
+
+    Ok((scope, heap))
+}
+
+/// `(heap-push heap value priority)` adds `value`; lower priorities pop first.
+pub fn eval_heap_push(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let (entries, next) = heap_argument(values, 0, &scope.context)?;
+    let priority: i64 = values
+        .get(2)
+        .unwrap()
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(2, &scope.context))?;
+
+    let heap = LispVal::Heap {
+        entries: entries.update((priority, next), values.get(1).unwrap().clone()),
+        next: next + 1,
+    };
+
+    Ok((scope, heap))
+}
+
+/// `(heap-peek heap)` is the value with the lowest priority.
+pub fn eval_heap_peek(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let (entries, _) = heap_argument(values, 0, &scope.context)?;
+    let (_, value) = entries.get_min().ok_or_else(|| empty(&scope.context))?;
+
+    let value = value.clone();
+
+    Ok((scope, value))
+}
+
+/// `(heap-pop heap)` is the heap without its lowest priority value.
+pub fn eval_heap_pop(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let (entries, next) = heap_argument(values, 0, &scope.context)?;
+    if entries.is_empty() {
+        return Err(empty(&scope.context));
+    }
+    let (_, entries) = entries.without_min();
+
+    Ok((scope, LispVal::Heap { entries, next }))
+}
+
+/// `(deque list)` is a deque holding the elements of `list`.
+pub fn eval_deque(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let list = list_argument(values, 0, &scope.context)?;
+
+    Ok((scope, LispVal::Deque(list.into_iter().collect())))
+}
+
+pub fn eval_push_front(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let mut deque = deque_argument(values, 0, &scope.context)?;
+    deque.push_front(values.get(1).unwrap().clone());
+
+    Ok((scope, LispVal::Deque(deque)))
+}
+
+pub fn eval_push_back(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let mut deque = deque_argument(values, 0, &scope.context)?;
+    deque.push_back(values.get(1).unwrap().clone());
+
+    Ok((scope, LispVal::Deque(deque)))
+}
+
+/// `(peek-front deque)` is the first element.
+pub fn eval_peek_front(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let deque = deque_argument(values, 0, &scope.context)?;
+    let value = deque.front().cloned().ok_or_else(|| empty(&scope.context))?;
+
+    Ok((scope, value))
+}
+
+/// `(peek-back deque)` is the last element.
+pub fn eval_peek_back(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let deque = deque_argument(values, 0, &scope.context)?;
+    let value = deque.back().cloned().ok_or_else(|| empty(&scope.context))?;
+
+    Ok((scope, value))
+}
+
+/// `(pop-front deque)` is the deque without its first element.
+pub fn eval_pop_front(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let mut deque = deque_argument(values, 0, &scope.context)?;
+    deque.pop_front().ok_or_else(|| empty(&scope.context))?;
+
+    Ok((scope, LispVal::Deque(deque)))
+}
+
+/// `(pop-back deque)` is the deque without its last element.
+pub fn eval_pop_back(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let mut deque = deque_argument(values, 0, &scope.context)?;
+    deque.pop_back().ok_or_else(|| empty(&scope.context))?;
+
+    Ok((scope, LispVal::Deque(deque)))
+}
+
+/// `(to_list collection)` lists the values of a heap in priority order or
+/// of a deque front to back. Lists are returned as they are.
+pub fn eval_to_list(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let list: LispVal = match values.first().unwrap() {
+        LispVal::Heap { entries, .. } => entries.values().cloned().collect(),
+        LispVal::Deque(deque) => deque.iter().cloned().collect(),
+        list @ LispVal::List(_) => list.clone(),
+        _ => return Err(expected(values, 0, &scope.context, LispType::List)),
+    };
+
+    Ok((scope, list))
+}
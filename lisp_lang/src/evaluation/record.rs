@@ -0,0 +1,161 @@
+use crate::{
+    builder::{call, list, quote, sym},
+    parsing::{LispType, LispVal},
+};
+
+use super::{define, error::EvalError, scope::Scope, EvalResult};
+
+fn function(parameters: Vec<String>, body: LispVal) -> LispVal {
+    LispVal::Function {
+        parameters,
+        body: Box::new(body),
+        applied: Vec::new(),
+        doc: None,
+        captured: Default::default(),
+    }
+}
+
+/// `(defrecord! point (x y))` binds the constructor `point`, the predicate
+/// `point?` and one `point-<field>` accessor per field.
+pub fn eval_record_definition(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let context = scope.context.clone();
+    let name = values
+        .first()
+        .unwrap()
+        .as_symbol()
+        .map_err(EvalError::from_arg(0, &context))?
+        .to_string();
+    let field_values: Vec<LispVal> = values
+        .get(1)
+        .unwrap()
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(1, &context))?;
+    let fields = field_values
+        .iter()
+        .map(|v| {
+            v.as_symbol()
+                .map(|v| v.to_string())
+                .map_err(EvalError::from_arg(1, &context))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let tag = quote(sym(&name));
+    let field_names = || fields.iter().map(|f| sym(f));
+    let constructor = function(
+        fields.clone(),
+        call(
+            "make_record",
+            [tag.clone(), quote(list(field_names())), call("list", field_names())],
+        ),
+    );
+    let predicate = function(
+        vec!["value".to_string()],
+        call("is_record", [tag.clone(), sym("value")]),
+    );
+
+    let scope = define(&scope, name.clone(), constructor)?;
+    let scope = define(&scope, format!("{name}?"), predicate)?;
+    let scope = fields.iter().try_fold(scope, |scope, field| {
+        let accessor = function(
+            vec!["value".to_string()],
+            call("record_field", [tag.clone(), quote(sym(field)), sym("value")]),
+        );
+        define(&scope, format!("{name}-{field}"), accessor)
+    })?;
+
+    Ok((scope, LispVal::Void()))
+}
+
+pub fn eval_make_record(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let context = scope.context.clone();
+    let name = values
+        .first()
+        .unwrap()
+        .as_symbol()
+        .map_err(EvalError::from_arg(0, &context))?
+        .to_string();
+    let fields: Vec<LispVal> = values
+        .get(1)
+        .unwrap()
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(1, &context))?;
+    let field_values: Vec<LispVal> = values
+        .get(2)
+        .unwrap()
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(2, &context))?;
+    if field_values.len() != fields.len() {
+        return Err(EvalError::ArgumentCount {
+            name: context.to_string(),
+            expected: fields.len(),
+            got: field_values.len(),
+        });
+    }
+
+    let fields = fields
+        .iter()
+        .zip(field_values)
+        .map(|(field, value)| {
+            field
+                .as_symbol()
+                .map(|field| (field.to_string(), value))
+                .map_err(EvalError::from_arg(1, &context))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((scope, LispVal::Record { name, fields }))
+}
+
+pub fn eval_is_record(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let context = scope.context.clone();
+    let name = values
+        .first()
+        .unwrap()
+        .as_symbol()
+        .map_err(EvalError::from_arg(0, &context))?;
+    let is_record = matches!(values.get(1), Some(LispVal::Record { name: tag, .. }) if tag == name);
+
+    Ok((scope, is_record.into()))
+}
+
+pub fn eval_record_field(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let context = scope.context.clone();
+    let name = values
+        .first()
+        .unwrap()
+        .as_symbol()
+        .map_err(EvalError::from_arg(0, &context))?;
+    let field = values
+        .get(1)
+        .unwrap()
+        .as_symbol()
+        .map_err(EvalError::from_arg(1, &context))?;
+    let record = values.get(2).unwrap();
+
+    match record {
+        LispVal::Record { name: tag, fields } if tag == name => fields
+            .iter()
+            .find(|(f, _)| f == field)
+            .map(|(_, value)| (scope.clone(), value.clone()))
+            .ok_or_else(|| EvalError::UnknownIdentifier(format!("{name}-{field}"))),
+        _ => Err(EvalError::InvalidRecord {
+            expected: name.to_string(),
+            got: record.clone(),
+        }),
+    }
+}
+
+pub fn eval_record_name(scope: Scope, values: &[LispVal]) -> EvalResult {
+    match values.first().unwrap() {
+        LispVal::Record { name, .. } => Ok((scope, sym(name))),
+        value => Err(EvalError::InvalidArgumentType {
+            name: scope.context.to_string(),
+            expected: LispType::Record,
+            got: value.to_type(),
+            position: 0,
+        }),
+    }
+}
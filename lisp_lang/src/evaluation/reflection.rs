@@ -0,0 +1,146 @@
+use crate::{
+    builder::{list, sym},
+    parsing::{LispType, LispVal},
+};
+
+use super::{error::EvalError, scope::Scope, EvalResult};
+
+/// `(current-file)` is the path of the file being evaluated, or `void`
+/// outside of one.
+pub fn eval_current_file(scope: Scope, _: &[LispVal]) -> EvalResult {
+    let file = match scope.runtime.location() {
+        Some((path, _)) => LispVal::String(path.into()),
+        None => LispVal::Void(),
+    };
+
+    Ok((scope, file))
+}
+
+/// `(current-line)` is the line where the top-level form being evaluated
+/// starts, or `void` outside of a file.
+pub fn eval_current_line(scope: Scope, _: &[LispVal]) -> EvalResult {
+    let line = match scope.runtime.location() {
+        Some((_, line)) => LispVal::Number(line as i64),
+        None => LispVal::Void(),
+    };
+
+    Ok((scope, line))
+}
+
+/// `(source 'inc)` rebuilds the definition of a user function as a list,
+/// `(defn! inc (x) (+ x 1))`. Given the function itself it returns the
+/// `fn!` form, as the name is not known.
+pub fn eval_source(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let argument = values.first().unwrap();
+    let (name, function) = match argument {
+        LispVal::Symbol(name) => match scope.get(name) {
+            Some(function) => (Some(name.as_str()), function),
+            None => return Err(EvalError::UnknownIdentifier(name.to_string())),
+        },
+        function => (None, function),
+    };
+
+    let LispVal::Function { parameters, body, doc, .. } = function else {
+        return Err(EvalError::InvalidArgumentType {
+            name: scope.context.to_string(),
+            expected: LispType::Function,
+            got: function.to_type(),
+            position: 0,
+        });
+    };
+
+    let parameters = list(parameters.iter().map(|p| sym(p)));
+    let definition = match (name, doc) {
+        (Some(name), Some(doc)) => list([sym("defn!"), sym(name), parameters, doc.as_str().into(), *body.clone()]),
+        (Some(name), None) => list([sym("defn!"), sym(name), parameters, *body.clone()]),
+        (None, _) => list([sym("fn!"), parameters, *body.clone()]),
+    };
+
+    Ok((scope, definition))
+}
+
+/// `(doc add)` is the docstring `add` was defined with, as in
+/// `(defn! add (a b) "Adds two numbers." (+ a b))`, or `void` when it has
+/// none.
+pub fn eval_doc(scope: Scope, values: &[LispVal]) -> EvalResult {
+    match values.first().unwrap() {
+        LispVal::Function { doc, .. } => {
+            let doc = doc.as_deref().map_or(LispVal::Void(), |doc| LispVal::String(doc.into()));
+            Ok((scope, doc))
+        }
+        LispVal::MultiMethod { .. } => Ok((scope, LispVal::Void())),
+        value => Err(EvalError::InvalidArgumentType {
+            name: scope.context.to_string(),
+            expected: LispType::Function,
+            got: value.to_type(),
+            position: 0,
+        }),
+    }
+}
+
+/// How many elements `(describe v)` shows from the start of a collection.
+const DESCRIBED_ELEMENTS: usize = 3;
+
+/// `(describe v)` is a `description` record summarizing any value: its type,
+/// the length and first elements of collections, the remaining parameters
+/// of functions, and the `min`, `max` and `mean` of lists of integers and
+/// floats. `min` and `max` are elements of the list, while the mean is
+/// always a float.
+pub fn eval_describe(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let value = values.first().unwrap();
+    let mut fields = vec![("type".to_string(), sym(&value.to_type().to_string()))];
+    let mut field = |name: &str, value: LispVal| fields.push((name.to_string(), value));
+
+    let elements: Option<Vec<&LispVal>> = match value {
+        LispVal::List(values) => Some(values.iter().collect()),
+        LispVal::Deque(values) => Some(values.iter().collect()),
+        LispVal::Heap { entries, .. } => Some(entries.values().collect()),
+        _ => None,
+    };
+
+    match value {
+        LispVal::String(s) => field("length", LispVal::Number(s.chars().count() as i64)),
+        LispVal::Function { parameters, applied, .. } => {
+            let remaining = &parameters[applied.len().min(parameters.len())..];
+            field("arity", LispVal::Number(remaining.len() as i64));
+            field("parameters", list(remaining.iter().map(|p| sym(p))));
+        }
+        LispVal::Record { name, fields: record_fields } => {
+            field("name", sym(name));
+            field("fields", list(record_fields.iter().map(|(name, _)| sym(name))));
+        }
+        _ => {}
+    }
+
+    if let Some(elements) = elements {
+        field("length", LispVal::Number(elements.len() as i64));
+        field("first", list(elements.iter().take(DESCRIBED_ELEMENTS).map(|v| (*v).clone())));
+
+        let numbers: Option<Vec<(&LispVal, f64)>> = elements
+            .iter()
+            .map(|v| match v {
+                LispVal::Number(n) => Some((*v, *n as f64)),
+                LispVal::Float(x) => Some((*v, *x)),
+                _ => None,
+            })
+            .collect();
+        if let Some(numbers) = numbers.filter(|numbers| !numbers.is_empty()) {
+            // Integers compare exactly, even where they'd round to the same float
+            let compare = |(a, x): &&(&LispVal, f64), (b, y): &&(&LispVal, f64)| match (a, b) {
+                (LispVal::Number(a), LispVal::Number(b)) => a.cmp(b),
+                _ => x.total_cmp(y),
+            };
+            let sum: f64 = numbers.iter().map(|(_, x)| x).sum();
+            field("min", numbers.iter().min_by(compare).unwrap().0.clone());
+            field("max", numbers.iter().max_by(compare).unwrap().0.clone());
+            field("mean", LispVal::Float(sum / numbers.len() as f64));
+        }
+    }
+
+    let description = LispVal::Record {
+        name: "description".to_string(),
+        fields,
+    };
+
+    Ok((scope, description))
+}
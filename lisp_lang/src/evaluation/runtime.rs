@@ -0,0 +1,332 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::parsing::LispVal;
+
+use super::{
+    error::EvalError,
+    module::ModuleResolver,
+    store::HostStore,
+    task::{Task, DEFAULT_BUDGET},
+    warning::Warning,
+};
+
+thread_local! {
+    static EFFECTS: RefCell<Option<Vec<Effect>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` as a dry run on the current thread: the effects builtins would
+/// perform are recorded and returned instead. Other evaluations, even of
+/// scopes sharing a runtime, aren't affected.
+pub fn with_dry_run<T>(f: impl FnOnce() -> T) -> (T, Vec<Effect>) {
+    let previous = EFFECTS.replace(Some(Vec::new()));
+    let result = f();
+    let effects = EFFECTS.replace(previous).unwrap_or_default();
+    (result, effects)
+}
+
+/// Records `effect` when a dry run is active on the current thread. Returns
+/// whether the caller should skip performing it.
+pub fn intercept(effect: Effect) -> bool {
+    EFFECTS.with_borrow_mut(|effects| match effects {
+        Some(effects) => {
+            effects.push(effect);
+            true
+        }
+        None => false,
+    })
+}
+
+/// Evaluation state shared by every frame derived from the same root scope:
+/// counters for metrics, the warnings
+/// raised so far, the location being evaluated, the expression that failed,
+/// where modules come from, the host store, event handlers, scheduled tasks,
+/// builtin aliases and the interruption flag.
+#[derive(Debug, Default)]
+pub struct Runtime {
+    evaluated_nodes: AtomicUsize,
+    function_calls: AtomicUsize,
+    depth: AtomicUsize,
+    max_depth: AtomicUsize,
+    list_allocations: AtomicUsize,
+    /// Set from another thread to abort the running evaluation.
+    interrupted: AtomicBool,
+    /// Warnings raised since the host last took them.
+    warnings: Mutex<Vec<Warning>>,
+    /// File and line of the top-level form being evaluated, if it comes from a file.
+    location: Mutex<Option<(String, usize)>>,
+    /// The innermost expression that failed to evaluate since the last
+    /// top-level evaluation started.
+    failure: Mutex<Option<LispVal>>,
+    /// Where `load` and `require` find modules. Without one they fail.
+    resolver: Mutex<Option<Arc<dyn ModuleResolver>>>,
+    /// Modules evaluated by `require`, which won't evaluate them again.
+    loaded: Mutex<HashSet<String>>,
+    /// What `store-get` and `store-set!` read and write. Without one they fail.
+    store: Mutex<Option<Arc<dyn HostStore>>>,
+    /// Handlers registered with `on-event!`, by event name.
+    handlers: Mutex<HashMap<String, Vec<LispVal>>>,
+    /// Handler calls queued by emitted events, with their payloads.
+    pending: Mutex<VecDeque<(LispVal, LispVal)>>,
+    /// Tasks defined with `deftask!`, in the order `tick` runs them.
+    tasks: Mutex<Vec<Task>>,
+    /// Expressions each task may evaluate per tick, 0 for the default.
+    task_budget: AtomicUsize,
+    /// The task `tick` is running, and the evaluated node count at which it
+    /// runs out of budget.
+    task: Mutex<Option<(String, usize)>>,
+    /// Copy of the deadline in `task`, 0 when no task runs, so `eval` can
+    /// check it without locking.
+    deadline: AtomicUsize,
+    /// Alternative names for builtins, like `≤` for `<=`.
+    aliases: Mutex<HashMap<String, &'static str>>,
+}
+
+/// A side effect a builtin would perform.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Effect {
+    Print(String),
+    Bind(String),
+    ClearScope,
+    StoreSet(String),
+    Subscribe(String),
+    Schedule(String),
+}
+
+impl std::fmt::Display for Effect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Effect::Print(s) => write!(f, "would print {:?}", s),
+            Effect::Bind(name) => write!(f, "would bind `{}`", name),
+            Effect::ClearScope => write!(f, "would clear the scope"),
+            Effect::StoreSet(key) => write!(f, "would store `{}`", key),
+            Effect::Subscribe(event) => write!(f, "would handle `{}` events", event),
+            Effect::Schedule(task) => write!(f, "would schedule `{}`", task),
+        }
+    }
+}
+
+/// A snapshot of the [`Runtime`] counters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Expressions passed to `eval`.
+    pub evaluated_nodes: usize,
+    /// Calls to builtins and user functions.
+    pub function_calls: usize,
+    /// Deepest nesting of `eval` reached so far.
+    pub max_depth: usize,
+    /// Argument lists built to call functions and special forms.
+    pub list_allocations: usize,
+}
+
+impl Runtime {
+    pub fn enter(&self) {
+        self.evaluated_nodes.fetch_add(1, Ordering::Relaxed);
+        let depth = self.depth.fetch_add(1, Ordering::Relaxed) + 1;
+        self.max_depth.fetch_max(depth, Ordering::Relaxed);
+        if depth == 1 {
+            *self.failure.lock().unwrap() = None;
+        }
+    }
+
+    pub fn exit(&self) {
+        self.depth.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn count_call(&self) {
+        self.function_calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count_list(&self) {
+        self.list_allocations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Asks the running evaluation to stop at the next expression.
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::Relaxed);
+    }
+
+    pub fn clear_interrupt(&self) {
+        self.interrupted.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::Relaxed)
+    }
+
+    pub fn warn(&self, warning: Warning) {
+        self.warnings.lock().unwrap().push(warning);
+    }
+
+    /// Returns the warnings raised since the last call, oldest first.
+    pub fn take_warnings(&self) -> Vec<Warning> {
+        std::mem::take(&mut *self.warnings.lock().unwrap())
+    }
+
+    pub fn set_location(&self, location: Option<(String, usize)>) {
+        *self.location.lock().unwrap() = location;
+    }
+
+    pub fn location(&self) -> Option<(String, usize)> {
+        self.location.lock().unwrap().clone()
+    }
+
+    /// Remembers `expr` as the expression that failed, unless one it
+    /// contains failed first.
+    pub fn record_failure(&self, expr: &LispVal) {
+        self.failure.lock().unwrap().get_or_insert_with(|| expr.clone());
+    }
+
+    /// The innermost expression that failed during the last top-level
+    /// evaluation, to find in the [`Spans`](crate::parsing::Spans) of the
+    /// parsed input.
+    pub fn failure(&self) -> Option<LispVal> {
+        self.failure.lock().unwrap().clone()
+    }
+
+    pub fn set_resolver(&self, resolver: Arc<dyn ModuleResolver>) {
+        *self.resolver.lock().unwrap() = Some(resolver);
+    }
+
+    pub fn resolver(&self) -> Option<Arc<dyn ModuleResolver>> {
+        self.resolver.lock().unwrap().clone()
+    }
+
+    pub fn set_store(&self, store: Arc<dyn HostStore>) {
+        *self.store.lock().unwrap() = Some(store);
+    }
+
+    pub fn store(&self) -> Option<Arc<dyn HostStore>> {
+        self.store.lock().unwrap().clone()
+    }
+
+    pub fn subscribe(&self, event: String, handler: LispVal) {
+        self.handlers.lock().unwrap().entry(event).or_default().push(handler);
+    }
+
+    /// Queues a call to every handler of `event`, returning how many were queued.
+    pub fn emit(&self, event: &str, payload: LispVal) -> usize {
+        let handlers = self.handlers.lock().unwrap();
+        let handlers = handlers.get(event).map(Vec::as_slice).unwrap_or_default();
+
+        let mut pending = self.pending.lock().unwrap();
+        pending.extend(handlers.iter().map(|handler| (handler.clone(), payload.clone())));
+        handlers.len()
+    }
+
+    /// The oldest queued handler call, with its payload.
+    pub fn next_pending(&self) -> Option<(LispVal, LispVal)> {
+        self.pending.lock().unwrap().pop_front()
+    }
+
+    /// Adds `task` to the end of the schedule, or in place of the task with
+    /// the same name.
+    pub fn schedule(&self, task: Task) {
+        let mut tasks = self.tasks.lock().unwrap();
+        match tasks.iter_mut().find(|scheduled| scheduled.name == task.name) {
+            Some(scheduled) => *scheduled = task,
+            None => tasks.push(task),
+        }
+    }
+
+    /// Removes every task from the schedule, for `tick` to run them.
+    pub fn take_tasks(&self) -> Vec<Task> {
+        std::mem::take(&mut *self.tasks.lock().unwrap())
+    }
+
+    /// Puts tasks that keep running back ahead of those defined while they
+    /// ran, unless they were redefined. Returns how many tasks are scheduled.
+    pub fn reschedule(&self, running: Vec<Task>) -> usize {
+        let mut tasks = self.tasks.lock().unwrap();
+        let defined = std::mem::take(&mut *tasks);
+
+        tasks.extend(
+            running
+                .into_iter()
+                .filter(|task| defined.iter().all(|defined| defined.name != task.name)),
+        );
+        tasks.extend(defined);
+        tasks.len()
+    }
+
+    pub fn set_task_budget(&self, budget: usize) {
+        self.task_budget.store(budget, Ordering::Relaxed);
+    }
+
+    pub fn task_budget(&self) -> usize {
+        match self.task_budget.load(Ordering::Relaxed) {
+            0 => DEFAULT_BUDGET,
+            budget => budget,
+        }
+    }
+
+    /// Starts counting the budget of `task`, returning the task it
+    /// interrupts, for [`Runtime::end_task`] to resume.
+    pub fn begin_task(&self, task: &str) -> Option<(String, usize)> {
+        let deadline = self.evaluated_nodes.load(Ordering::Relaxed) + self.task_budget();
+        self.deadline.store(deadline, Ordering::Relaxed);
+
+        self.task.lock().unwrap().replace((task.to_string(), deadline))
+    }
+
+    pub fn end_task(&self, previous: Option<(String, usize)>) {
+        let deadline = previous.as_ref().map_or(0, |(_, deadline)| *deadline);
+        self.deadline.store(deadline, Ordering::Relaxed);
+
+        *self.task.lock().unwrap() = previous;
+    }
+
+    /// Fails once the running task has evaluated more than its budget.
+    pub fn check_budget(&self) -> Result<(), EvalError> {
+        let deadline = self.deadline.load(Ordering::Relaxed);
+        if deadline == 0 || self.evaluated_nodes.load(Ordering::Relaxed) < deadline {
+            return Ok(());
+        }
+
+        let task = self.task.lock().unwrap().as_ref().map(|(task, _)| task.clone());
+        Err(EvalError::TaskBudget {
+            task: task.unwrap_or_default(),
+            budget: self.task_budget(),
+        })
+    }
+
+    /// Makes calls to `alias` call `builtin`.
+    pub fn set_alias(&self, alias: String, builtin: &'static str) {
+        self.aliases.lock().unwrap().insert(alias, builtin);
+    }
+
+    pub fn alias(&self, name: &str) -> Option<&'static str> {
+        self.aliases.lock().unwrap().get(name).copied()
+    }
+
+    pub fn is_loaded(&self, module: &str) -> bool {
+        self.loaded.lock().unwrap().contains(module)
+    }
+
+    pub fn mark_loaded(&self, module: String) {
+        self.loaded.lock().unwrap().insert(module);
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            evaluated_nodes: self.evaluated_nodes.load(Ordering::Relaxed),
+            function_calls: self.function_calls.load(Ordering::Relaxed),
+            max_depth: self.max_depth.load(Ordering::Relaxed),
+            list_allocations: self.list_allocations.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Runtimes are compared by identity: two scopes are only equal if they
+/// share the same counters.
+impl PartialEq for Runtime {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
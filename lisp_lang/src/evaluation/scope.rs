@@ -1,67 +1,287 @@
+use std::sync::Arc;
+
+use compact_str::CompactString;
 use lazy_static::lazy_static;
 
-use crate::parsing::LispVal;
+use crate::parsing::{Environment, LispVal};
+
+use super::{runtime::Runtime, special_form::special_form, version::LanguageVersion};
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Settings {
+    /// Resolve builtins regardless of case, so `MAp` calls `map`.
+    pub case_insensitive: bool,
+    pub version: LanguageVersion,
+}
+
+/// An environment frame. Bindings made in a frame only live as long as the
+/// frame, while lookups fall back to the parent chain, so child frames for
+/// function calls can be created and discarded without copying the outer
+/// bindings.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Scope {
-    pub context: String,
+    pub context: CompactString,
+    /// Contexts of the calls still in progress around `context`, outermost first.
+    pub callers: im::Vector<CompactString>,
     pub bindings: im::HashMap<String, LispVal>,
+    /// Names bound with `const!`, which no frame may rebind.
+    pub constants: im::HashSet<String>,
+    pub parent: Option<Arc<Scope>>,
+    pub settings: Settings,
+    pub runtime: Arc<Runtime>,
 }
 
 impl Scope {
     pub fn empty(context: String) -> Scope {
         Scope {
-            context,
+            context: context.into(),
+            callers: im::Vector::new(),
             bindings: im::HashMap::<String, LispVal>::new(),
+            constants: im::HashSet::new(),
+            parent: None,
+            settings: Settings::default(),
+            runtime: Arc::new(Runtime::default()),
+        }
+    }
+
+    /// A new empty frame whose lookups fall back to `self`.
+    pub fn child(&self) -> Scope {
+        Scope {
+            context: self.context.clone(),
+            callers: self.callers.clone(),
+            bindings: Environment::new(),
+            constants: self.constants.clone(),
+            parent: Some(Arc::new(self.clone())),
+            settings: self.settings,
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn parent(&self) -> Option<&Scope> {
+        self.parent.as_deref()
+    }
+
+    pub fn with_context(&self, context: impl Into<CompactString>) -> Scope {
+        Scope {
+            context: context.into(),
+            ..self.clone()
+        }
+    }
+
+    /// Enters a call to `context`, remembering the current context as its caller.
+    pub fn enter(&self, context: impl Into<CompactString>) -> Scope {
+        let mut callers = self.callers.clone();
+        callers.push_back(self.context.clone());
+
+        Scope {
+            context: context.into(),
+            callers,
+            ..self.clone()
         }
     }
 
-    pub fn default() -> Scope {
-        INITIAL_SCOPE.clone()
+    /// Returns to the context and callers of `caller`, keeping everything else.
+    pub fn leave(&self, caller: &Scope) -> Scope {
+        Scope {
+            context: caller.context.clone(),
+            callers: caller.callers.clone(),
+            ..self.clone()
+        }
     }
 
-    pub fn with_context(&self, context: String) -> Scope {
+    /// The calls leading to the current context, like `my-fn → map → +`.
+    /// The main context and special forms are left out, since they aren't
+    /// calls the user wrote.
+    pub fn trace(&self) -> String {
+        self.callers
+            .iter()
+            .filter(|caller| caller.as_str() != MAIN_CONTEXT && special_form(caller).is_none())
+            .chain(std::iter::once(&self.context))
+            .map(CompactString::as_str)
+            .collect::<Vec<_>>()
+            .join(" → ")
+    }
+
+    pub fn with_settings(&self, settings: Settings) -> Scope {
         Scope {
-            context,
-            bindings: self.bindings.clone(),
+            settings,
+            ..self.clone()
         }
     }
 
+    pub fn with_runtime(&self, runtime: Arc<Runtime>) -> Scope {
+        Scope {
+            runtime,
+            ..self.clone()
+        }
+    }
+
+    /// Binds `name` in the current frame, shadowing any outer binding.
     pub fn bind(&self, name: String, value: LispVal) -> Scope {
         Scope {
-            context: self.context.clone(),
             bindings: self.bindings.update(name, value),
+            ..self.clone()
+        }
+    }
+
+    pub fn bind_constant(&self, name: String, value: LispVal) -> Scope {
+        Scope {
+            constants: self.constants.update(name.clone()),
+            ..self.bind(name, value)
         }
     }
 
+    /// The significant digits `*float-precision*` asks floats to be printed
+    /// with, or `None` to print them in full.
+    pub fn float_precision(&self) -> Option<usize> {
+        match self.get(FLOAT_PRECISION_BINDING) {
+            Some(LispVal::Number(digits)) if *digits > 0 => Some(*digits as usize),
+            _ => None,
+        }
+    }
+
+    pub fn is_constant(&self, name: &str) -> bool {
+        self.constants.contains(name)
+    }
+
+    /// This frame and its parents, innermost first. Frames are walked in a
+    /// loop rather than recursively, as deep recursion nests one per call.
+    fn frames(&self) -> impl Iterator<Item = &Scope> {
+        std::iter::successors(Some(self), |frame| frame.parent())
+    }
+
     pub fn get(&self, name: &str) -> Option<&LispVal> {
-        self.bindings.get(name)
+        self.frames().find_map(|frame| frame.bindings.get(name))
+    }
+
+    /// Every binding visible from this frame, with inner frames shadowing outer ones.
+    pub fn visible_bindings(&self) -> im::HashMap<String, LispVal> {
+        self.frames()
+            .fold(im::HashMap::new(), |visible, frame| visible.union(frame.bindings.clone()))
+    }
+
+    /// The bindings visible from this frame that a function created here
+    /// closes over: those of every frame but the outermost. Top-level
+    /// definitions are left out, so functions see them as they are when
+    /// called, which lets them call themselves and functions defined later.
+    pub fn captured(&self) -> Environment {
+        self.frames()
+            .filter(|frame| frame.parent().is_some())
+            .fold(Environment::new(), |captured, frame| captured.union(frame.bindings.clone()))
+    }
+
+    /// The frame of a call to a function that captured `bindings`: a new
+    /// frame starting out with them, whose lookups fall back to the
+    /// outermost frame, with its top-level definitions, but not to the
+    /// frames of the caller.
+    pub fn closure(&self, bindings: &Environment) -> Scope {
+        let root = self.frames().last().unwrap_or(self);
+        Scope {
+            bindings: bindings.clone(),
+            constants: root.constants.clone(),
+            parent: Some(Arc::new(root.clone())),
+            ..self.clone()
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = String> {
+        self.visible_bindings().into_iter().map(|(name, _)| name)
     }
 
     pub fn is_empty(&self) -> bool {
-        self.bindings.is_empty()
+        self.frames().all(|frame| frame.bindings.is_empty())
+    }
+}
+
+/// The initial scope, with a runtime of its own so metrics, warnings and
+/// handlers don't leak between unrelated scopes.
+impl Default for Scope {
+    fn default() -> Self {
+        INITIAL_SCOPE.with_runtime(Arc::new(Runtime::default()))
     }
 }
 
 pub const MAIN_CONTEXT: &str = "main";
+/// Binding with the significant digits floats are printed with, when set
+/// to a positive number.
+pub const FLOAT_PRECISION_BINDING: &str = "*float-precision*";
 
+/// Builds a [`Scope`] in the main context from `name = value` pairs.
+///
+/// Bindings are applied in source order, so when a name appears twice the
+/// later value shadows the earlier one. Values go through `Into<LispVal>`,
+/// which lets embedders write plain Rust values:
+///
+/// ```
+/// use lisp_lang::{scope, parsing::LispVal};
+///
+/// let scope = scope! { port = 8080, verbose = true, port = 9090 };
+/// assert_eq!(scope.get("port"), Some(&LispVal::Number(9090)));
+/// ```
 #[macro_export]
-macro_rules! lisp_scope {
-    // Base case
-    () => {
-        Scope::empty(MAIN_CONTEXT.to_string())
-    };
-    // Recursive case
-    ($name:ident = $value:expr, $($rest:tt)*) => {
-        lisp_scope!($($rest)*).bind(stringify!($name).to_string(), $value)
-    };
+macro_rules! scope {
+    ($($name:ident = $value:expr),* $(,)?) => {{
+        let scope = $crate::evaluation::scope::Scope::empty(
+            $crate::evaluation::scope::MAIN_CONTEXT.to_string(),
+        );
+        $(let scope = scope.bind(stringify!($name).to_string(), $value.into());)*
+        scope
+    }};
 }
 
 lazy_static! {
-    pub static ref INITIAL_SCOPE: Scope = lisp_scope!{
-        MIN_INT = LispVal::Number(i64::MIN),
-        MAX_INT = LispVal::Number(i64::MAX),
+    pub static ref INITIAL_SCOPE: Scope = scope! {
+        MIN_INT = i64::MIN,
+        MAX_INT = i64::MAX,
+        INT_BITS = i64::BITS as i64,
+        PI = std::f64::consts::PI,
+        E = std::f64::consts::E,
+        TAU = std::f64::consts::TAU,
     };
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::parsing::LispVal;
+
+    #[test]
+    fn test_binding_order() {
+        let scope = scope! { x = 1, y = 2, x = 3 };
+
+        assert_eq!(scope.get("x"), Some(&LispVal::Number(3)));
+        assert_eq!(scope.get("y"), Some(&LispVal::Number(2)));
+    }
+
+    #[test]
+    fn test_value_conversion() {
+        let scope = scope! { name = "flow", debug = false, items = vec![LispVal::Number(1)] };
 
+        assert_eq!(scope.get("name"), Some(&LispVal::String("flow".into())));
+        assert_eq!(scope.get("debug"), Some(&LispVal::Boolean(false)));
+        assert_eq!(scope.get("items"), Some(&LispVal::List(vec![LispVal::Number(1)])));
+        assert!(scope! {}.is_empty());
+    }
+
+    #[test]
+    fn test_child_frames() {
+        let outer = scope! { x = 1, y = 2 };
+        let inner = outer.child().bind("x".into(), 10.into());
+
+        assert_eq!(inner.get("x"), Some(&LispVal::Number(10)));
+        assert_eq!(inner.get("y"), Some(&LispVal::Number(2)));
+        assert_eq!(inner.visible_bindings().len(), 2);
+        assert_eq!(inner.parent(), Some(&outer));
+        assert_eq!(outer.get("x"), Some(&LispVal::Number(1)));
+        assert!(outer.captured().is_empty());
+        assert_eq!(inner.child().captured().get("x"), Some(&LispVal::Number(10)));
+    }
+
+    #[test]
+    fn test_default_scopes_have_their_own_runtime() {
+        let scope = super::Scope::default();
+        scope.runtime.count_call();
+
+        assert_eq!(scope.runtime.metrics().function_calls, 1);
+        assert_eq!(super::Scope::default().runtime.metrics().function_calls, 0);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::parsing::{sorted_elements, LispType, LispVal, SetElements};
+
+use super::{error::EvalError, eval, scope::Scope, EvalResult};
+
+fn set_argument(values: &[LispVal], position: usize, context: &str) -> Result<SetElements, EvalError> {
+    match values.get(position).unwrap() {
+        LispVal::Set(elements) => Ok(elements.clone()),
+        value => Err(EvalError::InvalidArgumentType {
+            name: context.to_string(),
+            expected: LispType::Set,
+            got: value.to_type(),
+            position,
+        }),
+    }
+}
+
+/// A `#{a b c}` literal evaluates its elements, in the order the set is
+/// printed in.
+pub fn eval_set_literal(scope: Scope, elements: &SetElements) -> EvalResult {
+    let (scope, evaluated) = sorted_elements(elements).into_iter().try_fold(
+        (scope, SetElements::new()),
+        |(scope, evaluated), element| {
+            let (scope, element) = eval(scope, element).map_err(EvalError::outside_tail)?;
+            Ok::<_, EvalError>((scope, evaluated.update(element)))
+        },
+    )?;
+
+    Ok((scope, LispVal::Set(evaluated)))
+}
+
+/// `(set list)` is a set of the elements of `list`.
+pub fn eval_set(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let elements: Vec<LispVal> = values.first().unwrap().clone().try_into().map_err(|_| EvalError::InvalidArgumentType {
+        name: scope.context.to_string(),
+        expected: LispType::List,
+        got: values.first().unwrap().to_type(),
+        position: 0,
+    })?;
+
+    Ok((scope, LispVal::Set(elements.into_iter().collect())))
+}
+
+/// `(union a b)` has the elements in either set.
+pub fn eval_union(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let left = set_argument(values, 0, &scope.context)?;
+    let right = set_argument(values, 1, &scope.context)?;
+
+    Ok((scope, LispVal::Set(left.union(right))))
+}
+
+/// `(intersection a b)` has the elements in both sets.
+pub fn eval_intersection(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let left = set_argument(values, 0, &scope.context)?;
+    let right = set_argument(values, 1, &scope.context)?;
+
+    Ok((scope, LispVal::Set(left.intersection(right))))
+}
+
+/// `(difference a b)` has the elements of `a` that aren't in `b`.
+pub fn eval_difference(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let left = set_argument(values, 0, &scope.context)?;
+    let right = set_argument(values, 1, &scope.context)?;
+
+    Ok((scope, LispVal::Set(left.relative_complement(right))))
+}
+
+/// `(contains? set x)` is whether `x` is in the set; on a map, whether `x`
+/// is one of its keys.
+pub fn eval_contains(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let value = values.get(1).unwrap();
+    let contains = match values.first().unwrap() {
+        LispVal::Map(entries) => entries.contains_key(value),
+        _ => set_argument(values, 0, &scope.context)?.contains(value),
+    };
+
+    Ok((scope, LispVal::Boolean(contains)))
+}
@@ -0,0 +1,223 @@
+use std::cmp::Ordering;
+
+use crate::parsing::LispVal;
+
+use super::{call, error::EvalError, list_argument, scope::Scope, EvalResult};
+
+/// Natural ordering of numbers, strings, booleans, symbols and keywords.
+/// Integers and floats compare by value, the integer promoted like `<`
+/// does, with NaN after every other float. Values of other different types
+/// can't be compared.
+fn compare(left: &LispVal, right: &LispVal) -> Result<Ordering, EvalError> {
+    match (left, right) {
+        (LispVal::Number(a), LispVal::Number(b)) => Ok(a.cmp(b)),
+        (LispVal::Float(a), LispVal::Float(b)) => Ok(a.total_cmp(b)),
+        (LispVal::Number(a), LispVal::Float(b)) => Ok((*a as f64).total_cmp(b)),
+        (LispVal::Float(a), LispVal::Number(b)) => Ok(a.total_cmp(&(*b as f64))),
+        (LispVal::String(a), LispVal::String(b)) => Ok(a.cmp(b)),
+        (LispVal::Symbol(a), LispVal::Symbol(b)) | (LispVal::Keyword(a), LispVal::Keyword(b)) => Ok(a.cmp(b)),
+        (LispVal::Boolean(a), LispVal::Boolean(b)) => Ok(a.cmp(b)),
+        _ => Err(EvalError::IncomparableValues {
+            left: left.to_type(),
+            right: right.to_type(),
+        }),
+    }
+}
+
+/// Stable merge sort with a fallible `less` predicate, so a Lisp comparator
+/// that errors (or isn't a consistent ordering) can't panic the host.
+fn merge_sort<T, F>(mut items: Vec<T>, less: &mut F) -> Result<Vec<T>, EvalError>
+where
+    F: FnMut(&T, &T) -> Result<bool, EvalError>,
+{
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+
+    let right = items.split_off(items.len() / 2);
+    let mut left = merge_sort(items, less)?.into_iter().peekable();
+    let mut right = merge_sort(right, less)?.into_iter().peekable();
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        if less(r, l)? {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+/// Pairs every element of `list` with the result of calling `key` on it.
+fn with_keys(
+    scope: Scope,
+    key: &LispVal,
+    list: Vec<LispVal>,
+) -> Result<(Scope, Vec<(LispVal, LispVal)>), EvalError> {
+    list.into_iter()
+        .try_fold((scope, Vec::new()), |(scope, mut acc), value| {
+            let (scope, k) = call(scope, key, vec![value.clone()])?;
+            acc.push((k, value));
+            Ok((scope, acc))
+        })
+}
+
+/// `(sort list)` sorts by natural ordering, `(sort list less?)` with a
+/// two-argument predicate such as `'>`.
+pub fn eval_sort(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let context = scope.context.clone();
+    let list = list_argument(values, 0, &context)?;
+
+    let Some(less) = values.get(1) else {
+        let sorted = merge_sort(list, &mut |a, b| Ok(compare(b, a)? == Ordering::Greater))?;
+        return Ok((scope, sorted.into()));
+    };
+
+    let mut scope = scope;
+    let sorted = merge_sort(list, &mut |a, b| {
+        let (next, result) = call(scope.clone(), less, vec![a.clone(), b.clone()])?;
+        scope = next;
+        result.try_into().map_err(EvalError::from_arg(1, &context))
+    })?;
+
+    Ok((scope, sorted.into()))
+}
+
+/// `(sort-by key list)` sorts by the natural ordering of each element's key.
+pub fn eval_sort_by(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let context = scope.context.clone();
+    let list = list_argument(values, 1, &context)?;
+    let (scope, keyed) = with_keys(scope, values.first().unwrap(), list)?;
+
+    let sorted = merge_sort(keyed, &mut |(a, _), (b, _)| {
+        Ok(compare(b, a)? == Ordering::Greater)
+    })?;
+
+    Ok((scope, sorted.into_iter().map(|(_, value)| value).collect()))
+}
+
+fn eval_extreme_by(scope: Scope, values: &[LispVal], wanted: Ordering) -> EvalResult {
+    let context = scope.context.clone();
+    let list = list_argument(values, 1, &context)?;
+    let (scope, keyed) = with_keys(scope, values.first().unwrap(), list)?;
+
+    let mut keyed = keyed.into_iter();
+    let first = keyed.next().ok_or(EvalError::EmptyList { name: context.to_string() })?;
+    let (_, extreme) = keyed.try_fold(first, |best, candidate| {
+        Ok::<_, EvalError>(if compare(&candidate.0, &best.0)? == wanted {
+            candidate
+        } else {
+            best
+        })
+    })?;
+
+    Ok((scope, extreme))
+}
+
+/// `(max-by key list)` returns the first element with the largest key.
+pub fn eval_max_by(scope: Scope, values: &[LispVal]) -> EvalResult {
+    eval_extreme_by(scope, values, Ordering::Greater)
+}
+
+/// `(min-by key list)` returns the first element with the smallest key.
+pub fn eval_min_by(scope: Scope, values: &[LispVal]) -> EvalResult {
+    eval_extreme_by(scope, values, Ordering::Less)
+}
+
+/// Binary search over `list`, which must be sorted by `key`. Returns the
+/// index of a matching element, or where `target` would be inserted.
+fn search<F>(list: &[LispVal], target: &LispVal, key: F) -> Result<Result<usize, usize>, EvalError>
+where
+    F: Fn(&LispVal) -> Result<LispVal, EvalError>,
+{
+    let (mut low, mut high) = (0, list.len());
+
+    while low < high {
+        let middle = low + (high - low) / 2;
+        match compare(&key(&list[middle])?, target)? {
+            Ordering::Less => low = middle + 1,
+            Ordering::Greater => high = middle,
+            Ordering::Equal => return Ok(Ok(middle)),
+        }
+    }
+
+    Ok(Err(low))
+}
+
+/// `(bsearch sorted x)` is the index of `x` in `sorted`. When `x` is
+/// missing it is `(- -1 i)`, where `i` is the index `x` would be inserted
+/// at, so the result is always negative.
+pub fn eval_binary_search(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let list = list_argument(values, 0, &scope.context)?;
+    let index = match search(&list, values.get(1).unwrap(), |value| Ok(value.clone()))? {
+        Ok(found) => found as i64,
+        Err(insertion) => -1 - insertion as i64,
+    };
+
+    Ok((scope, LispVal::Number(index)))
+}
+
+/// `(sorted-insert sorted x)` inserts `x` keeping the list sorted, after any
+/// equal elements.
+pub fn eval_sorted_insert(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let mut list = list_argument(values, 0, &scope.context)?;
+    let value = values.get(1).unwrap();
+
+    let mut index = match search(&list, value, |value| Ok(value.clone()))? {
+        Ok(found) | Err(found) => found,
+    };
+    while index < list.len() && list[index] == *value {
+        index += 1;
+    }
+    list.insert(index, value.clone());
+
+    Ok((scope, list.into()))
+}
+
+/// Sorted maps are lists of `(key value)` pairs ordered by key.
+fn entry_key(context: &str) -> impl Fn(&LispVal) -> Result<LispVal, EvalError> + '_ {
+    move |entry| {
+        let pair: Vec<LispVal> = entry
+            .clone()
+            .try_into()
+            .map_err(EvalError::from_arg(0, context))?;
+
+        pair.into_iter().next().ok_or(EvalError::EmptyList {
+            name: context.to_string(),
+        })
+    }
+}
+
+/// `(sorted-map-put map key value)` adds the `(key value)` entry, replacing
+/// the one with the same key if there is one.
+pub fn eval_sorted_map_put(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let mut map = list_argument(values, 0, &scope.context)?;
+    let key = values.get(1).unwrap();
+    let entry: LispVal = vec![key.clone(), values.get(2).unwrap().clone()].into();
+
+    match search(&map, key, entry_key(&scope.context))? {
+        Ok(index) => map[index] = entry,
+        Err(index) => map.insert(index, entry),
+    }
+
+    Ok((scope, map.into()))
+}
+
+/// `(sorted-map-get map key default)` is the value stored under `key`, or
+/// `default` when there is none.
+pub fn eval_sorted_map_get(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let map = list_argument(values, 0, &scope.context)?;
+    let value = match search(&map, values.get(1).unwrap(), entry_key(&scope.context))? {
+        Ok(index) => match &map[index] {
+            LispVal::List(entry) => entry.get(1).cloned().unwrap_or(LispVal::Void()),
+            _ => LispVal::Void(),
+        },
+        Err(_) => values.get(2).unwrap().clone(),
+    };
+
+    Ok((scope, value))
+}
@@ -0,0 +1,63 @@
+/// A set of argument positions of a special form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arguments {
+    None,
+    All,
+    Last,
+    At(&'static [usize]),
+}
+
+impl Arguments {
+    /// Whether the argument at `position`, out of `count`, is in the set.
+    pub fn includes(&self, position: usize, count: usize) -> bool {
+        match self {
+            Arguments::None => false,
+            Arguments::All => true,
+            Arguments::Last => position + 1 == count,
+            Arguments::At(positions) => positions.contains(&position),
+        }
+    }
+}
+
+/// A builtin whose arguments are not all evaluated before it runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpecialForm {
+    pub name: &'static str,
+    /// Arguments evaluated before the form runs. The others are passed as
+    /// written, for the form to evaluate or not.
+    pub evaluated: Arguments,
+    /// Arguments whose value can become the value of the whole form, which
+    /// is where a call is a tail call.
+    pub tail: Arguments,
+}
+
+const fn form(name: &'static str, evaluated: Arguments, tail: Arguments) -> SpecialForm {
+    SpecialForm { name, evaluated, tail }
+}
+
+pub const SPECIAL_FORMS: &[SpecialForm] = &[
+    form("list", Arguments::All, Arguments::None),
+    form("if!", Arguments::At(&[0]), Arguments::At(&[1, 2])),
+    form("do!", Arguments::None, Arguments::Last),
+    form("let!", Arguments::None, Arguments::Last),
+    form("letrec!", Arguments::None, Arguments::Last),
+    form("loop!", Arguments::None, Arguments::Last),
+    form("match!", Arguments::At(&[0]), Arguments::None),
+    form("fn!", Arguments::None, Arguments::None),
+    form("def!", Arguments::At(&[1]), Arguments::None),
+    form("const!", Arguments::At(&[1]), Arguments::None),
+    form("defn!", Arguments::None, Arguments::None),
+    form("defrecord!", Arguments::None, Arguments::None),
+    // The dispatch function is only evaluated when it isn't a builtin's name
+    form("defmulti!", Arguments::None, Arguments::None),
+    form("defmethod!", Arguments::At(&[1]), Arguments::None),
+    form("deftask!", Arguments::None, Arguments::None),
+    form("quasiquote", Arguments::None, Arguments::None),
+    // The arguments after the first are only evaluated until one decides the result
+    form("and", Arguments::At(&[0]), Arguments::Last),
+    form("or", Arguments::At(&[0]), Arguments::Last),
+];
+
+pub fn special_form(name: &str) -> Option<&'static SpecialForm> {
+    SPECIAL_FORMS.iter().find(|form| form.name == name)
+}
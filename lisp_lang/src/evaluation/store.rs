@@ -0,0 +1,91 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use crate::parsing::LispVal;
+
+use super::{error::EvalError, runtime::{intercept, Effect}, scope::Scope, EvalResult};
+
+/// Application state a host lets scripts read and write with `store-get`,
+/// `store-set!` and `store-keys`. The host decides which keys exist and
+/// which values it accepts.
+pub trait HostStore: std::fmt::Debug + Send + Sync {
+    fn get(&self, key: &str) -> Option<LispVal>;
+    /// Stores `value` under `key`, or says why it can't.
+    fn set(&self, key: &str, value: LispVal) -> Result<(), String>;
+    fn list(&self) -> Vec<String>;
+}
+
+/// Keeps every value in memory, accepting any key.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    values: Mutex<BTreeMap<String, LispVal>>,
+}
+
+impl MemoryStore {
+    pub fn with_value(self, key: impl Into<String>, value: impl Into<LispVal>) -> Self {
+        self.values.lock().unwrap().insert(key.into(), value.into());
+        self
+    }
+}
+
+impl HostStore for MemoryStore {
+    fn get(&self, key: &str) -> Option<LispVal> {
+        self.values.lock().unwrap().get(key).cloned()
+    }
+
+    fn set(&self, key: &str, value: LispVal) -> Result<(), String> {
+        self.values.lock().unwrap().insert(key.to_string(), value);
+        Ok(())
+    }
+
+    fn list(&self) -> Vec<String> {
+        self.values.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+fn key(scope: &Scope, values: &[LispVal]) -> Result<String, EvalError> {
+    values
+        .first()
+        .unwrap()
+        .clone()
+        .try_into()
+        .map_err(EvalError::from_arg(0, &scope.context))
+}
+
+fn store(scope: &Scope, key: &str) -> Result<std::sync::Arc<dyn HostStore>, EvalError> {
+    scope.runtime.store().ok_or_else(|| EvalError::HostStore {
+        key: key.to_string(),
+        reason: "no host store is set".to_string(),
+    })
+}
+
+/// `(store-get "key")` is the value the host stores under `key`, or `void`.
+pub fn eval_store_get(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let key = key(&scope, values)?;
+    let value = store(&scope, &key)?.get(&key).unwrap_or(LispVal::Void());
+
+    Ok((scope, value))
+}
+
+pub fn eval_store_set(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let key = key(&scope, values)?;
+    let store = store(&scope, &key)?;
+    if intercept(Effect::StoreSet(key.clone())) {
+        return Ok((scope, LispVal::Void()));
+    }
+
+    store
+        .set(&key, values.get(1).unwrap().clone())
+        .map_err(|reason| EvalError::HostStore { key, reason })?;
+
+    Ok((scope, LispVal::Void()))
+}
+
+/// `(store-keys)` lists the keys of the host store as strings.
+pub fn eval_store_keys(scope: Scope, _: &[LispVal]) -> EvalResult {
+    let keys = match scope.runtime.store() {
+        Some(store) => store.list().into_iter().map(|key| LispVal::String(key.into())).collect(),
+        None => Vec::new(),
+    };
+
+    Ok((scope, keys.into()))
+}
@@ -0,0 +1,68 @@
+/// Edit distance between two strings, counted in characters.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Largest distance still considered a plausible typo of `name`.
+fn max_distance(name: &str) -> usize {
+    (name.chars().count() / 3).max(1)
+}
+
+/// Candidates close enough to `name` to be suggested, best matches first.
+/// Case differences are ignored when measuring the distance.
+pub fn similar<'a, I>(name: &str, candidates: I) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let name = name.to_lowercase();
+    let mut matches: Vec<_> = candidates
+        .into_iter()
+        .map(|candidate| (levenshtein(&name, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance(&name))
+        .collect();
+
+    matches.sort();
+    matches.dedup();
+    matches.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+/// The single best suggestion for `name`, if any candidate is close enough.
+pub fn closest<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    similar(name, candidates).into_iter().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("map", "map"), 0);
+        assert_eq!(levenshtein("mpa", "map"), 2);
+        assert_eq!(levenshtein("fold", "folds"), 1);
+        assert_eq!(levenshtein("", "head"), 4);
+    }
+
+    #[test]
+    fn test_closest() {
+        let names = ["map", "max", "fold", "print"];
+        assert_eq!(closest("MAp", names), Some("map"));
+        assert_eq!(closest("prnt", names), Some("print"));
+        assert_eq!(closest("concat", names), None);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::parsing::LispVal;
+
+use super::{error::EvalError, eval, runtime::{intercept, Effect}, scope::Scope, EvalResult};
+
+/// Expressions a task may evaluate in one tick before it is stopped, unless
+/// the host sets another budget.
+pub const DEFAULT_BUDGET: usize = 1000;
+
+/// A script run once per `tick` until it returns `false`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Task {
+    pub name: String,
+    pub body: LispVal,
+    /// How many times the task has run.
+    pub step: i64,
+    /// What the task returned last time, void before its first run.
+    pub state: LispVal,
+}
+
+/// `(deftask! name body)` schedules `body` to run on every `tick`, replacing
+/// any task with the same name.
+pub fn eval_task_definition(scope: Scope, values: &[LispVal]) -> EvalResult {
+    let name = values
+        .first()
+        .unwrap()
+        .as_symbol()
+        .map_err(EvalError::from_arg(0, &scope.context))?
+        .to_string();
+
+    if !intercept(Effect::Schedule(name.clone())) {
+        scope.runtime.schedule(Task {
+            name,
+            body: values.get(1).unwrap().clone(),
+            step: 0,
+            state: LispVal::Void(),
+        });
+    }
+
+    Ok((scope, LispVal::Void()))
+}
+
+/// `(tick)` runs every task once, see [`tick`].
+pub fn eval_tick(scope: Scope, _values: &[LispVal]) -> EvalResult {
+    let running = tick(&scope)?;
+
+    Ok((scope, LispVal::Number(running as i64)))
+}
+
+/// Runs every scheduled task once, in the order they were defined, with
+/// `step` and `state` bound. Each task may evaluate as many expressions as
+/// the runtime's task budget allows. Tasks that return `false` or fail are
+/// unscheduled; a failure doesn't keep the tasks after it from running, and
+/// the first one is returned once they all ran. Returns how many tasks are
+/// still scheduled.
+pub fn tick(scope: &Scope) -> Result<usize, EvalError> {
+    let mut first_error = None;
+    let mut running = Vec::new();
+
+    for mut task in scope.runtime.take_tasks() {
+        let task_scope = scope
+            .child()
+            .bind("step".to_string(), LispVal::Number(task.step))
+            .bind("state".to_string(), task.state.clone());
+
+        let previous = scope.runtime.begin_task(&task.name);
+        let result = eval(task_scope, &task.body);
+        scope.runtime.end_task(previous);
+
+        match result {
+            Ok((_, LispVal::Boolean(false))) => {}
+            Ok((_, state)) => {
+                task.step += 1;
+                task.state = state;
+                running.push(task);
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    let running = scope.runtime.reschedule(running);
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(running),
+    }
+}
@@ -0,0 +1,86 @@
+/// Which semantics a scope evaluates with. Behavior that would break
+/// existing scripts is only turned on in a newer version. Scopes start at
+/// the latest one, and scripts written for an older one pin it to keep the
+/// behavior they were written for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LanguageVersion {
+    /// The original semantics.
+    V1,
+    /// Every [`Feature`] turned on.
+    V2,
+}
+
+impl LanguageVersion {
+    pub const LATEST: LanguageVersion = LanguageVersion::V2;
+    pub const ALL: [LanguageVersion; 2] = [LanguageVersion::V1, LanguageVersion::V2];
+
+    /// The version's name, like `v2`.
+    pub fn name(self) -> &'static str {
+        match self {
+            LanguageVersion::V1 => "v1",
+            LanguageVersion::V2 => "v2",
+        }
+    }
+
+    /// Looks a version up by its name, ignoring case, or by its number, so
+    /// `v2`, `V2` and `2` all find [`LanguageVersion::V2`].
+    pub fn parse(name: &str) -> Option<LanguageVersion> {
+        Self::ALL
+            .into_iter()
+            .find(|version| version.name().eq_ignore_ascii_case(name) || version.name()[1..] == *name)
+    }
+
+    pub fn supports(self, feature: Feature) -> bool {
+        self >= feature.introduced_in()
+    }
+}
+
+impl Default for LanguageVersion {
+    fn default() -> Self {
+        LanguageVersion::LATEST
+    }
+}
+
+/// A behavior that differs between language versions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// `if!` takes any condition: only `false`, `nil` and void are false. Before,
+    /// conditions had to be booleans.
+    Truthiness,
+    /// Builtin names evaluate to themselves, so `(map not xs)` works without
+    /// quoting `not`. Before, they were unknown identifiers.
+    FirstClassNatives,
+    /// Definitions made inside `do!` end with it. Before, they leaked into
+    /// the enclosing scope.
+    ScopedDo,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 3] = [
+        Feature::Truthiness,
+        Feature::FirstClassNatives,
+        Feature::ScopedDo,
+    ];
+
+    pub fn introduced_in(self) -> LanguageVersion {
+        match self {
+            Feature::Truthiness | Feature::FirstClassNatives | Feature::ScopedDo => {
+                LanguageVersion::V2
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LanguageVersion;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(LanguageVersion::parse("v2"), Some(LanguageVersion::V2));
+        assert_eq!(LanguageVersion::parse("V1"), Some(LanguageVersion::V1));
+        assert_eq!(LanguageVersion::parse("2"), Some(LanguageVersion::V2));
+        assert_eq!(LanguageVersion::parse("3"), None);
+        assert_eq!(LanguageVersion::parse("v"), None);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::parsing::LispVal;
+
+use super::{call, error::EvalError, scope::Scope, EvalResult};
+
+/// Rebuilds `node` with `walk` applied to each of its children: list
+/// elements and record field values. Other values have no children.
+fn map_children<F>(scope: Scope, node: LispVal, walk: &mut F) -> EvalResult
+where
+    F: FnMut(Scope, LispVal) -> EvalResult,
+{
+    match node {
+        LispVal::List(elements) => {
+            let (scope, elements) = elements.into_iter().try_fold(
+                (scope, Vec::new()),
+                |(scope, mut acc), element| {
+                    let (scope, element) = walk(scope, element)?;
+                    acc.push(element);
+                    Ok::<_, EvalError>((scope, acc))
+                },
+            )?;
+            Ok((scope, elements.into()))
+        }
+        LispVal::Record { name, fields } => {
+            let (scope, fields) = fields.into_iter().try_fold(
+                (scope, Vec::new()),
+                |(scope, mut acc), (field, value)| {
+                    let (scope, value) = walk(scope, value)?;
+                    acc.push((field, value));
+                    Ok::<_, EvalError>((scope, acc))
+                },
+            )?;
+            Ok((scope, LispVal::Record { name, fields }))
+        }
+        node => Ok((scope, node)),
+    }
+}
+
+fn postwalk(scope: Scope, f: &LispVal, node: LispVal) -> EvalResult {
+    let (scope, node) = map_children(scope, node, &mut |scope, child| postwalk(scope, f, child))?;
+    call(scope, f, vec![node])
+}
+
+fn prewalk(scope: Scope, f: &LispVal, node: LispVal) -> EvalResult {
+    let (scope, node) = call(scope, f, vec![node])?;
+    map_children(scope, node, &mut |scope, child| prewalk(scope, f, child))
+}
+
+/// `(postwalk f tree)` calls `f` on every node, children before their
+/// parent, so `f` sees lists whose elements were already replaced.
+pub fn eval_postwalk(scope: Scope, values: &[LispVal]) -> EvalResult {
+    postwalk(scope, values.first().unwrap(), values.get(1).unwrap().clone())
+}
+
+/// `(prewalk f tree)` calls `f` on every node, parents first, then walks
+/// into the children of whatever `f` returned.
+pub fn eval_prewalk(scope: Scope, values: &[LispVal]) -> EvalResult {
+    prewalk(scope, values.first().unwrap(), values.get(1).unwrap().clone())
+}
@@ -0,0 +1,37 @@
+/// Something suspicious noticed while evaluating. Unlike errors, warnings
+/// don't stop evaluation; they are collected by the [`Runtime`] for the
+/// host to show.
+///
+/// [`Runtime`]: super::runtime::Runtime
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Warning {
+    /// A definition inside a function hides a name from an enclosing scope.
+    Shadowing { name: String },
+    /// A definition reuses the name of a builtin, which calls still resolve to.
+    ShadowedBuiltin { name: String },
+    /// An arithmetic builtin overflowed and its result wrapped around.
+    Overflow { operation: String },
+    /// A call used the old name of a renamed builtin.
+    Deprecated { name: String, replacement: String },
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::Shadowing { name } => {
+                write!(f, "`{}` shadows a definition from an enclosing scope", name)
+            }
+            Warning::ShadowedBuiltin { name } => write!(
+                f,
+                "`{}` shadows a builtin, calls to `{}` still use the builtin",
+                name, name
+            ),
+            Warning::Overflow { operation } => {
+                write!(f, "Integer overflow in `{}`, the result wrapped around", operation)
+            }
+            Warning::Deprecated { name, replacement } => {
+                write!(f, "`{}` is deprecated, use `{}` instead", name, replacement)
+            }
+        }
+    }
+}
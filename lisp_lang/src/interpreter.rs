@@ -0,0 +1,455 @@
+use std::sync::Arc;
+
+use crate::{
+    error_code::ErrorCode,
+    evaluation::{
+        call,
+        error::EvalError,
+        task::tick,
+        eval,
+        module::ModuleResolver,
+        store::HostStore,
+        runtime::{with_dry_run, Effect, Metrics, Runtime},
+        scope::{Scope, Settings, INITIAL_SCOPE},
+        version::LanguageVersion,
+        warning::Warning,
+    },
+    parsing::{parse_program, register_reader_macro, LispVal},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum InterpreterError {
+    #[error("Invalid syntax: {message}")]
+    InvalidSyntax { message: String },
+    #[error(transparent)]
+    Evaluation(#[from] EvalError),
+}
+
+impl InterpreterError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            InterpreterError::InvalidSyntax { .. } => ErrorCode::InvalidSyntax,
+            InterpreterError::Evaluation(e) => e.code(),
+        }
+    }
+}
+
+/// Owns a scope and its runtime counters, threading the scope between
+/// evaluations so hosts don't have to.
+#[derive(Clone, Debug)]
+pub struct Interpreter {
+    scope: Scope,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::with_scope(INITIAL_SCOPE.clone())
+    }
+
+    /// Starts from `scope` with fresh runtime counters.
+    pub fn with_scope(scope: Scope) -> Self {
+        Self {
+            scope: scope.with_runtime(Arc::new(Runtime::default())),
+        }
+    }
+
+    /// Pins the semantics scripts are evaluated with. Scripts written for an
+    /// older version keep working by pinning it; the default is the latest.
+    pub fn set_version(&mut self, version: LanguageVersion) {
+        self.scope = self.scope.with_settings(Settings {
+            version,
+            ..self.scope.settings
+        });
+    }
+
+    pub fn version(&self) -> LanguageVersion {
+        self.scope.settings.version
+    }
+
+    /// Sets where `load` and `require` find modules.
+    pub fn set_resolver(&mut self, resolver: impl ModuleResolver + 'static) {
+        self.scope.runtime.set_resolver(Arc::new(resolver));
+    }
+
+    /// Sets what `store-get` and `store-set!` read and write.
+    pub fn set_store(&mut self, store: impl HostStore + 'static) {
+        self.scope.runtime.set_store(Arc::new(store));
+    }
+
+    /// Makes `#tag"text"` read as the value `expand` returns for `text`.
+    /// Reader macros apply to everything parsed in the process, not only to
+    /// this interpreter.
+    pub fn register_reader_macro<F>(&self, tag: &str, expand: F)
+    where
+        F: Fn(&str) -> Result<LispVal, String> + Send + Sync + 'static,
+    {
+        register_reader_macro(tag, expand);
+    }
+
+    pub fn scope(&self) -> &Scope {
+        &self.scope
+    }
+
+    /// Evaluates `expr`, keeping any definitions it makes for later calls.
+    pub fn eval(&mut self, expr: &LispVal) -> Result<LispVal, EvalError> {
+        let (scope, value) = eval(self.scope.clone(), expr)?;
+        self.scope = scope;
+
+        Ok(value)
+    }
+
+    /// Parses every top-level form of `source`, then evaluates them in
+    /// order, returning the value of the last one, or `void` if there is
+    /// none. Nothing is evaluated when any form fails to parse.
+    pub fn eval_str(&mut self, source: &str) -> Result<LispVal, InterpreterError> {
+        let forms = parse_program(source).map_err(|e| InterpreterError::InvalidSyntax { message: e.to_string() })?;
+        let mut value = LispVal::Void();
+
+        for expr in &forms {
+            value = self.eval(expr)?;
+        }
+
+        Ok(value)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LispVal> {
+        self.scope.get(name)
+    }
+
+    /// Binds `name` for later evaluations, replacing any previous value,
+    /// even one defined with `const!`.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<LispVal>) {
+        self.scope = self.scope.bind(name.into(), value.into());
+    }
+
+    /// Every binding visible to the next evaluation, builtin constants
+    /// included, in no particular order.
+    pub fn bindings(&self) -> impl Iterator<Item = (String, LispVal)> {
+        self.scope.visible_bindings().into_iter()
+    }
+
+    /// Evaluates `expr` without committing anything: prints and definitions
+    /// are reported as effects instead of being performed, and the scope is
+    /// left untouched.
+    pub fn dry_run(&self, expr: &LispVal) -> Result<(LispVal, Vec<Effect>), EvalError> {
+        dry_run(self.scope.clone(), expr)
+    }
+
+    /// Queues a call with `payload` to every handler scripts registered for
+    /// `event` with `on-event!`, returning how many were queued. Nothing runs
+    /// until [`Interpreter::run_pending`].
+    pub fn emit(&self, event: &str, payload: impl Into<LispVal>) -> usize {
+        self.scope.runtime.emit(event, payload.into())
+    }
+
+    /// Calls the queued handlers in the order their events were emitted,
+    /// returning their results. A failing handler stops the run; the handlers
+    /// after it stay queued.
+    pub fn run_pending(&mut self) -> Result<Vec<LispVal>, EvalError> {
+        let mut results = Vec::new();
+        while let Some((handler, payload)) = self.scope.runtime.next_pending() {
+            let (scope, value) = call(self.scope.clone(), &handler, vec![payload])?;
+            self.scope = scope;
+            results.push(value);
+        }
+
+        Ok(results)
+    }
+
+    /// Runs every task scripts defined with `deftask!` once, returning how
+    /// many are still scheduled. See [`tick`].
+    pub fn tick(&self) -> Result<usize, EvalError> {
+        tick(&self.scope)
+    }
+
+    /// How many expressions each task may evaluate per tick before it is
+    /// stopped, 1000 by default.
+    pub fn set_task_budget(&self, budget: usize) {
+        self.scope.runtime.set_task_budget(budget);
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        self.scope.runtime.metrics()
+    }
+
+    /// Warnings raised since the last call, oldest first.
+    pub fn take_warnings(&self) -> Vec<Warning> {
+        self.scope.runtime.take_warnings()
+    }
+}
+
+/// Evaluates `expr` in `scope` while intercepting its side effects.
+pub fn dry_run(scope: Scope, expr: &LispVal) -> Result<(LispVal, Vec<Effect>), EvalError> {
+    let (result, effects) = with_dry_run(|| eval(scope, expr));
+
+    result.map(|(_, value)| (value, effects))
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_it, parsing::LispVal};
+
+    use crate::evaluation::{module::MemoryResolver, store::{HostStore, MemoryStore}};
+
+    use super::{ErrorCode, Interpreter, LanguageVersion, Warning};
+
+    #[test]
+    fn test_metrics() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(interpreter.metrics().evaluated_nodes, 0);
+
+        interpreter.eval(&parse_it!("(defn! inc (x) (+ x 1))")).unwrap();
+        let result = interpreter.eval(&parse_it!("(inc (inc 1))")).unwrap();
+        let metrics = interpreter.metrics();
+
+        assert_eq!(result, LispVal::Number(3));
+        assert_eq!(metrics.function_calls, 5);
+        assert!(metrics.max_depth >= 3);
+        assert!(metrics.evaluated_nodes > metrics.function_calls);
+    }
+
+    #[test]
+    #[cfg(feature = "io")]
+    fn test_dry_run() {
+        let mut interpreter = Interpreter::new();
+        let (value, effects) = interpreter
+            .dry_run(&parse_it!("(list (def! x 2) (print \"hi\") (+ x 1))"))
+            .unwrap();
+
+        assert_eq!(value, vec![LispVal::Void(), LispVal::Void(), LispVal::Number(3)].into());
+        assert_eq!(effects, vec![super::Effect::Bind("x".into()), super::Effect::Print("hi".into())]);
+        assert!(interpreter.eval(&parse_it!("x")).is_err());
+        assert_eq!(
+            interpreter.dry_run(&parse_it!("(debug 1)")).unwrap().1,
+            vec![super::Effect::Print(format!("{:#?}", LispVal::Number(1)))]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "io")]
+    fn test_float_precision_binding() {
+        let mut interpreter = Interpreter::new();
+        let third = parse_it!("(pprint (/ 1.0 3))");
+
+        assert_eq!(interpreter.dry_run(&third).unwrap().1, vec![super::Effect::Print("0.3333333333333333".into())]);
+        interpreter.eval(&parse_it!("(def! *float-precision* 4)")).unwrap();
+        assert_eq!(interpreter.dry_run(&third).unwrap().1, vec![super::Effect::Print("0.3333".into())]);
+    }
+
+    #[test]
+    fn test_warnings() {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval(&parse_it!("(def! x 1)")).unwrap();
+        interpreter.eval(&parse_it!("(defn! f (a) (def! x a))")).unwrap();
+
+        let result = interpreter
+            .eval(&parse_it!("(list (f 2) (def! len 3) (+ MAX_INT 1) (mat-mul (list (list MAX_INT)) '((2))))"))
+            .unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                LispVal::Void(),
+                LispVal::Void(),
+                LispVal::Number(i64::MIN),
+                vec![vec![LispVal::Number(-2)].into()].into(),
+            ]
+            .into()
+        );
+        assert_eq!(
+            interpreter.take_warnings(),
+            vec![
+                Warning::Shadowing { name: "x".into() },
+                Warning::ShadowedBuiltin { name: "len".into() },
+                Warning::Overflow { operation: "+".into() },
+                Warning::Overflow { operation: "mat-mul".into() },
+            ]
+        );
+        assert!(interpreter.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_deprecated_builtins_still_work() {
+        let mut interpreter = Interpreter::new();
+
+        assert_eq!(interpreter.eval(&parse_it!("(ltq 2 2)")).unwrap(), LispVal::Boolean(true));
+        assert_eq!(
+            interpreter.take_warnings(),
+            vec![Warning::Deprecated { name: "ltq".into(), replacement: "lte".into() }]
+        );
+
+        interpreter.eval(&parse_it!("(defn! gtq (a b) a)")).unwrap();
+        assert_eq!(interpreter.eval(&parse_it!("(gtq 1 2)")).unwrap(), LispVal::Number(1));
+        assert!(interpreter.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_eval_str() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set("base", 10);
+
+        let result = interpreter.eval_str("(defn! add-base (x) (+ x base))\n(add-base 5)");
+
+        assert_eq!(result.unwrap(), LispVal::Number(15));
+        assert_eq!(interpreter.get("base"), Some(&LispVal::Number(10)));
+        assert!(interpreter.bindings().any(|(name, _)| name == "add-base"));
+        assert_eq!(interpreter.eval_str("").unwrap(), LispVal::Void());
+        assert_eq!(interpreter.eval_str("(+ 1").unwrap_err().code(), ErrorCode::InvalidSyntax);
+        assert!(interpreter.eval_str("(defn! later () 1)\n(+ 1").is_err());
+        assert_eq!(interpreter.get("later"), None);
+        assert_eq!(interpreter.eval_str("missing").unwrap_err().code(), ErrorCode::UnknownIdentifier);
+    }
+
+    #[test]
+    fn test_modules() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_resolver(
+            MemoryResolver::default()
+                .with_module("counter", "(def! count (+ count 1))")
+                .with_module("broken", "(+ 1"),
+        );
+        interpreter.set("count", 0);
+
+        interpreter.eval_str("(list (load \"counter\") (load \"counter\"))").unwrap();
+        interpreter.eval_str("(list (require \"counter\") (require \"counter\"))").unwrap();
+
+        assert_eq!(interpreter.get("count"), Some(&LispVal::Number(3)));
+        for source in ["(load \"missing\")", "(load \"broken\")"] {
+            assert_eq!(interpreter.eval_str(source).unwrap_err().code(), ErrorCode::InvalidModule);
+        }
+    }
+
+    #[test]
+    fn test_host_store() {
+        #[derive(Debug, Default)]
+        struct ReadOnly;
+
+        impl HostStore for ReadOnly {
+            fn get(&self, key: &str) -> Option<LispVal> {
+                (key == "version").then(|| LispVal::Number(2))
+            }
+
+            fn set(&self, _: &str, _: LispVal) -> Result<(), String> {
+                Err("the store is read-only".to_string())
+            }
+
+            fn list(&self) -> Vec<String> {
+                vec!["version".to_string()]
+            }
+        }
+
+        let mut interpreter = Interpreter::new();
+        assert_eq!(interpreter.eval_str("(store-get \"theme\")").unwrap_err().code(), ErrorCode::HostStore);
+
+        interpreter.set_store(MemoryStore::default().with_value("theme", "dark"));
+        interpreter.eval_str("(store-set! \"count\" (+ 1 2))").unwrap();
+        assert_eq!(interpreter.eval_str("(store-get \"count\")").unwrap(), LispVal::Number(3));
+        assert_eq!(interpreter.eval_str("(store-get \"missing\")").unwrap(), LispVal::Void());
+        assert_eq!(interpreter.eval_str("(store-keys)").unwrap().to_string(), "(\"count\" \"theme\")");
+
+        interpreter.set_store(ReadOnly);
+        assert_eq!(interpreter.eval_str("(store-get \"version\")").unwrap(), LispVal::Number(2));
+        let error = interpreter.eval_str("(store-set! \"version\" 3)").unwrap_err();
+        assert!(error.to_string().contains("the store is read-only"));
+    }
+
+    #[test]
+    fn test_events() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set("total", 0);
+        interpreter
+            .eval_str("(on-event! \"score\" (fn! (points) (* points 10)))\n(on-event! \"score\" (fn! (points) (+ points total)))")
+            .unwrap();
+
+        assert_eq!(interpreter.emit("score", 2), 2);
+        assert_eq!(interpreter.emit("unknown", 1), 0);
+        assert_eq!(interpreter.run_pending().unwrap(), vec![LispVal::Number(20), LispVal::Number(2)]);
+
+        interpreter.emit("score", "three");
+        interpreter.emit("score", 3);
+        assert_eq!(interpreter.run_pending().unwrap_err().code(), ErrorCode::InvalidArgumentType);
+        assert_eq!(interpreter.run_pending().unwrap_err().code(), ErrorCode::InvalidArgumentType);
+        assert_eq!(interpreter.run_pending().unwrap(), vec![LispVal::Number(30), LispVal::Number(3)]);
+        assert_eq!(interpreter.run_pending().unwrap(), vec![]);
+        assert_eq!(interpreter.eval_str("(on-event! \"score\" 1)").unwrap_err().code(), ErrorCode::InvalidArgumentType);
+    }
+
+    #[test]
+    fn test_tasks() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval_str(
+                "(deftask! countdown (if! (= step 0) 3 (if! (= state 1) false (- state 1))))
+                 (deftask! forever step)",
+            )
+            .unwrap();
+
+        assert_eq!(interpreter.tick().unwrap(), 2);
+        assert_eq!(interpreter.tick().unwrap(), 2);
+        assert_eq!(interpreter.tick().unwrap(), 2);
+        assert_eq!(interpreter.tick().unwrap(), 1);
+        assert_eq!(interpreter.eval_str("(tick)").unwrap(), LispVal::Number(1));
+
+        interpreter.set_task_budget(20);
+        interpreter
+            .eval_str("(defn! spin (n) (spin (+ n 1)))\n(deftask! spinner (spin 0))")
+            .unwrap();
+
+        let error = interpreter.tick().unwrap_err();
+        assert_eq!(error.code(), ErrorCode::TaskBudget);
+        assert_eq!(error.to_string(), "Task `spinner` evaluated more than 20 expressions in one tick");
+        assert_eq!(interpreter.tick().unwrap(), 1);
+        assert_eq!(interpreter.eval_str("(+ 1 2)").unwrap(), LispVal::Number(3));
+    }
+
+    #[test]
+    fn test_language_versions() {
+        let programs = [
+            "(if! 0 \"truthy\" \"falsy\")",
+            "(map not '(true false))",
+            "(do! (do! (def! hidden 1)) hidden)",
+        ];
+
+        let mut v1 = Interpreter::new();
+        v1.set_version(LanguageVersion::V1);
+        for program in &programs[..2] {
+            assert!(v1.eval_str(program).is_err(), "{program}");
+        }
+        assert_eq!(v1.eval_str(programs[2]).unwrap(), LispVal::Number(1));
+        assert_eq!(v1.eval_str("(and true (or false true))").unwrap(), LispVal::Boolean(true));
+        assert_eq!(v1.eval_str("(and true true false)").unwrap(), LispVal::Boolean(false));
+        assert_eq!(v1.eval_str("(and false (undefined))").unwrap(), LispVal::Boolean(false));
+        assert_eq!(v1.eval_str("(and true 1)").unwrap_err().code(), ErrorCode::InvalidArgumentType);
+
+        let mut v2 = Interpreter::new();
+        assert_eq!(v2.version(), LanguageVersion::LATEST);
+        assert_eq!(v2.eval_str(programs[0]).unwrap(), LispVal::String("truthy".into()));
+        assert_eq!(v2.eval_str(programs[1]).unwrap(), vec![LispVal::Boolean(false), LispVal::Boolean(true)].into());
+        assert_eq!(v2.eval_str(programs[2]).unwrap_err().code(), ErrorCode::UnknownIdentifier);
+        assert_eq!(v2.eval_str("(and false (undefined))").unwrap(), LispVal::Boolean(false));
+        assert_eq!(v2.eval_str("(and true (or false true))").unwrap(), LispVal::Boolean(true));
+        assert_eq!(v2.eval_str("(or false 1)").unwrap_err().code(), ErrorCode::InvalidArgumentType);
+        assert_eq!(v2.eval_str("(or false false true (undefined))").unwrap(), LispVal::Boolean(true));
+        assert_eq!(v2.eval_str("(and true true (undefined))").unwrap_err().code(), ErrorCode::UnknownIdentifier);
+        assert_eq!(v2.eval_str("(list (and true true true) (or false false false))").unwrap(), vec![LispVal::Boolean(true), LispVal::Boolean(false)].into());
+        assert_eq!(v2.eval_str("(list (and) (or) (and false) (or true))").unwrap(), [true, false, false, true].map(LispVal::Boolean).to_vec().into());
+        assert_eq!(v2.eval_str("(list (if! nil 1 2) (if! '() 1 2))").unwrap(), vec![LispVal::Number(2), LispVal::Number(1)].into());
+    }
+
+    #[test]
+    fn test_reader_macros() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_reader_macro("digits", |text| {
+            text.parse::<i64>().map(LispVal::Number).map_err(|e| e.to_string())
+        });
+
+        assert_eq!(interpreter.eval_str("(+ #digits\"41\" 1)").unwrap(), LispVal::Number(42));
+        assert_eq!(interpreter.eval_str("#digits\"x\"").unwrap_err().code(), ErrorCode::InvalidSyntax);
+    }
+}
@@ -0,0 +1,149 @@
+use serde_json::{Map, Number, Value};
+
+use crate::parsing::{sorted_elements, LispType, LispVal};
+
+/// Name of the records JSON objects are converted to.
+pub const OBJECT_RECORD: &str = "json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum JsonError {
+    #[error("JSON has no number for `{0}`")]
+    NonFiniteFloat(f64),
+    #[error("Cannot convert a `{0}` to JSON")]
+    Unsupported(LispType),
+    #[error("JSON object keys must be strings, got a `{0}`")]
+    NonStringKey(LispType),
+}
+
+/// `null` becomes `nil`, arrays become lists and objects become `json`
+/// records with one field per key.
+impl TryFrom<Value> for LispVal {
+    type Error = JsonError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::Null => LispVal::Nil,
+            Value::Bool(b) => LispVal::Boolean(b),
+            Value::Number(n) => match n.as_i64() {
+                Some(n) => LispVal::Number(n),
+                None => LispVal::Float(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::String(s) => LispVal::String(s.into()),
+            Value::Array(values) => values
+                .into_iter()
+                .map(LispVal::try_from)
+                .collect::<Result<Vec<_>, _>>()?
+                .into(),
+            Value::Object(entries) => LispVal::Record {
+                name: OBJECT_RECORD.to_string(),
+                fields: entries
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, LispVal::try_from(value)?)))
+                    .collect::<Result<_, JsonError>>()?,
+            },
+        })
+    }
+}
+
+/// Records of any name and maps with string, symbol or keyword keys become
+/// objects, symbols and keywords become strings, keywords without their
+/// colon, and quoted values are converted as they are. Heaps and deques become arrays, in the order they would be popped
+/// from the front. Functions, pairs and complex numbers can't be converted.
+impl TryFrom<LispVal> for Value {
+    type Error = JsonError;
+
+    fn try_from(value: LispVal) -> Result<Self, Self::Error> {
+        let array = |values: Vec<LispVal>| -> Result<Value, JsonError> {
+            values
+                .into_iter()
+                .map(Value::try_from)
+                .collect::<Result<_, _>>()
+                .map(Value::Array)
+        };
+
+        match value {
+            LispVal::Void() | LispVal::Nil => Ok(Value::Null),
+            LispVal::Boolean(b) => Ok(Value::Bool(b)),
+            LispVal::Number(n) => Ok(Value::Number(n.into())),
+            LispVal::Float(f) => Number::from_f64(f).map(Value::Number).ok_or(JsonError::NonFiniteFloat(f)),
+            LispVal::String(s) | LispVal::Symbol(s) | LispVal::Keyword(s) => Ok(Value::String(s.into())),
+            LispVal::Unevaluated(value) => Value::try_from(*value),
+            LispVal::List(values) | LispVal::Vector(values) => array(values),
+            LispVal::Deque(values) => array(values.into_iter().collect()),
+            LispVal::Set(elements) => array(sorted_elements(&elements).into_iter().cloned().collect()),
+            LispVal::Heap { entries, .. } => array(entries.values().cloned().collect()),
+            LispVal::Record { fields, .. } => fields
+                .into_iter()
+                .map(|(key, value)| Ok((key, Value::try_from(value)?)))
+                .collect::<Result<Map<_, _>, _>>()
+                .map(Value::Object),
+            LispVal::Map(entries) => entries
+                .into_iter()
+                .map(|(key, value)| match key {
+                    LispVal::String(k) | LispVal::Symbol(k) | LispVal::Keyword(k) => {
+                        Ok((k.into(), Value::try_from(value)?))
+                    }
+                    key => Err(JsonError::NonStringKey(key.to_type())),
+                })
+                .collect::<Result<Map<_, _>, _>>()
+                .map(Value::Object),
+            value @ (LispVal::Function { .. }
+            | LispVal::MultiMethod { .. }
+            | LispVal::Pair(..)
+            | LispVal::Complex(..)) => Err(JsonError::Unsupported(value.to_type())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::{
+        builder::{keyword, list, quote, sym},
+        parsing::LispVal,
+    };
+
+    #[test]
+    fn test_round_trip() {
+        let value = json!({"name": "flow", "ports": [80, 443], "debug": false, "parent": null});
+        let lisp = LispVal::try_from(value.clone()).unwrap();
+
+        assert_eq!(lisp.get_path::<i64>(&["ports", "1"]), Some(443));
+        assert_eq!(Value::try_from(lisp).unwrap(), value);
+    }
+
+    #[test]
+    fn test_floats() {
+        assert_eq!(LispVal::try_from(json!(1.5)).unwrap(), LispVal::Float(1.5));
+        assert_eq!(Value::try_from(LispVal::Float(0.25)).unwrap(), json!(0.25));
+        assert!(Value::try_from(LispVal::Float(f64::NAN)).is_err());
+    }
+
+    #[test]
+    fn test_keywords() {
+        assert_eq!(Value::try_from(list([keyword("a"), sym("b")])).unwrap(), json!(["a", "b"]));
+    }
+
+    #[test]
+    fn test_maps() {
+        let map = LispVal::Map([(keyword("a"), 1.into()), ("b".into(), list([2]))].into_iter().collect());
+        assert_eq!(Value::try_from(map).unwrap(), json!({"a": 1, "b": [2]}));
+
+        let map = LispVal::Map([(1.into(), 1.into())].into_iter().collect());
+        assert!(Value::try_from(map).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_values() {
+        assert!(Value::try_from(quote(list([sym("a"), "b".into(), 1.into()]))).is_ok());
+        assert!(Value::try_from(LispVal::Function {
+            parameters: vec![],
+            body: Box::new(LispVal::Void()),
+            applied: vec![],
+            doc: None,
+            captured: Default::default(),
+        })
+        .is_err());
+    }
+}
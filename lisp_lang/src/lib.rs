@@ -1,6 +1,11 @@
-#![feature(iterator_try_collect)]
-
+pub mod builder;
 pub mod parsing;
 pub mod evaluation;
 pub mod display;
-pub mod convert;
\ No newline at end of file
+pub mod pretty;
+pub mod convert;
+pub mod interpreter;
+pub mod error_code;
+pub mod catalog;
+#[cfg(feature = "json")]
+pub mod json;
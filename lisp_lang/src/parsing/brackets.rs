@@ -0,0 +1,273 @@
+use std::{fmt, str::Chars};
+
+/// Where a bracket sits in the input, counting both from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// The position of the byte at `offset` in `input`, counting columns in
+    /// characters.
+    pub fn of(input: &str, offset: usize) -> Position {
+        let before = &input[..offset.min(input.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        Position {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 1 {
+            write!(f, "column {}", self.column)
+        } else {
+            write!(f, "line {}, column {}", self.line, self.column)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BracketError {
+    #[error("expected closing `{expected}` at {at}, found `{found}`, to match `{open}` at {opened}")]
+    Mismatched { open: char, expected: char, opened: Position, found: char, at: Position },
+    /// `at` is just past the end of the form.
+    #[error("expected closing `{expected}` at {at}, to match `{open}` at {opened}")]
+    Unclosed { open: char, expected: char, opened: Position, at: Position },
+    #[error("unexpected `{found}` at {at}")]
+    Unopened { found: char, at: Position },
+}
+
+impl BracketError {
+    /// Where the input goes wrong.
+    pub fn position(&self) -> Position {
+        match self {
+            BracketError::Mismatched { at, .. } | BracketError::Unclosed { at, .. } | BracketError::Unopened { at, .. } => *at,
+        }
+    }
+}
+
+fn closing(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+/// Skips a `#| ... |#` comment, and any nested in it, once its `#` has been
+/// read, keeping `at` on the last character read. False when the input ends
+/// before the comment does.
+fn skip_block_comment(chars: &mut Chars, at: &mut Position) -> bool {
+    let mut depth = 0;
+    let mut previous = '#';
+
+    for c in chars.by_ref() {
+        at.column += 1;
+        if c == '\n' {
+            at.line += 1;
+            at.column = 0;
+        }
+        match (previous, c) {
+            ('#', '|') => depth += 1,
+            ('|', '#') => depth -= 1,
+            _ => {
+                previous = c;
+                continue;
+            }
+        }
+        if depth == 0 {
+            return true;
+        }
+        // The two characters make a single delimiter, so `#|#` doesn't close
+        previous = ' ';
+    }
+    false
+}
+
+/// Checks that the brackets of the first form in `input` are balanced,
+/// ignoring anything inside string literals and block comments.
+pub fn check_brackets(input: &str) -> Result<(), BracketError> {
+    check_brackets_from(input, Position { line: 1, column: 1 })
+}
+
+/// Like [`check_brackets`], for input that starts at `start` in a larger
+/// source, so positions are those in the source.
+pub fn check_brackets_from(input: &str, start: Position) -> Result<(), BracketError> {
+    let mut open: Vec<(char, Position)> = Vec::new();
+    let mut at = Position { column: start.column - 1, ..start };
+    // Just past the last character that isn't whitespace
+    let mut end = start;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        at.column += 1;
+        if !c.is_whitespace() {
+            end = Position { column: at.column + 1, ..at };
+        }
+        match c {
+            '\n' => {
+                at.line += 1;
+                at.column = 0;
+            }
+            '"' => {
+                while let Some(c) = chars.next() {
+                    at.column += 1;
+                    end = Position { column: at.column + 1, ..at };
+                    match c {
+                        '"' => break,
+                        '\\' => {
+                            chars.next();
+                            at.column += 1;
+                            end.column += 1;
+                        }
+                        '\n' => {
+                            at.line += 1;
+                            at.column = 0;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            // Comments are skipped whole, and one left open ends the input
+            '#' if chars.clone().next() == Some('|') && !skip_block_comment(&mut chars, &mut at) => break,
+            '(' | '[' | '{' => open.push((c, at)),
+            ')' | ']' | '}' => match open.pop() {
+                Some((open_char, opened)) if closing(open_char) != c => {
+                    return Err(BracketError::Mismatched {
+                        open: open_char,
+                        expected: closing(open_char),
+                        opened,
+                        found: c,
+                        at,
+                    });
+                }
+                Some(_) if open.is_empty() => return Ok(()),
+                Some(_) => {}
+                None => return Err(BracketError::Unopened { found: c, at }),
+            },
+            _ => {}
+        }
+    }
+
+    match open.pop() {
+        Some((open_char, opened)) => Err(BracketError::Unclosed {
+            open: open_char,
+            expected: closing(open_char),
+            opened,
+            at: end,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Whether every bracket, string and block comment opened in `input` is
+/// closed, so a line editor knows to keep reading instead of submitting. Mismatched
+/// brackets count as complete, for the parser to report.
+pub fn is_complete(input: &str) -> bool {
+    let mut depth = 0usize;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => {
+                        chars.next();
+                    }
+                    Some(_) => {}
+                    None => return false,
+                }
+            },
+            '#' if chars.clone().next() == Some('|')
+                && !skip_block_comment(&mut chars, &mut Position { line: 1, column: 1 }) =>
+            {
+                return false
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth == 0 => return true,
+            ')' | ']' | '}' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    depth == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balanced() {
+        assert_eq!(check_brackets("(+ 1 (* 2 3))"), Ok(()));
+        assert_eq!(check_brackets("(print \"(]\")"), Ok(()));
+        assert_eq!(check_brackets("(print \"\\\")\")"), Ok(()));
+        assert_eq!(check_brackets("(f #| ) #| ] |# |# x)"), Ok(()));
+    }
+
+    #[test]
+    fn test_mismatched() {
+        assert_eq!(
+            check_brackets("(+ 1 2]").unwrap_err().to_string(),
+            "expected closing `)` at column 7, found `]`, to match `(` at column 1"
+        );
+    }
+
+    #[test]
+    fn test_unclosed() {
+        assert_eq!(
+            check_brackets("(+ 1\n  (* 2 3)").unwrap_err().to_string(),
+            "expected closing `)` at line 2, column 10, to match `(` at column 1"
+        );
+        assert_eq!(
+            check_brackets("(do!\n  (+ 1 2\n\n").unwrap_err().to_string(),
+            "expected closing `)` at line 2, column 9, to match `(` at line 2, column 3"
+        );
+        assert_eq!(
+            check_brackets("(print \"a)\"").unwrap_err().position(),
+            Position { line: 1, column: 12 }
+        );
+        assert_eq!(
+            check_brackets("(f #| (\n |# (").unwrap_err().to_string(),
+            "expected closing `)` at line 2, column 6, to match `(` at line 2, column 5"
+        );
+    }
+
+    #[test]
+    fn test_unopened() {
+        assert_eq!(
+            check_brackets(") 1").unwrap_err().to_string(),
+            "unexpected `)` at column 1"
+        );
+    }
+
+    #[test]
+    fn test_is_complete() {
+        assert!(is_complete("(+ 1 2)\n(+ 3 4)"));
+        assert!(is_complete("(+ 1 2))"));
+        assert!(!is_complete("(defn! f (x)\n"));
+        assert!(!is_complete("(+ 1 2)\n(print \"a)"));
+        assert!(!is_complete("(print \"a\\\""));
+        assert!(is_complete("#| ( |# (+ 1 2)"));
+        assert!(!is_complete("#| #| |# (+ 1 2)"));
+    }
+
+    #[test]
+    fn test_positions_in_a_larger_source() {
+        let source = "(+ 1 2)\n  (- 3";
+        let error = check_brackets_from(&source[10..], Position::of(source, 10)).unwrap_err();
+
+        assert_eq!(error.position(), Position { line: 2, column: 7 });
+    }
+
+    #[test]
+    fn test_only_the_first_form_is_checked() {
+        assert_eq!(check_brackets("(+ 1 2) ]"), Ok(()));
+    }
+}
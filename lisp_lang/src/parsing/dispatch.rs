@@ -0,0 +1,116 @@
+use std::cell::Cell;
+
+use nom::{
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::{map, recognize},
+    error::{context, ErrorKind, ParseError},
+    multi::many0,
+    sequence::{delimited, preceded},
+};
+
+use super::{error::ParseFailure, parse_expression, parse_list, LispVal, ParseResult};
+use crate::builder::{call, list, sym};
+
+thread_local! {
+    /// Whether the parser is inside the body of a `#(...)`.
+    static IN_LAMBDA: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The index of an argument symbol, 1 for both `%` and `%1`.
+fn argument_index(name: &str) -> Option<usize> {
+    match name.strip_prefix('%')? {
+        "" => Some(1),
+        digits => digits.parse().ok().filter(|index| *index > 0),
+    }
+}
+
+/// Renames `%` to `%1` in `value`, keeping track of the highest argument
+/// used.
+fn number_arguments(value: LispVal, highest: &mut usize) -> LispVal {
+    match value {
+        LispVal::Symbol(name) => match argument_index(&name) {
+            Some(index) => {
+                *highest = (*highest).max(index);
+                sym(&format!("%{index}"))
+            }
+            None => LispVal::Symbol(name),
+        },
+        LispVal::List(values) => LispVal::List(values.into_iter().map(|v| number_arguments(v, highest)).collect()),
+        LispVal::Vector(values) => LispVal::Vector(values.into_iter().map(|v| number_arguments(v, highest)).collect()),
+        LispVal::Map(entries) => LispVal::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (number_arguments(key, highest), number_arguments(value, highest)))
+                .collect(),
+        ),
+        LispVal::Set(elements) => LispVal::Set(elements.into_iter().map(|v| number_arguments(v, highest)).collect()),
+        LispVal::Pair(head, tail) => LispVal::cons(number_arguments(*head, highest), number_arguments(*tail, highest)),
+        LispVal::Unevaluated(value) => number_arguments(*value, highest).to_unevaluated(),
+        value => value,
+    }
+}
+
+/// `#(+ % 1)` reads as `(fn! (%1) (+ %1 1))`. The arguments are `%1`, `%2`
+/// and so on, with `%` short for `%1`, and the function takes as many as
+/// the highest one used. Inside, `%` is the argument rather than the
+/// remainder, which is still available as `mod`. They can't be nested, as
+/// the arguments of the inner one would be ambiguous.
+pub fn parse_anonymous_function(input: &str) -> ParseResult<'_, LispVal> {
+    if IN_LAMBDA.with(Cell::get) {
+        return Err(nom::Err::Failure(ParseFailure::message(input, "`#(...)` can't be nested")));
+    }
+
+    IN_LAMBDA.with(|in_lambda| in_lambda.set(true));
+    let result = context("anonymous function", parse_list)(input);
+    IN_LAMBDA.with(|in_lambda| in_lambda.set(false));
+    let (rest, body) = result?;
+
+    let mut highest = 0;
+    let body = number_arguments(LispVal::List(body), &mut highest);
+    let parameters = list((1..=highest).map(|index| sym(&format!("%{index}"))));
+
+    Ok((rest, call("fn!", [parameters, body])))
+}
+
+/// `#{a b c}` reads as a set, dropping repeated elements.
+pub fn parse_set(input: &str) -> ParseResult<'_, LispVal> {
+    context(
+        "set",
+        map(delimited(char('{'), many0(parse_expression), char('}')), |elements| {
+            LispVal::Set(elements.into_iter().collect())
+        }),
+    )(input)
+}
+
+/// `#| ... |#` reads as whitespace, to comment out any amount of text.
+/// Comments nest, so commenting out code that has one in it works.
+pub fn parse_block_comment(input: &str) -> ParseResult<'_, &str> {
+    let Some(mut rest) = input.strip_prefix("#|") else {
+        return Err(nom::Err::Error(ParseFailure::from_error_kind(input, ErrorKind::Tag)));
+    };
+
+    let mut depth = 1;
+    while depth > 0 {
+        if let Some(after) = rest.strip_prefix("#|") {
+            depth += 1;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("|#") {
+            depth -= 1;
+            rest = after;
+        } else {
+            let mut chars = rest.chars();
+            if chars.next().is_none() {
+                return Err(nom::Err::Failure(ParseFailure::message(input, "unterminated block comment")));
+            }
+            rest = chars.as_str();
+        }
+    }
+
+    Ok((rest, &input[..input.len() - rest.len()]))
+}
+
+/// `#_` and the form after it read as whitespace, to comment out a form.
+pub fn parse_discard(input: &str) -> ParseResult<'_, &str> {
+    recognize(preceded(tag("#_"), parse_expression))(input)
+}
@@ -1,21 +1,151 @@
-use super::LispType;
+use std::num::ParseIntError;
 
+use nom::error::{ContextError, ErrorKind, FromExternalError, ParseError};
 
-#[derive(Debug)]
+use super::{brackets::check_brackets_from, LispType, Position};
+
+#[derive(Debug, thiserror::Error)]
+#[error("Expected {expected}, got {got}")]
 pub struct LispValUnwrapError {
     pub expected: LispType,
     pub got: LispType,
 }
 
-impl std::fmt::Display for LispValUnwrapError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "Expected {}, got {}",
-            self.expected.to_string(),
-            self.got.to_string()
-        )
+/// What the parser was looking for where it failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expected {
+    Char(char),
+    /// The name given to a parser with `context`, like `list`.
+    Context(&'static str),
+    /// Why otherwise well formed input was rejected.
+    Message(String),
+    Kind(ErrorKind),
+}
+
+/// The error of the parsers. Of the alternatives tried, it keeps the one
+/// that got furthest into the input, which is usually where the mistake
+/// is, instead of the last one tried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFailure<'a> {
+    /// The input left where parsing failed.
+    pub input: &'a str,
+    pub expected: Expected,
+}
+
+impl<'a> ParseFailure<'a> {
+    pub fn message(input: &'a str, message: impl Into<String>) -> Self {
+        Self {
+            input,
+            expected: Expected::Message(message.into()),
+        }
+    }
+}
+
+impl<'a> ParseError<&'a str> for ParseFailure<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        Self {
+            input,
+            expected: Expected::Kind(kind),
+        }
+    }
+
+    fn append(_: &'a str, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: &'a str, c: char) -> Self {
+        Self {
+            input,
+            expected: Expected::Char(c),
+        }
+    }
+
+    fn or(self, other: Self) -> Self {
+        if other.input.len() < self.input.len() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl<'a> ContextError<&'a str> for ParseFailure<'a> {
+    /// A parser that failed right where it started is named by its
+    /// context, so `)` where an expression goes is "expected expression".
+    fn add_context(input: &'a str, context: &'static str, other: Self) -> Self {
+        match other.expected {
+            Expected::Message(_) => other,
+            _ if other.input.len() == input.len() => Self {
+                input,
+                expected: Expected::Context(context),
+            },
+            _ => other,
+        }
+    }
+}
+
+impl<'a> FromExternalError<&'a str, ParseIntError> for ParseFailure<'a> {
+    fn from_external_error(input: &'a str, kind: ErrorKind, _: ParseIntError) -> Self {
+        Self::from_error_kind(input, kind)
+    }
+}
+
+impl<'a> FromExternalError<&'a str, String> for ParseFailure<'a> {
+    fn from_external_error(input: &'a str, _: ErrorKind, message: String) -> Self {
+        Self::message(input, message)
+    }
+}
+
+/// Why a form failed to parse, with where in its source the problem is.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{message}")]
+pub struct SyntaxError {
+    pub message: String,
+    pub position: Position,
+}
+
+impl SyntaxError {
+    /// Explains why the form starting `offset` bytes into `source` failed
+    /// to parse. Unbalanced brackets are reported as such, anything else at
+    /// the furthest point the parser got to.
+    pub fn new(source: &str, offset: usize, error: &nom::Err<ParseFailure>) -> SyntaxError {
+        let start = Position::of(source, offset);
+        if let Err(e) = check_brackets_from(&source[offset..], start) {
+            return SyntaxError {
+                message: e.to_string(),
+                position: e.position(),
+            };
+        }
+
+        let failure = match error {
+            nom::Err::Error(failure) | nom::Err::Failure(failure) => Some(failure),
+            nom::Err::Incomplete(_) => None,
+        };
+        let at = failure.map_or(source.trim_end().len(), |failure| source.len() - failure.input.len());
+        let position = Position::of(source, at);
+        let found = match source[at..].chars().next() {
+            Some(c) => format!("`{c}`"),
+            None => "end of input".to_string(),
+        };
+
+        let message = match failure.map(|failure| &failure.expected) {
+            Some(Expected::Char(c)) => format!("expected `{c}` at {position}, found {found}"),
+            Some(Expected::Context(context)) => format!("expected {context} at {position}, found {found}"),
+            Some(Expected::Message(message)) => format!("{message} at {position}"),
+            Some(Expected::Kind(_)) | None => format!("unexpected {found} at {position}"),
+        };
+
+        SyntaxError { message, position }
     }
 }
 
-impl std::error::Error for LispValUnwrapError {}
\ No newline at end of file
+/// Why input that should hold exactly one form doesn't.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseCompleteError {
+    #[error(transparent)]
+    Syntax(#[from] SyntaxError),
+    /// Something other than whitespace follows the form, starting `offset`
+    /// bytes into the input.
+    #[error("unexpected input at {position}: {rest}")]
+    TrailingInput { rest: String, offset: usize, position: Position },
+}
@@ -0,0 +1,52 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use nom::{
+    character::complete::{char, digit1},
+    combinator::{map, map_res},
+    error::context,
+    sequence::{delimited, pair},
+};
+
+use super::{error::ParseFailure, parse_expression, LispVal, ParseResult};
+
+thread_local! {
+    /// Values labelled with `#n=` in the expression being parsed.
+    static LABELS: RefCell<HashMap<u64, LispVal>> = RefCell::new(HashMap::new());
+}
+
+/// Forgets the labels of the previous expression, so `#0#` only refers to
+/// a `#0=` in the same top-level expression.
+pub fn clear_labels() {
+    LABELS.with(|labels| labels.borrow_mut().clear());
+}
+
+fn parse_label_number(input: &str) -> ParseResult<'_, u64> {
+    map_res(digit1, str::parse::<u64>)(input)
+}
+
+/// `#0=(1 2)` reads as `(1 2)` and labels it so `#0#` can repeat it.
+pub fn parse_label_definition(input: &str) -> ParseResult<'_, LispVal> {
+    context(
+        "datum label",
+        map(
+            pair(delimited(char('#'), parse_label_number, char('=')), parse_expression),
+            |(label, value)| {
+                LABELS.with(|labels| labels.borrow_mut().insert(label, value.clone()));
+                value
+            },
+        ),
+    )(input)
+}
+
+/// `#0#` reads as the value labelled `#0=` earlier in the expression.
+pub fn parse_label_reference(input: &str) -> ParseResult<'_, LispVal> {
+    let (rest, label) = context(
+        "datum label",
+        delimited(char('#'), parse_label_number, char('#')),
+    )(input)?;
+
+    match LABELS.with(|labels| labels.borrow().get(&label).cloned()) {
+        Some(value) => Ok((rest, value)),
+        None => Err(nom::Err::Failure(ParseFailure::message(input, format!("unknown label `#{label}#`")))),
+    }
+}
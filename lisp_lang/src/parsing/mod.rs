@@ -1,41 +1,184 @@
+pub use compact_str::CompactString;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{alpha1, alphanumeric1, char, digit1, multispace0, one_of},
-    combinator::{map, map_res, opt, recognize},
-    error::context,
-    multi::{many0, many0_count, many1},
-    sequence::{delimited, pair, preceded, terminated},
+    character::complete::{char, digit1, multispace1, one_of, satisfy},
+    combinator::{eof, map, not, opt, peek, recognize, value, verify},
+    error::{context, ParseError},
+    multi::{many0, many0_count},
+    sequence::{delimited, pair, preceded, terminated, tuple},
     IResult,
 };
+use std::{
+    cell::Cell,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use unicode_ident::{is_xid_continue, is_xid_start};
+
 use crate::{parsing::string::parse_string};
 
-use self::error::LispValUnwrapError;
+use self::dispatch::{parse_anonymous_function, parse_block_comment, parse_discard, parse_set};
+use self::error::{LispValUnwrapError, ParseCompleteError, ParseFailure, SyntaxError};
+use self::operator::is_operator;
+use self::label::{clear_labels, parse_label_definition, parse_label_reference};
+use self::quasiquote::{in_template, parse_quasiquote, parse_unquote, reset_depth};
+use self::reader::parse_reader_macro;
+use self::span::spanned;
+pub use self::operator::{with_operator_chars, DEFAULT_OPERATOR_CHARS};
+pub use self::brackets::{check_brackets, is_complete, BracketError, Position};
+pub use self::reader::{register_reader_macro, ReaderMacro};
+pub use self::span::{parse_spanned, Span, Spans};
+pub use self::stream::{ParseStream, StreamError};
+pub use self::string::escape;
 
+mod brackets;
+mod dispatch;
+mod label;
+mod operator;
+mod quasiquote;
+mod reader;
+#[cfg(feature = "serde")]
+mod serialize;
+mod span;
+mod stream;
 mod string;
 pub mod error;
 
-#[derive(Debug, PartialEq, Clone)]
+/// The result of the parsers, which fail with a [`ParseFailure`].
+pub type ParseResult<'a, T> = IResult<&'a str, T, ParseFailure<'a>>;
+
+/// Symbols and strings are stored inline when short, so most of them are
+/// cloned without allocating.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LispVal {
-    Symbol(String),
-    String(String),
+    Symbol(CompactString),
+    String(CompactString),
+    /// `:name`, which evaluates to itself. The name is kept without the
+    /// colon.
+    Keyword(CompactString),
     List(Vec<LispVal>),
+    /// `[a b c]`, which evaluates its elements into a list instead of
+    /// being called, so data needs no quoting.
+    Vector(Vec<LispVal>),
     Number(i64),
+    Float(f64),
+    /// A complex number, as its real and imaginary parts.
+    Complex(f64, f64),
     Boolean(bool),
     Unevaluated(Box<LispVal>),
-    Function { parameters: Vec<String>, body: Box<LispVal>, applied: Vec<LispVal> },
+    /// `doc` is the docstring given to `defn!`. It is metadata, so it
+    /// doesn't take part in equality. `captured` is what the body sees
+    /// besides its parameters and the top-level definitions.
+    Function {
+        parameters: Vec<String>,
+        body: Box<LispVal>,
+        applied: Vec<LispVal>,
+        doc: Option<String>,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        captured: Arc<Captured>,
+    },
+    Record { name: String, fields: Vec<(String, LispVal)> },
+    MultiMethod { name: String, dispatch: Box<LispVal>, methods: Vec<(LispVal, LispVal)> },
+    /// Min-priority queue. Entries are keyed by priority and insertion
+    /// order, so equal priorities pop first-in first-out.
+    Heap {
+        #[cfg_attr(feature = "serde", serde(with = "serialize::heap_entries"))]
+        entries: im::OrdMap<(i64, u64), LispVal>,
+        next: u64,
+    },
+    Deque(im::Vector<LispVal>),
+    Map(#[cfg_attr(feature = "serde", serde(with = "serialize::map_entries"))] MapEntries),
+    /// `#{a b c}`, which holds each value once.
+    Set(#[cfg_attr(feature = "serde", serde(with = "serialize::set_elements"))] SetElements),
+    /// `(a . b)`, a cons cell whose tail isn't a list. Chains of them are
+    /// improper lists, `(a b . c)`. Build them with `LispVal::cons`, which
+    /// keeps cells ending in a list as plain lists.
+    Pair(Box<LispVal>, Box<LispVal>),
+    /// `nil`, a value standing for no value, like a missing element. Unlike
+    /// void, which is what definitions and `print` return, it is data, and
+    /// unlike `()` it isn't a list.
+    Nil,
     Void(),
 }
 
-#[derive(PartialEq, Debug)]
+/// Entries of a `LispVal::Map`.
+pub type MapEntries = im::HashMap<LispVal, LispVal>;
+
+/// The entries of a map sorted by their printed key, the order maps are
+/// printed in and `keys` and `vals` list them in.
+pub fn sorted_entries(entries: &MapEntries) -> Vec<(&LispVal, &LispVal)> {
+    let mut sorted: Vec<_> = entries.iter().collect();
+    sorted.sort_by_cached_key(|(key, _)| key.to_string());
+    sorted
+}
+
+/// Elements of a `LispVal::Set`.
+pub type SetElements = im::HashSet<LispVal>;
+
+/// Names bound to values, like the bindings of a frame.
+pub type Environment = im::HashMap<String, LispVal>;
+
+/// What a `LispVal::Function` closes over.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Captured {
+    /// The local bindings visible where the function was created.
+    pub bindings: Environment,
+    /// The functions bound along with it by `letrec!`, or itself when
+    /// defined with `defn!` inside another function, so it can call them
+    /// by name. They are stored without the group and given it again on
+    /// every call, which avoids a cycle of references.
+    pub group: Environment,
+}
+
+/// The elements of a set sorted by how they print, the order sets are
+/// printed and listed in.
+pub fn sorted_elements(elements: &SetElements) -> Vec<&LispVal> {
+    let mut sorted: Vec<_> = elements.iter().collect();
+    sorted.sort_by_cached_key(|element| element.to_string());
+    sorted
+}
+
+/// Hashes `items` in a way that doesn't depend on their order, for maps and
+/// sets, where equal values can iterate in different orders.
+fn unordered_hash<T: std::hash::Hash, H: std::hash::Hasher>(items: impl ExactSizeIterator<Item = T>, state: &mut H) {
+    let count = items.len();
+    let combined = items
+        .map(|item| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            item.hash(&mut hasher);
+            std::hash::Hasher::finish(&hasher)
+        })
+        .fold(0u64, u64::wrapping_add);
+    std::hash::Hash::hash(&(count, combined), state);
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum LispType {
     Any,
     Symbol,
     String,
+    Keyword,
     List,
     Number,
+    Float,
+    Complex,
     Boolean,
     Function,
+    Record,
+    Heap,
+    Deque,
+    Map,
+    Set,
+    Pair,
+    /// A value written with `'` where it isn't evaluated, like the
+    /// arguments of a special form.
+    Quoted,
+    Nil,
     Void,
 }
 
@@ -45,11 +188,102 @@ impl std::fmt::Display for LispType {
             LispType::Any => write!(f, "any"),
             LispType::Symbol => write!(f, "symbol"),
             LispType::String => write!(f, "string"),
+            LispType::Keyword => write!(f, "keyword"),
             LispType::List => write!(f, "list"),
             LispType::Number => write!(f, "number"),
+            LispType::Float => write!(f, "float"),
+            LispType::Complex => write!(f, "complex"),
             LispType::Boolean => write!(f, "boolean"),
             LispType::Function => write!(f, "function"),
+            LispType::Record => write!(f, "record"),
+            LispType::Heap => write!(f, "heap"),
+            LispType::Deque => write!(f, "deque"),
+            LispType::Map => write!(f, "map"),
+            LispType::Set => write!(f, "set"),
+            LispType::Pair => write!(f, "pair"),
+            LispType::Quoted => write!(f, "quoted value"),
             LispType::Void => write!(f, "void"),
+            LispType::Nil => write!(f, "nil"),
+        }
+    }
+}
+
+/// Floats are equal when their bits are, so values can be hashed and used
+/// as set elements: `NaN` equals itself and `0.0` differs from `-0.0`.
+/// Numeric comparison, which promotes integers, is up to the builtins.
+impl PartialEq for LispVal {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Symbol(a), Self::Symbol(b))
+            | (Self::String(a), Self::String(b))
+            | (Self::Keyword(a), Self::Keyword(b)) => a == b,
+            (Self::List(a), Self::List(b)) | (Self::Vector(a), Self::Vector(b)) => a == b,
+            (Self::Number(a), Self::Number(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a.to_bits() == b.to_bits(),
+            (Self::Complex(a, b), Self::Complex(c, d)) => a.to_bits() == c.to_bits() && b.to_bits() == d.to_bits(),
+            (Self::Boolean(a), Self::Boolean(b)) => a == b,
+            (Self::Unevaluated(a), Self::Unevaluated(b)) => a == b,
+            (
+                Self::Function { parameters, body, applied, captured, .. },
+                Self::Function {
+                    parameters: other_parameters,
+                    body: other_body,
+                    applied: other_applied,
+                    captured: other_captured,
+                    ..
+                },
+            ) => {
+                parameters == other_parameters
+                    && body == other_body
+                    && applied == other_applied
+                    && captured == other_captured
+            }
+            (Self::Record { name, fields }, Self::Record { name: other_name, fields: other_fields }) => {
+                name == other_name && fields == other_fields
+            }
+            (
+                Self::MultiMethod { name, dispatch, methods },
+                Self::MultiMethod { name: other_name, dispatch: other_dispatch, methods: other_methods },
+            ) => name == other_name && dispatch == other_dispatch && methods == other_methods,
+            (Self::Heap { entries, next }, Self::Heap { entries: other_entries, next: other_next }) => {
+                entries == other_entries && next == other_next
+            }
+            (Self::Deque(a), Self::Deque(b)) => a == b,
+            (Self::Map(a), Self::Map(b)) => a == b,
+            (Self::Set(a), Self::Set(b)) => a == b,
+            (Self::Pair(head, tail), Self::Pair(other_head, other_tail)) => head == other_head && tail == other_tail,
+            (Self::Void(), Self::Void()) | (Self::Nil, Self::Nil) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for LispVal {}
+
+impl std::hash::Hash for LispVal {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Symbol(s) | Self::String(s) | Self::Keyword(s) => s.hash(state),
+            Self::List(values) | Self::Vector(values) => values.hash(state),
+            Self::Number(n) => n.hash(state),
+            Self::Float(f) => f.to_bits().hash(state),
+            Self::Complex(re, im) => (re.to_bits(), im.to_bits()).hash(state),
+            Self::Boolean(b) => b.hash(state),
+            Self::Unevaluated(value) => value.hash(state),
+            Self::Function { parameters, body, applied, captured, .. } => {
+                (parameters, body, applied).hash(state);
+                unordered_hash(captured.bindings.iter(), state);
+                unordered_hash(captured.group.iter(), state);
+            }
+            Self::Record { name, fields } => (name, fields).hash(state),
+            Self::MultiMethod { name, dispatch, methods } => (name, dispatch, methods).hash(state),
+            Self::Heap { entries, next } => (entries, next).hash(state),
+            Self::Deque(values) => values.hash(state),
+            Self::Map(entries) => unordered_hash(entries.iter(), state),
+            Self::Set(elements) => unordered_hash(elements.iter(), state),
+            Self::Pair(head, tail) => (head, tail).hash(state),
+            Self::Void() | Self::Nil => {}
         }
     }
 }
@@ -65,13 +299,23 @@ impl LispVal {
     pub fn to_type(&self) -> LispType {
         match self {
             Self::Void() => LispType::Void,
+            Self::Nil => LispType::Nil,
             Self::Symbol(_) => LispType::Symbol,
             Self::Number(_) => LispType::Number,
+            Self::Float(_) => LispType::Float,
+            Self::Complex(..) => LispType::Complex,
             Self::String(_) => LispType::String,
-            Self::List(_) => LispType::List,
+            Self::Keyword(_) => LispType::Keyword,
+            Self::List(_) | Self::Vector(_) => LispType::List,
             Self::Boolean(_) => LispType::Boolean,
-            Self::Function { .. } => LispType::Function,
-            Self::Unevaluated(v) => v.to_type(),
+            Self::Function { .. } | Self::MultiMethod { .. } => LispType::Function,
+            Self::Record { .. } => LispType::Record,
+            Self::Heap { .. } => LispType::Heap,
+            Self::Deque(_) => LispType::Deque,
+            Self::Map(_) => LispType::Map,
+            Self::Set(_) => LispType::Set,
+            Self::Pair(..) => LispType::Pair,
+            Self::Unevaluated(_) => LispType::Quoted,
         }
     }
 
@@ -79,6 +323,15 @@ impl LispVal {
         Self::Unevaluated(Box::new(self.clone()))
     }
 
+    /// The value a quote wraps, or the value itself when it isn't quoted, so
+    /// forms can take `'(x y)` where they take `(x y)`.
+    pub fn unquoted(&self) -> &Self {
+        match self {
+            Self::Unevaluated(value) => value.unquoted(),
+            value => value,
+        }
+    }
+
     pub fn concat(&self, other: &Self) -> Self {
         match (self, other) {
             (LispVal::List(left), LispVal::List(right)) => {
@@ -102,47 +355,308 @@ impl LispVal {
         matches!(self, Self::Void())
     }
 
+    pub fn is_nil(&self) -> bool {
+        matches!(self, Self::Nil)
+    }
+
+    /// A cell with `head` in front of `tail`. In front of a list or `nil`
+    /// that is just a longer list; anything else makes a pair.
+    pub fn cons(head: LispVal, tail: LispVal) -> Self {
+        match tail {
+            Self::List(mut values) => {
+                values.insert(0, head);
+                Self::List(values)
+            }
+            Self::Nil => Self::List(vec![head]),
+            tail => Self::Pair(Box::new(head), Box::new(tail)),
+        }
+    }
+
+    /// The heads of a chain of pairs and the tail it ends with, `[a, b]` and
+    /// `c` for `(a b . c)`. Other values are a chain of no heads.
+    pub fn pair_parts(&self) -> (Vec<&LispVal>, &LispVal) {
+        let mut heads = vec![];
+        let mut tail = self;
+        while let Self::Pair(head, rest) = tail {
+            heads.push(head.as_ref());
+            tail = rest;
+        }
+        (heads, tail)
+    }
+
     pub fn is_macro(&self) -> bool {
-        matches!(self, Self::Symbol(v) if v.ends_with("!"))
+        matches!(self, Self::Symbol(v) if v.ends_with('!'))
+    }
+
+    /// Number of arguments a function still needs before its body runs.
+    pub fn remaining_arity(&self) -> Option<usize> {
+        match self {
+            Self::Function { parameters, applied, .. } => {
+                Some(parameters.len().saturating_sub(applied.len()))
+            }
+            _ => None,
+        }
+    }
+
+    /// The body of a partially applied function with the applied arguments
+    /// substituted and the missing ones shown as `_`, e.g. `(+ 2 _)`.
+    pub fn partial_body(&self) -> Option<LispVal> {
+        fn substitute(expr: &LispVal, bound: &[(&String, LispVal)]) -> LispVal {
+            match expr {
+                LispVal::Symbol(name) => bound
+                    .iter()
+                    .find(|(parameter, _)| *parameter == name)
+                    .map(|(_, value)| value.clone())
+                    .unwrap_or_else(|| expr.clone()),
+                LispVal::List(values) => values.iter().map(|v| substitute(v, bound)).collect(),
+                LispVal::Unevaluated(value) => substitute(value, bound).to_unevaluated(),
+                _ => expr.clone(),
+            }
+        }
+
+        match self {
+            Self::Function { parameters, body, applied, .. } => {
+                let placeholders = std::iter::repeat(LispVal::Symbol("_".into()));
+                let bound: Vec<_> = parameters
+                    .iter()
+                    .zip(applied.iter().cloned().chain(placeholders))
+                    .collect();
+
+                Some(substitute(body, &bound))
+            }
+            _ => None,
+        }
+    }
+
+    /// Calls `visitor` on this value and then on every value nested in it,
+    /// depth first: list elements, quoted expressions, record fields and the
+    /// contents of heaps and deques.
+    pub fn walk<F: FnMut(&LispVal)>(&self, visitor: &mut F) {
+        visitor(self);
+
+        match self {
+            Self::List(values) | Self::Vector(values) => values.iter().for_each(|v| v.walk(visitor)),
+            Self::Unevaluated(value) => value.walk(visitor),
+            Self::Record { fields, .. } => fields.iter().for_each(|(_, v)| v.walk(visitor)),
+            Self::Heap { entries, .. } => entries.values().for_each(|v| v.walk(visitor)),
+            Self::Deque(values) => values.iter().for_each(|v| v.walk(visitor)),
+            Self::Map(entries) => entries.iter().for_each(|(k, v)| {
+                k.walk(visitor);
+                v.walk(visitor);
+            }),
+            Self::Set(elements) => elements.iter().for_each(|v| v.walk(visitor)),
+            Self::Pair(head, tail) => {
+                head.walk(visitor);
+                tail.walk(visitor);
+            }
+            _ => {}
+        }
+    }
+
+    /// The value one step inside this one: a record field by name, the value
+    /// of the `(key value)` entry of an association list, the value of a map
+    /// under the keyword or string `key`, or a list element by index.
+    pub fn get(&self, key: &str) -> Option<&LispVal> {
+        match self {
+            Self::Record { fields, .. } => {
+                fields.iter().find(|(name, _)| name == key).map(|(_, v)| v)
+            }
+            Self::List(values) => {
+                let entry = values.iter().find_map(|entry| match entry {
+                    Self::List(pair) => match pair.as_slice() {
+                        [Self::Symbol(k) | Self::String(k), value] if k == key => Some(value),
+                        _ => None,
+                    },
+                    _ => None,
+                });
+                entry.or_else(|| key.parse::<usize>().ok().and_then(|i| values.get(i)))
+            }
+            Self::Map(entries) => entries
+                .get(&Self::Keyword(key.into()))
+                .or_else(|| entries.get(&Self::String(key.into()))),
+            _ => None,
+        }
+    }
+
+    /// Follows `path` with [`LispVal::get`] and converts the value found,
+    /// so `value.get_path::<i64>(&["config", "port"])` reads a number out of
+    /// nested records or association lists.
+    pub fn get_path<T: TryFrom<LispVal>>(&self, path: &[&str]) -> Option<T> {
+        path.iter()
+            .try_fold(self, |value, key| value.get(key))
+            .and_then(|value| T::try_from(value.clone()).ok())
     }
 }
 
 
-fn parse_symbol(input: &str) -> IResult<&str, &str> {
-    let parse_operators = recognize(many1(one_of("><+-*/%=")));
-    let parse_identifier = recognize(pair(
-        alt((alpha1, tag("_"))),
-        terminated(many0_count(alt((alphanumeric1, tag("_")))), opt(one_of("?!"))),
+/// Whitespace between expressions, including `#| ... |#` comments. Commas
+/// count as whitespace, so data pasted as `(1, 2, 3)` reads as `(1 2 3)`,
+/// except inside a quasiquote, where they unquote.
+fn whitespace(input: &str) -> ParseResult<'_, &str> {
+    let comma = verify(tag(","), |_: &str| !in_template());
+    recognize(many0_count(alt((multispace1, comma, parse_block_comment, parse_discard))))(input)
+}
+
+/// Identifiers follow Unicode's identifier classes, so `λ`, `résultat` and
+/// `日本語` are names, with `-` allowed after the first character.
+fn parse_identifier(input: &str) -> ParseResult<'_, &str> {
+    recognize(pair(
+        satisfy(|c| c == '_' || is_xid_start(c)),
+        terminated(
+            many0_count(satisfy(|c| c == '-' || is_xid_continue(c))),
+            opt(one_of("?!")),
+        ),
+    ))(input)
+}
+
+/// Symbols are identifiers and operators, freely mixed after their first
+/// character, so `->`, `|>`, `>>=` and `not=` are all names. `%1`, `%2`,
+/// ... are single symbols, the arguments of `#(...)`.
+fn parse_symbol(input: &str) -> ParseResult<'_, &str> {
+    let parse_argument = recognize(pair(char('%'), digit1));
+    let parse_name = recognize(pair(
+        alt((satisfy(is_operator), satisfy(|c| c == '_' || is_xid_start(c)))),
+        terminated(
+            many0_count(satisfy(|c| c == '-' || is_operator(c) || is_xid_continue(c))),
+            opt(one_of("?!")),
+        ),
     ));
 
-    context("symbol", alt((parse_operators, parse_identifier)))(input)
+    context("symbol", alt((parse_argument, parse_name)))(input)
+}
+
+/// Keywords are named like identifiers, so `:name` and `:empty?` parse
+/// but `:+` doesn't.
+fn parse_keyword(input: &str) -> ParseResult<'_, &str> {
+    context("keyword", preceded(char(':'), parse_identifier))(input)
 }
 
-fn parse_boolean(input: &str) -> IResult<&str, bool> {
+/// `nil`, but not names that start with it, like `nil?` or `nil-count`.
+fn parse_nil(input: &str) -> ParseResult<'_, LispVal> {
+    let name_continues = satisfy(|c| "-?!".contains(c) || is_operator(c) || is_xid_continue(c));
+
+    context("nil", value(LispVal::Nil, terminated(tag("nil"), not(name_continues))))(input)
+}
+
+fn parse_boolean(input: &str) -> ParseResult<'_, bool> {
     context(
         "boolean",
         alt((map(tag("true"), |_| true), map(tag("false"), |_| false))),
     )(input)
 }
 
-fn parse_number(input: &str) -> IResult<&str, i64> {
-    context(
+/// Digits, which can be grouped with underscores between them, as in
+/// `1_000_000`. The underscores are dropped before the number is read.
+fn digits(input: &str) -> ParseResult<'_, &str> {
+    recognize(pair(digit1, many0_count(preceded(char('_'), digit1))))(input)
+}
+
+/// Fails when a number is followed by what can only be a malformed part of
+/// it, like the exponent without digits of `1e` or `1.5e+`, or the
+/// underscore of `1_` and `1__0` that isn't between two digits.
+fn check_number_end(rest: &str) -> Result<(), nom::Err<ParseFailure<'_>>> {
+    if rest.starts_with(['e', 'E']) {
+        return Err(nom::Err::Failure(ParseFailure::message(rest, "expected digits in the exponent")));
+    }
+    if rest.starts_with('_') {
+        return Err(nom::Err::Failure(ParseFailure::message(rest, "expected a digit after `_`")));
+    }
+
+    Ok(())
+}
+
+/// Floats need digits on both sides of the point, so `1.` and `.5` don't
+/// parse as numbers, or an exponent, as in `1e10` and `2.5e-3`. Exponents
+/// too large for a float are an error rather than infinity.
+fn parse_float(input: &str) -> ParseResult<'_, f64> {
+    let exponent = || recognize(tuple((one_of("eE"), opt(one_of("+-")), digits)));
+    let fraction = pair(char('.'), digits);
+    let (rest, text) = context(
+        "float",
+        recognize(tuple((
+            opt(one_of("+-")),
+            digits,
+            alt((recognize(pair(fraction, opt(exponent()))), exponent())),
+        ))),
+    )(input)?;
+    check_number_end(rest)?;
+
+    match text.replace('_', "").parse::<f64>() {
+        Ok(float) if float.is_finite() => Ok((rest, float)),
+        _ => Err(nom::Err::Failure(ParseFailure::message(input, "number too large for a float"))),
+    }
+}
+
+fn parse_number(input: &str) -> ParseResult<'_, i64> {
+    let (rest, text) = context(
         "number",
-        map_res(
-            recognize(preceded(opt(alt((char('-'), char('+')))), digit1)),
-            str::parse::<i64>,
+        recognize(preceded(opt(alt((char('-'), char('+')))), digits)),
+    )(input)?;
+    check_number_end(rest)?;
+
+    match text.replace('_', "").parse::<i64>() {
+        Ok(number) => Ok((rest, number)),
+        Err(_) => Err(nom::Err::Failure(ParseFailure::message(input, "number too large for an integer"))),
+    }
+}
+
+/// `(a b c)`, or `(a b . c)` when its last tail isn't `()`. A list after
+/// the dot continues the list, so `(a . (b))` is `(a b)`. The dot needs
+/// space after it, so `(1 .5)` is an error rather than a pair.
+fn parse_list_or_pair(input: &str) -> ParseResult<'_, LispVal> {
+    let (rest, (elements, last)) = context(
+        "list",
+        delimited(
+            char('('),
+            pair(many0(parse_expression), opt(preceded(pair(char('.'), multispace1), parse_expression))),
+            char(')'),
         ),
-    )(input)
+    )(input)?;
+
+    match last {
+        None => Ok((rest, LispVal::List(elements))),
+        Some(_) if elements.is_empty() => {
+            Err(nom::Err::Failure(ParseFailure::message(&input[1..], "expected a value before `.`")))
+        }
+        Some(last) => Ok((rest, elements.into_iter().rev().fold(last, |tail, head| LispVal::cons(head, tail)))),
+    }
 }
 
-fn parse_list<'a>(input: &str) -> IResult<&str, Vec<LispVal>> {
+fn parse_list(input: &str) -> ParseResult<'_, Vec<LispVal>> {
     context(
         "list",
         delimited(char('('), many0(parse_expression), char(')')),
     )(input)
 }
 
-fn parse_unevaluated(input: &str) -> IResult<&str, LispVal> {
+fn parse_vector(input: &str) -> ParseResult<'_, Vec<LispVal>> {
+    context(
+        "vector",
+        delimited(char('['), many0(parse_expression), char(']')),
+    )(input)
+}
+
+/// `{key value ...}`, which needs a value for every key.
+fn parse_map(input: &str) -> ParseResult<'_, MapEntries> {
+    let (rest, values) = context(
+        "map",
+        delimited(char('{'), many0(parse_expression), char('}')),
+    )(input)?;
+
+    if !values.len().is_multiple_of(2) {
+        // Points at the closing brace, where the value is missing
+        let closing = &input[input.len() - rest.len() - 1..];
+        return Err(nom::Err::Failure(ParseFailure::message(closing, "expected a value for the last key of the map")));
+    }
+
+    let entries = values
+        .chunks(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()))
+        .collect();
+    Ok((rest, entries))
+}
+
+fn parse_unevaluated(input: &str) -> ParseResult<'_, LispVal> {
     context(
         "unevaluated",
         preceded(
@@ -152,68 +666,185 @@ fn parse_unevaluated(input: &str) -> IResult<&str, LispVal> {
     )(input)
 }
 
-fn parse_expression<'a>(input: &str) -> IResult<&str, LispVal> {
-    context(
-        "expression",
-        delimited(
-            opt(multispace0),
-            alt((
-                parse_unevaluated,
-                map(parse_boolean, LispVal::Boolean),
-                map(parse_number, LispVal::Number),
-                map(parse_symbol, |v| LispVal::Symbol(v.into())),
-                map(parse_string, |v| LispVal::String(v.into())),
-                map(parse_list, |v| LispVal::List(v.into())),
-            )),
-            opt(multispace0),
-        ),
-    )(input)
+/// A parser for the forms written `#` and then a given character, which
+/// receives the input after the `#`.
+type Dispatch = fn(&str) -> ParseResult<'_, LispVal>;
+
+/// The forms that start with `#`, by the character after it. Labels
+/// (`#0=`) and reader macros (`#tag"text"`) go on with digits and names
+/// instead, and `#_` reads as whitespace, so they aren't in the table.
+const DISPATCH: &[(char, Dispatch)] = &[('(', parse_anonymous_function), ('{', parse_set)];
+
+fn parse_dispatch(input: &str) -> ParseResult<'_, LispVal> {
+    let (rest, _) = char('#')(input)?;
+
+    match DISPATCH.iter().find(|(c, _)| rest.starts_with(*c)) {
+        Some((_, parser)) => parser(rest),
+        None => Err(nom::Err::Error(ParseFailure::from_error_kind(input, nom::error::ErrorKind::Char))),
+    }
+}
+
+/// What can end an atom: whitespace, a closing bracket or the end of the
+/// input.
+fn delimiter(input: &str) -> ParseResult<'_, &str> {
+    alt((eof, multispace1, recognize(one_of(")]},")), tag("#|"), tag("#_")))(input)
+}
+
+/// `parser`, which has to be followed by a [`delimiter`], so `1abc` and
+/// `"a"b` are errors rather than two forms.
+fn atom<'a, O>(mut parser: impl FnMut(&'a str) -> ParseResult<'a, O>) -> impl FnMut(&'a str) -> ParseResult<'a, O> {
+    move |input| {
+        let (rest, value) = parser(input)?;
+        match peek(delimiter)(rest) {
+            Ok(_) => Ok((rest, value)),
+            Err(_) => Err(nom::Err::Failure(ParseFailure::message(rest, "expected a space or closing bracket"))),
+        }
+    }
+}
+
+/// An expression without the whitespace around it.
+fn parse_datum(input: &str) -> ParseResult<'_, LispVal> {
+    alt((
+        parse_unevaluated,
+        parse_quasiquote,
+        parse_unquote,
+        parse_dispatch,
+        parse_label_definition,
+        parse_label_reference,
+        parse_reader_macro,
+        // `true` and `nil` can start a symbol, like `trueish` and `nil?`
+        map(terminated(parse_boolean, peek(delimiter)), LispVal::Boolean),
+        terminated(parse_nil, peek(delimiter)),
+        map(atom(parse_float), LispVal::Float),
+        map(atom(parse_number), LispVal::Number),
+        map(atom(parse_symbol), |v| LispVal::Symbol(v.into())),
+        map(atom(parse_keyword), |v| LispVal::Keyword(v.into())),
+        map(atom(parse_string), |s| LispVal::String(s.into())),
+        parse_list_or_pair,
+        map(parse_vector, LispVal::Vector),
+        map(parse_map, LispVal::Map),
+    ))(input)
+}
+
+/// How deeply expressions can nest, unless changed with [`set_max_depth`].
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+static MAX_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_DEPTH);
+
+thread_local! {
+    /// How many expressions the parser is inside of.
+    static NESTING: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Sets how deeply expressions can nest before the parser gives up with an
+/// error on input like `((((...`. The parser grows its stack as it needs,
+/// so even a large limit can't crash the host. The setting is shared by the
+/// whole process.
+pub fn set_max_depth(depth: usize) {
+    MAX_DEPTH.store(depth, Ordering::Relaxed);
+}
+
+/// How much stack must be left for an expression to be parsed on the
+/// current one. It covers the parsers between one expression and the next.
+const STACK_RED_ZONE: usize = 256 * 1024;
+/// The size of each stack segment allocated once the stack runs low.
+const STACK_SEGMENT_SIZE: usize = 4 * 1024 * 1024;
+
+/// Parses an expression, on a new stack segment whenever the stack runs
+/// low, so a large [`set_max_depth`] can't overflow it and crash the host.
+fn parse_expression(input: &str) -> ParseResult<'_, LispVal> {
+    stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || parse_nested_expression(input))
+}
+
+fn parse_nested_expression(input: &str) -> ParseResult<'_, LispVal> {
+    let depth = NESTING.with(Cell::get);
+    let max = MAX_DEPTH.load(Ordering::Relaxed);
+    if depth >= max {
+        return Err(nom::Err::Failure(ParseFailure::message(input, format!("expressions nested more than {max} deep"))));
+    }
+
+    NESTING.with(|nesting| nesting.set(depth + 1));
+    let result = context("expression", delimited(whitespace, spanned(parse_datum), whitespace))(input);
+    NESTING.with(|nesting| nesting.set(depth));
+    result
+}
+
+pub fn parse(input: &str) -> ParseResult<'_, LispVal> {
+    clear_labels();
+    reset_depth();
+    terminated(parse_expression, whitespace)(input)
+}
+
+/// Turns the result of parsing the whole of `input` into the form, or an
+/// error when the form doesn't parse or something follows it.
+fn complete<'a, T>(input: &'a str, result: ParseResult<'a, T>) -> Result<T, ParseCompleteError> {
+    let (rest, value) = result.map_err(|e| SyntaxError::new(input, 0, &e))?;
+    if rest.is_empty() {
+        return Ok(value);
+    }
+
+    let offset = input.len() - rest.len();
+    Err(ParseCompleteError::TrailingInput {
+        rest: rest.to_string(),
+        offset,
+        position: Position::of(input, offset),
+    })
 }
 
-pub fn parse(input: &str) -> IResult<&str, LispVal> {
-    terminated(parse_expression, multispace0)(input)
+/// Like [`parse`], but `input` has to be a single form: `(+ 1 2) junk` is
+/// an error rather than `(+ 1 2)` with `junk` left over.
+pub fn parse_complete(input: &str) -> Result<LispVal, ParseCompleteError> {
+    complete(input, parse(input))
+}
+
+/// Like [`parse_spanned`], but `input` has to be a single form, as in
+/// [`parse_complete`].
+pub fn parse_complete_spanned(input: &str) -> Result<(LispVal, Spans), ParseCompleteError> {
+    complete(input, parse_spanned(input))
+}
+
+/// Parses every top-level form of `input`, like a whole source file, in
+/// order. Fails on the first form that doesn't parse.
+pub fn parse_program(input: &str) -> Result<Vec<LispVal>, SyntaxError> {
+    let mut forms = Vec::new();
+    let mut rest = input.trim_start();
+
+    while !rest.is_empty() {
+        let (remaining, form) = parse(rest).map_err(|e| SyntaxError::new(input, input.len() - rest.len(), &e))?;
+        forms.push(form);
+        rest = remaining.trim_start();
+    }
+
+    Ok(forms)
 }
 
 #[macro_export]
 macro_rules! parse_it {
     ($input:expr) => {
-        crate::parsing::parse($input).map(|(_, v)| v).unwrap()
+        $crate::parsing::parse($input).map(|(_, v)| v).unwrap()
     };
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::parsing::LispVal;
+    use crate::{
+        builder::{call, keyword, list, quote, sym},
+        parsing::{error::{ParseCompleteError, SyntaxError}, parse_complete, parse_program, LispVal, Position},
+    };
 
     #[test]
     fn test_math_expression() {
-        assert_eq!(parse_it!("(+ 1 2)"), LispVal::List(vec![
-            LispVal::Symbol("+".into()),
-            LispVal::Number(1),
-            LispVal::Number(2),
-        ]));
+        assert_eq!(parse_it!("(+ 1 2)"), call("+", [1, 2]));
     }
 
     #[test]
     fn test_nested_math_expression() {
-        assert_eq!(parse_it!("(+ 1 (* 2 3))"), LispVal::List(vec![
-            LispVal::Symbol("+".into()),
-            LispVal::Number(1),
-            LispVal::List(vec![
-                LispVal::Symbol("*".into()),
-                LispVal::Number(2),
-                LispVal::Number(3),
-            ]),
-        ]));
+        assert_eq!(parse_it!("(+ 1 (* 2 3))"), call("+", [1.into(), call("*", [2, 3])]));
     }
 
     #[test]
     fn test_unevaluated_expression() {
-        assert_eq!(parse_it!("'(+ 1 2)"), LispVal::Unevaluated(Box::new(LispVal::List(vec![
-            LispVal::Symbol("+".into()),
-            LispVal::Number(1),
-            LispVal::Number(2),
-        ]))));
+        assert_eq!(parse_it!("'(+ 1 2)"), quote(call("+", [1, 2])));
     }
 
     #[test]
@@ -222,10 +853,351 @@ mod tests {
         assert_eq!(parse_it!("false"), LispVal::Boolean(false));
     }
 
+    #[test]
+    fn test_nil() {
+        assert_eq!(parse_it!("nil"), LispVal::Nil);
+        assert_eq!(parse_it!("(f nil)"), call("f", [LispVal::Nil]));
+        assert_eq!(parse_it!("nil?"), sym("nil?"));
+        assert_eq!(parse_it!("nil-count"), sym("nil-count"));
+        assert_eq!(parse_it!("nils"), sym("nils"));
+    }
+
+    #[test]
+    fn test_partial_body() {
+        let partial = LispVal::Function {
+            parameters: vec!["a0".into(), "a1".into()],
+            body: Box::new(call("+", [sym("a0"), sym("a1")])),
+            applied: vec![LispVal::Number(2)],
+            doc: None,
+            captured: Default::default(),
+        };
+
+        assert_eq!(partial.remaining_arity(), Some(1));
+        assert_eq!(partial.partial_body(), Some(call("+", [2.into(), sym("_")])));
+    }
+
+    #[test]
+    fn test_hyphenated_symbol() {
+        assert_eq!(parse_it!("point-x"), LispVal::Symbol("point-x".into()));
+        assert_eq!(parse_it!("(- x 1)"), call("-", [sym("x"), 1.into()]));
+    }
+
+    #[test]
+    fn test_unicode_operators() {
+        assert_eq!(parse_it!("(≤ 1 2)"), call("≤", [LispVal::Number(1), LispVal::Number(2)]));
+        assert_eq!(parse_it!("(× 2 3)"), call("×", [LispVal::Number(2), LispVal::Number(3)]));
+    }
+
+    #[test]
+    fn test_operator_symbols() {
+        for name in ["->", "->>", "|>", ">>=", "not=", "<$>", "a+b", "-x"] {
+            assert_eq!(parse_it!(name), sym(name));
+        }
+        assert_eq!(parse_it!("(-> x (f))"), call("->", [sym("x"), list([sym("f")])]));
+        assert_eq!(parse_it!("(- 1 -2)"), call("-", [1, -2]));
+        assert_eq!(parse_it!("nil->x"), sym("nil->x"));
+
+        assert!(super::parse("!=").is_err());
+        super::with_operator_chars(&format!("{}!#", super::DEFAULT_OPERATOR_CHARS), || {
+            assert_eq!(parse_it!("!="), sym("!="));
+            assert!(super::parse("#=").is_err());
+        });
+        assert!(super::parse("!=").is_err());
+    }
+
+    #[test]
+    fn test_unicode_symbols() {
+        for name in ["λ", "résultat", "日本語", "π-2", "_ñ?", "e\u{301}"] {
+            assert_eq!(parse_it!(name), sym(name));
+        }
+        assert_eq!(parse_it!("(λ x)"), call("λ", [sym("x")]));
+        assert_eq!(parse_it!(":größe"), keyword("größe"));
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let function = |parameters: &[&str], body| call("fn!", [list(parameters.iter().map(|p| sym(p))), body]);
+
+        assert_eq!(parse_it!("#(+ % 1)"), function(&["%1"], call("+", [sym("%1"), 1.into()])));
+        assert_eq!(parse_it!("#(list %2 '[%])"), function(&["%1", "%2"], call("list", [sym("%2"), quote(LispVal::Vector(vec![sym("%1")]))])));
+        assert_eq!(parse_it!("#(print 1)"), function(&[], call("print", [1])));
+        assert_eq!(parse_it!("#(f %1)").to_string(), "(fn! (%1) (f %1))");
+        assert_eq!(parse_it!("(%2 3)"), call("%2", [3]));
+        assert!(super::parse("#(map #(+ % 1) %)").is_err());
+
+        assert_eq!(parse_it!("#| (defn! f (x) #| nested |# x) |# (f #|1|# 2)"), call("f", [2]));
+        assert_eq!(parse_it!("(1 #|#| 2 |#|# 3)"), list([1, 3]));
+
+        let LispVal::Set(elements) = parse_it!("#{3 :a 3 (f x)}") else {
+            panic!("expected a set");
+        };
+        assert_eq!(elements.len(), 3);
+        assert!(elements.contains(&call("f", [sym("x")])));
+        assert_eq!(LispVal::Set(elements).to_string(), "#{(f x) 3 :a}");
+        assert_eq!(parse_it!("#{}"), LispVal::Set(Default::default()));
+        assert!(super::parse("#{1 2").is_err());
+
+        assert_eq!(parse_it!("(1 #_2 3 #_ (4 5))"), list([LispVal::Number(1), LispVal::Number(3)]));
+        assert_eq!(parse_it!("#_#_1 2 3"), LispVal::Number(3));
+        assert!(super::parse("#_1").is_err());
+    }
+
     #[test]
     fn test_number() {
         assert_eq!(parse_it!("1"), LispVal::Number(1));
         assert_eq!(parse_it!("+1"), LispVal::Number(1));
         assert_eq!(parse_it!("-1"), LispVal::Number(-1));
+        assert_eq!(parse_it!("1_000_000"), LispVal::Number(1_000_000));
+        assert_eq!(parse_it!("-1_0"), LispVal::Number(-10));
+        assert_eq!(parse_it!("1_234.5_6"), LispVal::Float(1234.56));
+        assert_eq!(parse_it!("2_5.0_1e1_0"), LispVal::Float(25.01e10));
+        assert_eq!(parse_it!("(1 _2)"), list([1.into(), sym("_2")]));
+        for malformed in ["1_", "1__0", "(+ 1_ 2)", "1_.5", "1.5_", "1e1_"] {
+            assert!(super::parse(malformed).is_err(), "{malformed}");
+        }
+        assert_eq!(LispVal::Number(1_000).to_string(), "1000");
+    }
+
+    #[test]
+    fn test_float() {
+        assert_eq!(parse_it!("1.5"), LispVal::Float(1.5));
+        assert_eq!(parse_it!("-0.25"), LispVal::Float(-0.25));
+        assert_eq!(parse_it!("(/ 1.5 2.0)"), call("/", [LispVal::Float(1.5), LispVal::Float(2.0)]));
+        assert_eq!(LispVal::Float(2.0).to_string(), "2.0");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_scientific_notation() {
+        assert_eq!(parse_it!("1e10"), LispVal::Float(1e10));
+        assert_eq!(parse_it!("2.5e-3"), LispVal::Float(2.5e-3));
+        assert_eq!(parse_it!("-1E+2"), LispVal::Float(-100.0));
+        assert!(super::parse("1e999").is_err());
+        for malformed in ["(1e 2)", "1.5e", "1e+", "2E-x"] {
+            assert!(super::parse(malformed).is_err(), "{malformed}");
+        }
+
+        for float in [1e10, 1e16, 2.5e-3, 1e-7, -1.5e300, f64::MAX, f64::MIN_POSITIVE, 5e-324] {
+            let printed = LispVal::Float(float).to_string();
+            assert_eq!(parse_it!(&printed), LispVal::Float(float), "{printed}");
+        }
+    }
+
+    #[test]
+    fn test_float_precision() {
+        use crate::display::with_float_precision;
+
+        let shown = |precision, float| with_float_precision(precision, || LispVal::Float(float).to_string());
+
+        assert_eq!(shown(Some(4), 1.0 / 3.0), "0.3333");
+        assert_eq!(shown(Some(3), 12345.678), "12300.0");
+        assert_eq!(shown(Some(2), 2.5e-30), "2.5e-30");
+        assert_eq!(shown(Some(4), 0.1 + 0.2), "0.3");
+        assert_eq!(shown(None, 0.1 + 0.2), "0.30000000000000004");
+        // Rounded floats still read back as floats
+        assert_eq!(parse_it!(&shown(Some(1), 7.0)), LispVal::Float(7.0));
+        // The precision only lasts for the call
+        assert_eq!(LispVal::Float(1.0 / 3.0).to_string(), "0.3333333333333333");
+    }
+
+    #[test]
+    fn test_short_symbols_are_inline() {
+        let LispVal::List(values) = parse_it!("(multimethod-dispatch \"hello\")") else {
+            panic!("expected a list");
+        };
+
+        assert!(values.iter().all(|value| match value {
+            LispVal::Symbol(s) | LispVal::String(s) => !s.is_heap_allocated(),
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn test_get_path() {
+        let server = list([
+            list([sym("host"), "localhost".into()]),
+            list([sym("port"), 8080.into()]),
+        ]);
+        let config = LispVal::Record {
+            name: "config".into(),
+            fields: vec![
+                ("server".into(), server),
+                ("tags".into(), list(["a", "b"])),
+            ],
+        };
+
+        assert_eq!(config.get_path::<i64>(&["server", "port"]), Some(8080));
+        assert_eq!(config.get_path::<String>(&["server", "host"]), Some("localhost".into()));
+        assert_eq!(config.get_path::<String>(&["tags", "1"]), Some("b".into()));
+        assert_eq!(config.get_path::<i64>(&["server", "host"]), None);
+        assert_eq!(config.get_path::<i64>(&["missing"]), None);
+    }
+
+    #[test]
+    fn test_walk() {
+        let mut numbers = Vec::new();
+        parse_it!("(+ 1 '(2 (3)) \"4\")").walk(&mut |value| {
+            if let LispVal::Number(n) = value {
+                numbers.push(*n);
+            }
+        });
+
+        assert_eq!(numbers, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_reader_macros() {
+        super::register_reader_macro("shout", |text| {
+            if text.is_empty() {
+                Err("nothing to shout".to_string())
+            } else {
+                Ok(LispVal::String(text.to_uppercase().into()))
+            }
+        });
+
+        assert_eq!(parse_it!("(print #shout\"hi\")"), call("print", ["HI"]));
+        assert!(super::parse("#shout\"\"").is_err());
+        assert!(super::parse("#whisper\"hi\"").is_err());
+    }
+
+    #[test]
+    fn test_datum_labels() {
+        let point = list([1, 2]);
+        let value = list([point.clone(), quote(list([point.clone(), point])), list([3])]);
+
+        let printed = value.to_labeled_string();
+
+        assert_eq!(printed, "(#0=(1 2) '(#0# #0#) (3))");
+        assert_eq!(parse_it!(&printed), value);
+        assert!(super::parse("(#1# 2)").is_err());
+    }
+
+    #[test]
+    fn test_commas_are_whitespace() {
+        assert_eq!(parse_it!("(1, 2 ,3,)"), list([1, 2, 3]));
+        assert_eq!(parse_it!("'(\"a, b\", c)"), quote(list(["a, b".into(), sym("c")])));
+    }
+    #[test]
+    fn test_keyword() {
+        assert_eq!(parse_it!(":name"), keyword("name"));
+        assert_eq!(parse_it!("(:empty? :a-b)"), list([keyword("empty?"), keyword("a-b")]));
+        assert_eq!(keyword("name").to_string(), ":name");
+        assert_ne!(keyword("name"), sym("name"));
+        assert!(super::parse(":").is_err());
+    }
+
+    #[test]
+    fn test_quasiquote() {
+        let unquote = |value| call("unquote", [value]);
+
+        assert_eq!(
+            parse_it!("`(a ,b ,@c)"),
+            call("quasiquote", [list([sym("a"), unquote(sym("b")), call("unquote-splicing", [sym("c")])])])
+        );
+        assert_eq!(
+            parse_it!("`(,x ,y)"),
+            call("quasiquote", [list([unquote(sym("x")), unquote(sym("y"))])])
+        );
+        // Inside an unquote, commas are whitespace again
+        assert_eq!(
+            parse_it!("`,(list 1, 2)"),
+            call("quasiquote", [unquote(call("list", [1, 2]))])
+        );
+        assert_eq!(parse_it!("(a, `b, c)"), list([sym("a"), call("quasiquote", [sym("b")]), sym("c")]));
+    }
+
+    #[test]
+    fn test_vector() {
+        assert_eq!(parse_it!("[1 (+ 1 2)]"), LispVal::Vector(vec![1.into(), call("+", [1, 2])]));
+        assert_eq!(parse_it!("[1 [2]]").to_string(), "[1 [2]]");
+        assert_ne!(parse_it!("[1 2]"), list([1, 2]));
+    }
+
+    #[test]
+    fn test_dotted_pair() {
+        let pair = parse_it!("(a b . c)");
+        assert_eq!(pair, LispVal::cons(sym("a"), LispVal::cons(sym("b"), sym("c"))));
+        assert_eq!(pair.pair_parts(), (vec![&sym("a"), &sym("b")], &sym("c")));
+        assert_eq!(pair.to_string(), "(a b . c)");
+        assert_eq!(parse_it!("(1 . (2 3))"), list([1, 2, 3]));
+        assert!(super::parse("(1 .5)").is_err());
+    }
+
+    #[test]
+    fn test_map() {
+        let LispVal::Map(entries) = parse_it!("{:b (+ 1 2), :a \"x\"}") else {
+            panic!("expected a map");
+        };
+        assert_eq!(entries.get(&keyword("b")), Some(&call("+", [1, 2])));
+        assert_eq!(LispVal::Map(entries).to_string(), "{:a \"x\", :b (+ 1 2)}");
+        assert_eq!(parse_it!("{}"), LispVal::Map(Default::default()));
+        assert!(super::parse("{:a}").is_err());
+        assert_eq!(parse_it!("{:a {:b 1}}").get_path::<i64>(&["a", "b"]), Some(1));
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let value = parse_it!(r#""line\n\ttab \"quoted\" \\ \u{3bb} \u{0}""#);
+        assert_eq!(value, LispVal::String("line\n\ttab \"quoted\" \\ λ \0".into()));
+        assert_eq!(value.to_string(), r#""line\n\ttab \"quoted\" \\ λ \u{0}""#);
+    }
+
+    #[test]
+    fn test_nesting_is_limited() {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert!(super::parse(&nested(super::DEFAULT_MAX_DEPTH - 1)).is_ok());
+        let input = nested(100_000);
+        assert_eq!(
+            SyntaxError::new(&input, 0, &super::parse(&input).unwrap_err()).to_string(),
+            format!("expressions nested more than {} deep at column {}", super::DEFAULT_MAX_DEPTH, super::DEFAULT_MAX_DEPTH + 1)
+        );
+        assert!(super::parse(&"'".repeat(100_000)).is_err());
+        assert!(parse_program(&format!("{}\n1", nested(10))).is_ok());
+    }
+
+    #[test]
+    fn test_parse_complete() {
+        assert_eq!(parse_complete(" (+ 1 2) \n").unwrap(), call("+", [1, 2]));
+        assert_eq!(
+            parse_complete("(+ 1 2) junk"),
+            Err(ParseCompleteError::TrailingInput { rest: "junk".into(), offset: 8, position: Position { line: 1, column: 9 } })
+        );
+        assert_eq!(parse_complete("1\n2").unwrap_err().to_string(), "unexpected input at line 2, column 1: 2");
+        assert!(matches!(parse_complete("(+ 1"), Err(ParseCompleteError::Syntax(_))));
+        assert!(matches!(parse_complete(""), Err(ParseCompleteError::Syntax(_))));
+    }
+
+    #[test]
+    fn test_parse_program() {
+        let forms = parse_program("(defn! f (x) x)\n\n(defn! g (x) (f x))\n(g 1) ").unwrap();
+        assert_eq!(forms.len(), 3);
+        assert_eq!(forms[2], call("g", [1]));
+        assert_eq!(parse_program("  \n").unwrap(), vec![]);
+
+        let error = parse_program("(+ 1 2)\n(- 3\n").unwrap_err();
+        assert_eq!(error.position, Position { line: 2, column: 5 });
+        assert_eq!(error.to_string(), "expected closing `)` at line 2, column 5, to match `(` at line 2, column 1");
+
+        for input in ["1abc", "\"a\"b", "(f 1\"x\")", ":a:b", "(a'b)", "(f(g))"] {
+            assert!(parse_program(input).is_err(), "{input}");
+        }
+        assert_eq!(parse_program("1abc").unwrap_err().to_string(), "expected a space or closing bracket at column 2");
+        assert_eq!(parse_program("(f 1)(g \"a\")[2]").unwrap().len(), 3);
+        assert_eq!(parse_it!("trueish"), sym("trueish"));
+    }
+
+    #[test]
+    fn test_syntax_errors_point_at_the_mistake() {
+        let message = |input: &str| SyntaxError::new(input, 0, &super::parse(input).unwrap_err()).to_string();
+
+        assert_eq!(message("(a . b c)"), "expected `)` at column 8, found `c`");
+        assert_eq!(message("(. b)"), "expected a value before `.` at column 2");
+        assert_eq!(message("(list 1\n  \"a\\qb\")"), "expected escape sequence at line 2, column 6, found `q`");
+        assert_eq!(message("{:a 1 :b}"), "expected a value for the last key of the map at column 9");
+        assert_eq!(message("(f #9#)"), "unknown label `#9#` at column 4");
+        assert_eq!(message("#| a #| b |#"), "unterminated block comment at column 1");
+        assert_eq!(message("@"), "expected expression at column 1, found `@`");
+        assert_eq!(message("'"), "unexpected end of input at column 2");
+        assert_eq!(message("99999999999999999999"), "number too large for an integer at column 1");
+    }
+}
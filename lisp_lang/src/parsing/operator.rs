@@ -0,0 +1,34 @@
+use std::cell::RefCell;
+
+use unicode_ident::is_xid_continue;
+
+/// The ASCII characters symbols can be made of besides letters, digits,
+/// `_` and `-`, unless changed with [`with_operator_chars`].
+pub const DEFAULT_OPERATOR_CHARS: &str = "<>=+-*/%|&^~$";
+
+thread_local! {
+    static OPERATOR_CHARS: RefCell<String> = RefCell::new(DEFAULT_OPERATOR_CHARS.to_string());
+}
+
+/// Runs `f` with `chars` as the ASCII characters symbols can be made of
+/// besides letters, digits, `_` and `-` on the current thread, so that
+/// names like `|>` or `not=` read as symbols in what it parses.
+/// Characters the reader gives another meaning to, like brackets, quotes,
+/// `#`, `:`, `,` and `.`, are ignored.
+pub fn with_operator_chars<T>(chars: &str, f: impl FnOnce() -> T) -> T {
+    let chars = chars.chars().filter(|c| !"()[]{}\"'`,;#:.@".contains(*c) && !c.is_whitespace());
+    let previous = OPERATOR_CHARS.replace(chars.collect());
+    let result = f();
+    OPERATOR_CHARS.set(previous);
+    result
+}
+
+/// Whether `c` is an operator character. Symbols outside ASCII, like `≤`
+/// or `×`, are always operators so they can be aliased to builtins.
+pub fn is_operator(c: char) -> bool {
+    if c.is_ascii() {
+        OPERATOR_CHARS.with_borrow(|chars| chars.contains(c))
+    } else {
+        !is_xid_continue(c) && !c.is_whitespace()
+    }
+}
@@ -0,0 +1,72 @@
+use std::cell::Cell;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::map,
+    error::{context, ParseError},
+    sequence::preceded,
+};
+
+use super::{error::ParseFailure, parse_datum, span::spanned, whitespace, LispVal, ParseResult};
+use crate::builder::call;
+
+thread_local! {
+    /// How many quasiquotes the parser is inside of, minus the unquotes
+    /// between them and the current position.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Forgets the depth left by a previous expression.
+pub fn reset_depth() {
+    DEPTH.with(|depth| depth.set(0));
+}
+
+/// Whether commas unquote here instead of being whitespace.
+pub fn in_template() -> bool {
+    DEPTH.with(|depth| depth.get() > 0)
+}
+
+/// Parses the expression after a quasiquote or unquote with the depth
+/// moved by `change`, restoring it afterwards whether it parses or not.
+/// Whitespace after the expression belongs to the outer depth, so it is
+/// left for the caller.
+fn nested(change: isize) -> impl FnMut(&str) -> ParseResult<'_, LispVal> {
+    move |input| {
+        let outer = DEPTH.with(Cell::get);
+        DEPTH.with(|depth| depth.set(outer.saturating_add_signed(change)));
+        let result = preceded(whitespace, spanned(parse_datum))(input);
+        DEPTH.with(|depth| depth.set(outer));
+        result
+    }
+}
+
+/// `` `template `` reads as `(quasiquote template)`.
+pub fn parse_quasiquote(input: &str) -> ParseResult<'_, LispVal> {
+    context(
+        "quasiquote",
+        preceded(
+            char('`'),
+            map(nested(1), |template| call("quasiquote", [template])),
+        ),
+    )(input)
+}
+
+/// Inside a template, `,value` reads as `(unquote value)` and `,@value` as
+/// `(unquote-splicing value)`. Elsewhere commas are whitespace.
+pub fn parse_unquote(input: &str) -> ParseResult<'_, LispVal> {
+    if !in_template() {
+        return Err(nom::Err::Error(ParseFailure::from_error_kind(input, nom::error::ErrorKind::Char)));
+    }
+
+    let unquoted = |name: &'static str| move |value| call(name, [value]);
+
+    context(
+        "unquote",
+        alt((
+            preceded(tag(",@"), map(nested(-1), unquoted("unquote-splicing"))),
+            preceded(char(','), map(nested(-1), unquoted("unquote"))),
+        )),
+    )(input)
+}
@@ -0,0 +1,59 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use lazy_static::lazy_static;
+use nom::{
+    character::complete::char,
+    error::context,
+    sequence::{pair, preceded},
+};
+
+use super::{error::ParseFailure, parse_symbol, string::parse_string, LispVal, ParseResult};
+
+/// Turns the text of a `#tag"text"` literal into the value it stands for,
+/// or explains why the text is invalid.
+pub type ReaderMacro = Arc<dyn Fn(&str) -> Result<LispVal, String> + Send + Sync>;
+
+lazy_static! {
+    static ref READER_MACROS: RwLock<HashMap<String, ReaderMacro>> = RwLock::new(HashMap::new());
+}
+
+/// Makes the parser read `#tag"text"` as the value `expand` returns for
+/// `text`. The registry is shared by the whole process, and registering a
+/// tag again replaces its previous expansion.
+pub fn register_reader_macro<F>(tag: &str, expand: F)
+where
+    F: Fn(&str) -> Result<LispVal, String> + Send + Sync + 'static,
+{
+    READER_MACROS
+        .write()
+        .unwrap()
+        .insert(tag.to_string(), Arc::new(expand));
+}
+
+fn expand(tag: &str, text: &str) -> Result<LispVal, String> {
+    let expand = READER_MACROS
+        .read()
+        .unwrap()
+        .get(tag)
+        .cloned()
+        .ok_or_else(|| format!("unknown reader macro `#{tag}`"))?;
+
+    expand(text)
+}
+
+/// `#tag"text"`, expanded while parsing. Unknown tags and rejected text
+/// fail to parse, with the reason the expansion gave.
+pub fn parse_reader_macro(input: &str) -> ParseResult<'_, LispVal> {
+    let (rest, (tag, text)) = context(
+        "reader macro",
+        pair(preceded(char('#'), parse_symbol), parse_string),
+    )(input)?;
+
+    match expand(tag, &text) {
+        Ok(value) => Ok((rest, value)),
+        Err(message) => Err(nom::Err::Failure(ParseFailure::message(input, message))),
+    }
+}
@@ -0,0 +1,88 @@
+//! Serde support for the collections in [`LispVal`] that don't map onto
+//! every format. Map and heap keys aren't strings, which formats like JSON
+//! require of object keys, so maps, sets and heaps are written as sequences
+//! instead, maps and sets in the order they print in, so the output doesn't
+//! change from run to run.
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+use super::{sorted_elements, sorted_entries, LispVal, MapEntries, SetElements};
+
+pub mod map_entries {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(entries: &MapEntries, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(sorted_entries(entries))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<MapEntries, D::Error> {
+        Ok(Vec::<(LispVal, LispVal)>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+pub mod set_elements {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(elements: &SetElements, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(sorted_elements(elements))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SetElements, D::Error> {
+        Ok(Vec::<LispVal>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+pub mod heap_entries {
+    use super::*;
+
+    type Entries = im::OrdMap<(i64, u64), LispVal>;
+
+    pub fn serialize<S: Serializer>(entries: &Entries, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(entries.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Entries, D::Error> {
+        Ok(Vec::<((i64, u64), LispVal)>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        evaluation::{eval, scope::Scope},
+        parse_it,
+        parsing::{parse_program, LispVal},
+    };
+
+    fn round_trip(value: &LispVal) -> LispVal {
+        serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_programs_round_trip() {
+        let program = parse_program("(defn! f (x) \"doc\" (+ x 1.5))\n'(a . b)\n#{:a \"s\" nil}\n{[1 2] {:b #{}}}").unwrap();
+        for form in &program {
+            assert_eq!(&round_trip(form), form);
+        }
+        assert_eq!(round_trip(&parse_it!("(f x)")).to_string(), "(f x)");
+    }
+
+    #[test]
+    fn test_values_round_trip() {
+        let (_, heap) = eval(Scope::default(), &parse_it!("(heap-push (heap-push (heap) :b 2) :a 1)")).unwrap();
+        let (_, deque) = eval(Scope::default(), &parse_it!("(deque '(1 2))")).unwrap();
+        let (_, function) = eval(Scope::default(), &parse_it!("(fn! (x) (* x 2))")).unwrap();
+
+        for value in [heap, deque, function, LispVal::Void()] {
+            assert_eq!(round_trip(&value), value);
+        }
+    }
+
+    #[test]
+    fn test_maps_serialize_in_printed_order() {
+        assert_eq!(
+            serde_json::to_string(&parse_it!("{:b 2 :a 1}")).unwrap(),
+            r#"{"Map":[[{"Keyword":"a"},{"Number":1}],[{"Keyword":"b"},{"Number":2}]]}"#
+        );
+    }
+}
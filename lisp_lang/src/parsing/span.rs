@@ -0,0 +1,135 @@
+use std::cell::RefCell;
+
+use super::{brackets::Position, parse, LispVal, ParseResult};
+
+/// Where an expression sits in the input it was parsed from, as byte
+/// offsets: `start` is its first byte and `end` is one past its last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The line and column of the start of the span in `input`, counting
+    /// both from 1 and columns in characters.
+    pub fn position(&self, input: &str) -> Position {
+        Position::of(input, self.start)
+    }
+
+    /// The span moved `offset` bytes further into the input, for spans of a
+    /// form that doesn't start at the beginning of its source.
+    pub fn shifted(self, offset: usize) -> Span {
+        Span {
+            start: self.start + offset,
+            end: self.end + offset,
+        }
+    }
+}
+
+/// The spans of every expression in a parsed form, outer expressions
+/// before the ones they contain.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Spans(Vec<(LispVal, Span)>);
+
+impl Spans {
+    /// The span of the first expression equal to `expr`. Equal expressions
+    /// can't be told apart, so a repeated one is found at its first
+    /// occurrence.
+    pub fn find(&self, expr: &LispVal) -> Option<Span> {
+        self.0.iter().find(|(value, _)| value == expr).map(|(_, span)| *span)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(LispVal, Span)> {
+        self.0.iter()
+    }
+}
+
+thread_local! {
+    /// Expressions parsed so far with the length of the input left before
+    /// and after each, while [`parse_spanned`] is running.
+    static RECORDED: RefCell<Option<Vec<(LispVal, usize, usize)>>> = const { RefCell::new(None) };
+}
+
+/// Runs `parser`, recording the span of what it parses when spans are
+/// being collected.
+pub(super) fn spanned<'a, F>(mut parser: F) -> impl FnMut(&'a str) -> ParseResult<'a, LispVal>
+where
+    F: FnMut(&'a str) -> ParseResult<'a, LispVal>,
+{
+    move |input| {
+        let (rest, value) = parser(input)?;
+        RECORDED.with(|recorded| {
+            if let Some(recorded) = recorded.borrow_mut().as_mut() {
+                recorded.push((value.clone(), input.len(), rest.len()));
+            }
+        });
+        Ok((rest, value))
+    }
+}
+
+/// Like [`parse`], also returning where each expression of the form is in
+/// `input`, so errors can point at the expression they come from.
+pub fn parse_spanned(input: &str) -> ParseResult<'_, (LispVal, Spans)> {
+    RECORDED.with(|recorded| *recorded.borrow_mut() = Some(Vec::new()));
+    let result = parse(input);
+    let recorded = RECORDED.with(|recorded| recorded.borrow_mut().take()).unwrap_or_default();
+
+    let (rest, value) = result?;
+    let mut spans: Vec<(LispVal, Span)> = recorded
+        .into_iter()
+        .map(|(value, before, after)| (value, Span { start: input.len() - before, end: input.len() - after }))
+        .collect();
+    // Alternatives the parser backtracked from can leave duplicates behind
+    spans.sort_by_key(|(_, span)| (span.start, std::cmp::Reverse(span.end)));
+    spans.dedup();
+
+    Ok((rest, (value, Spans(spans))))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        builder::{call, sym},
+        evaluation::{eval, scope::Scope},
+        parsing::{parse_spanned, Position},
+    };
+
+    #[test]
+    fn test_spans() {
+        let input = "(+ 1\n   (* x 2))  ";
+        let (rest, (expr, spans)) = parse_spanned(input).unwrap();
+        assert_eq!(rest, "");
+
+        let whole = spans.find(&expr).unwrap();
+        assert_eq!(&input[whole.start..whole.end], input.trim_end());
+
+        let inner = spans.find(&call("*", [sym("x"), 2.into()])).unwrap();
+        assert_eq!(&input[inner.start..inner.end], "(* x 2)");
+        assert_eq!(inner.position(input), Position { line: 2, column: 4 });
+        assert_eq!(inner.shifted(2).start, inner.start + 2);
+    }
+
+    #[test]
+    fn test_unicode_columns() {
+        let input = "(\"λλ\" missing)";
+        let (_, (_, spans)) = parse_spanned(input).unwrap();
+
+        assert_eq!(spans.find(&sym("missing")).unwrap().position(input).column, 7);
+    }
+
+    #[test]
+    fn test_failing_expression_is_found() {
+        let input = "(list 1 (+ 2 \"a\") 3)";
+        let (_, (expr, spans)) = parse_spanned(input).unwrap();
+        let scope = Scope::default();
+
+        assert!(eval(scope.clone(), &expr).is_err());
+        let failure = scope.runtime.failure().unwrap();
+        let span = spans.find(&failure).unwrap();
+        assert_eq!(&input[span.start..span.end], "(+ 2 \"a\")");
+
+        eval(scope.clone(), &call("+", [1, 2])).unwrap();
+        assert_eq!(scope.runtime.failure(), None);
+    }
+}
@@ -0,0 +1,205 @@
+use std::io::{self, BufRead, BufReader, Read};
+
+use super::{error::{ParseFailure, SyntaxError}, is_complete, parse, whitespace, LispVal};
+
+#[derive(Debug, thiserror::Error)]
+pub enum StreamError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Syntax(#[from] SyntaxError),
+}
+
+/// What the buffered text holds so far.
+enum Step {
+    /// A whole form, and how many bytes of the buffer it took up.
+    Form(LispVal, usize),
+    /// The start of a form, or nothing but whitespace, before the end of
+    /// the input.
+    More,
+    /// Nothing but whitespace at the end of the input.
+    End,
+    Failed(SyntaxError),
+}
+
+/// Reads the forms of a source one at a time, keeping only the form being
+/// read in memory, so large data files don't have to be loaded whole.
+///
+/// The source is read a line at a time. A form is only complete once
+/// something follows it or the input ends, as more of the same symbol or
+/// number could be on the next line. Reading stops at the first error, with
+/// syntax errors positioned from the start of the source.
+pub struct ParseStream<R> {
+    reader: BufReader<R>,
+    buffer: String,
+    /// Lines read past, and characters read past on the current line.
+    line: usize,
+    column: usize,
+    eof: bool,
+    done: bool,
+}
+
+impl<R: Read> ParseStream<R> {
+    pub fn new(reader: R) -> Self {
+        ParseStream {
+            reader: BufReader::new(reader),
+            buffer: String::new(),
+            line: 0,
+            column: 0,
+            eof: false,
+            done: false,
+        }
+    }
+
+    /// Reads at least as much as is buffered already, in whole lines, so a
+    /// long form is parsed a number of times logarithmic in its length.
+    fn fill(&mut self) -> io::Result<()> {
+        let wanted = self.buffer.len().max(1);
+        let mut read = 0;
+        while read < wanted {
+            let count = self.reader.read_line(&mut self.buffer)?;
+            if count == 0 {
+                self.eof = true;
+                break;
+            }
+            read += count;
+        }
+        Ok(())
+    }
+
+    fn step(&self) -> Step {
+        let input = self.buffer.as_str();
+        if whitespace(input).is_ok_and(|(rest, _)| rest.is_empty()) {
+            return if self.eof { Step::End } else { Step::More };
+        }
+
+        match parse(input) {
+            Ok((rest, form)) if !rest.is_empty() || self.eof => Step::Form(form, input.len() - rest.len()),
+            Ok(_) => Step::More,
+            // An open bracket or string, or a failure at the very end, like
+            // after a `'`, may be finished by the lines still to come
+            Err(nom::Err::Incomplete(_)) if !self.eof => Step::More,
+            Err(nom::Err::Error(failure) | nom::Err::Failure(failure))
+                if !self.eof && (failure.input.is_empty() || !is_complete(input)) =>
+            {
+                Step::More
+            }
+            Err(error) => Step::Failed(self.syntax_error(&error)),
+        }
+    }
+
+    /// Drops the first `count` bytes of the buffer, keeping track of where
+    /// in the source the rest starts.
+    fn consume(&mut self, count: usize) {
+        let consumed: String = self.buffer.drain(..count).collect();
+        match consumed.rfind('\n') {
+            Some(last) => {
+                self.line += consumed.matches('\n').count();
+                self.column = consumed[last + 1..].chars().count();
+            }
+            None => self.column += consumed.chars().count(),
+        }
+    }
+
+    fn syntax_error(&self, error: &nom::Err<ParseFailure>) -> SyntaxError {
+        // The text read past is stood in for by as many line breaks and
+        // spaces, so positions count from the start of the source
+        let padding = "\n".repeat(self.line) + &" ".repeat(self.column);
+        let source = padding.clone() + &self.buffer;
+        SyntaxError::new(&source, padding.len(), error)
+    }
+}
+
+impl<R: Read> Iterator for ParseStream<R> {
+    type Item = Result<LispVal, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.step() {
+                Step::Form(form, count) => {
+                    self.consume(count);
+                    return Some(Ok(form));
+                }
+                Step::More => {
+                    if let Err(error) = self.fill() {
+                        self.done = true;
+                        return Some(Err(error.into()));
+                    }
+                }
+                Step::End => self.done = true,
+                Step::Failed(error) => {
+                    self.done = true;
+                    return Some(Err(error.into()));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        builder::{call, list, sym},
+        parsing::{LispVal, Position},
+    };
+
+    use super::{ParseStream, StreamError};
+
+    fn forms(input: &str) -> Vec<Result<LispVal, String>> {
+        ParseStream::new(Cursor::new(input.to_string()))
+            .map(|form| form.map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_reads_forms_in_order() {
+        assert_eq!(
+            forms("(def! a 1)\n\n(f\n  a\n  \"b\nc\")\nabc\n12 (x)"),
+            vec![
+                Ok(call("def!", [sym("a"), 1.into()])),
+                Ok(call("f", [sym("a"), LispVal::String("b\nc".into())])),
+                Ok(sym("abc")),
+                Ok(LispVal::Number(12)),
+                Ok(list([sym("x")])),
+            ]
+        );
+        assert_eq!(forms(""), vec![]);
+        assert_eq!(forms("  \n \n"), vec![]);
+    }
+
+    #[test]
+    fn test_reads_long_forms() {
+        let numbers = (0..2000).map(|n| n.to_string()).collect::<Vec<_>>().join("\n");
+        let forms = forms(&format!("(list\n{numbers})"));
+        let [Ok(LispVal::List(values))] = forms.as_slice() else {
+            panic!("expected one list");
+        };
+        assert_eq!(values.len(), 2001);
+    }
+
+    #[test]
+    fn test_errors_are_positioned_in_the_source() {
+        let mut stream = ParseStream::new(Cursor::new("(a)\n(b) (c\n  ]"));
+        assert_eq!(stream.next().unwrap().unwrap(), list([sym("a")]));
+        assert_eq!(stream.next().unwrap().unwrap(), list([sym("b")]));
+        let Some(Err(StreamError::Syntax(error))) = stream.next() else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!(error.position, Position { line: 3, column: 3 });
+        assert!(stream.next().is_none());
+
+        assert_eq!(
+            forms("1 (2"),
+            vec![Ok(LispVal::Number(1)), Err("expected closing `)` at column 5, to match `(` at column 3".into())]
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_an_io_error() {
+        let mut stream = ParseStream::new(Cursor::new(vec![b'(', 0xff, b')']));
+        assert!(matches!(stream.next(), Some(Err(StreamError::Io(_)))));
+    }
+}
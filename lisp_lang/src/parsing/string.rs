@@ -2,7 +2,7 @@ use nom::branch::alt;
 use nom::bytes::streaming::{is_not, take_while_m_n};
 use nom::character::streaming::{char, multispace1};
 use nom::combinator::{map, map_opt, map_res, value, verify};
-use nom::error::{FromExternalError, ParseError};
+use nom::error::{ContextError, ErrorKind, FromExternalError, ParseError};
 use nom::IResult;
 use nom::multi::fold_many0;
 use nom::sequence::{delimited, preceded};
@@ -41,7 +41,7 @@ fn parse_unicode<'a, E>(input: &'a str) -> IResult<&'a str, char, E>
     // the function returns None, map_opt returns an error. In this case, because
     // not all u32 values are valid unicode code points, we have to fallibly
     // convert to char with from_u32.
-    map_opt(parse_u32, |value| std::char::from_u32(value))(input)
+    map_opt(parse_u32, std::char::from_u32)(input)
 }
 
 /// Parse an escaped character: \n, \t, \r, \u{00AC}, etc.
@@ -102,11 +102,24 @@ enum StringFragment<'a> {
     EscapedWS,
 }
 
+/// A backslash that starts neither an escaped character nor escaped
+/// whitespace. It fails without backtracking, so the error points at the
+/// character after the backslash instead of the start of the string.
+fn parse_invalid_escape<'a, E>(input: &'a str) -> IResult<&'a str, StringFragment<'a>, E>
+    where
+        E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    let (rest, _) = char('\\')(input)?;
+    let error = E::from_error_kind(rest, ErrorKind::Escaped);
+
+    Err(nom::Err::Failure(E::add_context(rest, "escape sequence", error)))
+}
+
 /// Combine parse_literal, parse_escaped_whitespace, and parse_escaped_char
 /// into a StringFragment.
 fn parse_fragment<'a, E>(input: &'a str) -> IResult<&'a str, StringFragment<'a>, E>
     where
-        E: ParseError<&'a str> + FromExternalError<&'a str, std::num::ParseIntError>,
+        E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, std::num::ParseIntError>,
 {
     alt((
         // The `map` combinator runs a parser, then applies a function to the output
@@ -114,6 +127,7 @@ fn parse_fragment<'a, E>(input: &'a str) -> IResult<&'a str, StringFragment<'a>,
         map(parse_literal, StringFragment::Literal),
         map(parse_escaped_char, StringFragment::EscapedChar),
         value(StringFragment::EscapedWS, parse_escaped_whitespace),
+        parse_invalid_escape,
     ))(input)
 }
 
@@ -121,7 +135,7 @@ fn parse_fragment<'a, E>(input: &'a str) -> IResult<&'a str, StringFragment<'a>,
 /// into an output string.
 pub fn parse_string<'a, E>(input: &'a str) -> IResult<&'a str, String, E>
     where
-        E: ParseError<&'a str> + FromExternalError<&'a str, std::num::ParseIntError>,
+        E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, std::num::ParseIntError>,
 {
     // fold_many0 is the equivalent of iterator::fold. It runs a parser in a loop,
     // and for each output value, calls a folding function on each output value.
@@ -147,4 +161,22 @@ pub fn parse_string<'a, E>(input: &'a str) -> IResult<&'a str, String, E>
     // `delimited` with a looping parser (like fold_many0), be sure that the
     // loop won't accidentally match your closing delimiter!
     delimited(char('"'), build_string, char('"'))(input)
-}
\ No newline at end of file
+}
+/// Writes `s` back in the escaped form `parse_string` reads, without the
+/// surrounding quotes. Control characters without a short escape are
+/// written as `\u{...}`; everything else, non-ASCII included, is kept.
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
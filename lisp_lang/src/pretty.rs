@@ -0,0 +1,184 @@
+use crate::parsing::{sorted_elements, sorted_entries, LispVal};
+
+/// How far the body of a special form is indented past its `(`.
+const INDENT: usize = 2;
+
+impl LispVal {
+    /// Prints the value over as many lines as it takes to stay within
+    /// `width` columns. Anything that fits stays on one line, as
+    /// `to_string` prints it. Otherwise calls keep their first argument
+    /// next to the function and line the others up under it, special forms
+    /// (`defn!`, `if!`, ...) indent their body, and the elements of data
+    /// line up after the opening bracket, atoms as many to a line as fit.
+    /// Atoms too long for the width are left as they are.
+    pub fn pretty(&self, width: usize) -> String {
+        let mut out = String::new();
+        write(self, width, &mut out);
+        out
+    }
+}
+
+/// How many arguments of a special form stay on the line of its name, with
+/// the rest indented as its body. `None` for functions.
+fn header_length(name: &str) -> Option<usize> {
+    match name {
+        "do!" => Some(0),
+        "defn!" => Some(2),
+        "defmethod!" => Some(3),
+        name if name.ends_with('!') => Some(1),
+        _ => None,
+    }
+}
+
+fn is_atom(value: &LispVal) -> bool {
+    !matches!(
+        value,
+        LispVal::List(_) | LispVal::Vector(_) | LispVal::Map(_) | LispVal::Set(_) | LispVal::Unevaluated(_)
+    )
+}
+
+/// The column the next character written to `out` lands on.
+fn column(out: &str) -> usize {
+    out.rsplit('\n').next().unwrap_or_default().chars().count()
+}
+
+fn new_line(indent: usize, out: &mut String) {
+    out.push('\n');
+    out.push_str(&" ".repeat(indent));
+}
+
+fn write(value: &LispVal, width: usize, out: &mut String) {
+    let flat = value.to_string();
+    if column(out) + flat.chars().count() <= width {
+        out.push_str(&flat);
+        return;
+    }
+
+    match value {
+        LispVal::List(values) => match values.split_first() {
+            Some((LispVal::Symbol(name), arguments)) if !arguments.is_empty() => {
+                write_call(name, arguments, width, out)
+            }
+            _ => write_elements("(", values, ")", width, out),
+        },
+        LispVal::Vector(values) => write_elements("[", values, "]", width, out),
+        LispVal::Set(elements) => {
+            let elements: Vec<_> = sorted_elements(elements).into_iter().cloned().collect();
+            write_elements("#{", &elements, "}", width, out)
+        }
+        LispVal::Map(entries) => {
+            out.push('{');
+            let indent = column(out);
+            for (i, (key, value)) in sorted_entries(entries).into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                    new_line(indent, out);
+                }
+                write(key, width, out);
+                out.push(' ');
+                write(value, width, out);
+            }
+            out.push('}');
+        }
+        LispVal::Unevaluated(value) => {
+            out.push('\'');
+            write(value, width, out);
+        }
+        _ => out.push_str(&flat),
+    }
+}
+
+fn write_call(name: &str, arguments: &[LispVal], width: usize, out: &mut String) {
+    out.push('(');
+    let open = column(out) - 1;
+    out.push_str(name);
+
+    match header_length(name) {
+        Some(header) => {
+            for (i, argument) in arguments.iter().enumerate() {
+                if i < header {
+                    out.push(' ');
+                } else {
+                    new_line(open + INDENT, out);
+                }
+                write(argument, width, out);
+            }
+        }
+        None => {
+            out.push(' ');
+            let indent = column(out);
+            for (i, argument) in arguments.iter().enumerate() {
+                if i > 0 {
+                    new_line(indent, out);
+                }
+                write(argument, width, out);
+            }
+        }
+    }
+    out.push(')');
+}
+
+fn write_elements(open: &str, values: &[LispVal], close: &str, width: usize, out: &mut String) {
+    out.push_str(open);
+    let indent = column(out);
+
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            // The closing bracket has to fit after the last element too
+            let room = if i + 1 == values.len() { close.len() } else { 0 };
+            let length = value.to_string().chars().count();
+            if is_atom(value) && is_atom(&values[i - 1]) && column(out) + 1 + length + room <= width {
+                out.push(' ');
+            } else {
+                new_line(indent, out);
+            }
+        }
+        write(value, width, out);
+    }
+    out.push_str(close);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_it, parsing::LispVal};
+
+    fn pretty(input: &str, width: usize) -> String {
+        parse_it!(input).pretty(width)
+    }
+
+    #[test]
+    fn test_short_values_stay_on_one_line() {
+        assert_eq!(pretty("(f [1 2] {:a 1, :b 2})", 80), "(f [1 2] {:a 1, :b 2})");
+        assert_eq!(pretty("\"a long string\"", 4), "\"a long string\"");
+    }
+
+    #[test]
+    fn test_calls_align_their_arguments() {
+        assert_eq!(pretty("(+ (* 1 2) (* 3 4) 5)", 12), "(+ (* 1 2)\n   (* 3 4)\n   5)");
+        assert_eq!(
+            pretty("(defn! f (x) (if! (< x 1) x (f (- x 1))))", 24),
+            "(defn! f (x)\n  (if! (< x 1)\n    x\n    (f (- x 1))))"
+        );
+    }
+
+    #[test]
+    fn test_data_is_aligned_after_the_bracket() {
+        assert_eq!(pretty("(1 2 3 4 5 6 7 8)", 10), "(1 2 3 4 5\n 6 7 8)");
+        assert_eq!(pretty("[[1 2] [3 4]]", 8), "[[1 2]\n [3 4]]");
+        assert_eq!(pretty("{:a (1 2 3), :b #{1 2}}", 16), "{:a (1 2 3),\n :b #{1 2}}");
+        assert_eq!(pretty("'((1 2) (3 4))", 10), "'((1 2)\n  (3 4))");
+    }
+
+    #[test]
+    fn test_output_reads_back_the_same() {
+        let source = "(defn! walk (tree) (do! (print \"visiting\") (map (fn! (x) [x {:depth 1 :seen #{x}}]) '(a (b c) (d (e f))))))";
+        for width in [0, 10, 20, 40, 80] {
+            let printed = pretty(source, width);
+            assert_eq!(parse_it!(&printed), parse_it!(source), "width {width}:\n{printed}");
+            if width >= 40 {
+                assert!(printed.lines().all(|line| line.chars().count() <= width), "{printed}");
+            }
+        }
+        assert_eq!(LispVal::Number(1).pretty(0), "1");
+    }
+}
@@ -0,0 +1,85 @@
+use lisp_lang::{evaluation::builtin_names, interpreter::Interpreter, parsing::parse};
+
+const CASES: &str = include_str!("golden/builtins.txt");
+
+/// Builtins that only exist with the `io` feature; their cases are skipped
+/// without it.
+const IO_BUILTINS: [&str; 4] = ["print", "pprint", "print_scope", "debug"];
+
+struct Case<'a> {
+    line: usize,
+    expr: &'a str,
+    expected: Result<&'a str, &'a str>,
+}
+
+fn calls(expr: &str, name: &str) -> bool {
+    expr.contains(&format!("({name} ")) || expr.contains(&format!("({name})"))
+}
+
+fn cases() -> Vec<Case<'static>> {
+    CASES
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with(';'))
+        .map(|(i, line)| {
+            let (expr, expected) = line
+                .split_once(" => ")
+                .unwrap_or_else(|| panic!("line {}: missing ` => `", i + 1));
+            let expected = match expected.strip_prefix("error: ") {
+                Some(message) => Err(message),
+                None => Ok(expected),
+            };
+
+            Case {
+                line: i + 1,
+                expr,
+                expected,
+            }
+        })
+        .filter(|case| cfg!(feature = "io") || !IO_BUILTINS.iter().any(|name| calls(case.expr, name)))
+        .collect()
+}
+
+fn run(expr: &str) -> Result<String, String> {
+    let (rest, expr) = parse(expr).map_err(|e| e.to_string())?;
+    assert!(rest.is_empty(), "unparsed input: {rest}");
+
+    Interpreter::new()
+        .eval(&expr)
+        .map(|value| value.to_string())
+        .map_err(|e| e.to_string())
+}
+
+#[test]
+fn golden_cases() {
+    let failures: Vec<String> = cases()
+        .iter()
+        .filter_map(|case| {
+            let result = run(case.expr);
+            let passed = match (&result, case.expected) {
+                (Ok(value), Ok(expected)) => value == expected,
+                (Err(message), Err(expected)) => message.contains(expected),
+                _ => false,
+            };
+
+            (!passed).then(|| {
+                format!(
+                    "line {}: {}\n  expected {:?}\n       got {:?}",
+                    case.line, case.expr, case.expected, result
+                )
+            })
+        })
+        .collect();
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn every_builtin_is_covered() {
+    let cases = cases();
+    let missing: Vec<&str> = builtin_names()
+        .filter(|name| !cases.iter().any(|case| calls(case.expr, name)))
+        .collect();
+
+    assert!(missing.is_empty(), "builtins without golden cases: {missing:?}");
+}
@@ -0,0 +1,65 @@
+use lisp_lang::parsing::parse;
+
+const CORPUS: &str = include_str!("corpus/parser.txt");
+
+fn corpus() -> Vec<String> {
+    let lines = CORPUS
+        .lines()
+        .filter(|line| !line.starts_with(';'))
+        .map(str::to_string);
+
+    ["", " ", "\n", "(+ 1\n 2)", "\"a\nb\"", "'\n", "(\n"]
+        .into_iter()
+        .map(str::to_string)
+        .chain(lines)
+        .collect()
+}
+
+/// Inputs built from the characters the parser treats specially, from a
+/// fixed seed so failures reproduce.
+fn generated(count: usize) -> Vec<String> {
+    const ALPHABET: &[char] = &['(', ')', '\'', '"', '\\', ' ', 'a', '1', '-', '#', '=', '!', 'u', '{', '}'];
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    (0..count)
+        .map(|_| {
+            let length = next() % 12;
+            (0..length)
+                .map(|_| ALPHABET[(next() % ALPHABET.len() as u64) as usize])
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn parser_never_panics() {
+    for input in corpus().into_iter().chain(generated(5000)) {
+        let result = std::panic::catch_unwind(|| parse(&input).map(|(_, value)| value));
+
+        assert!(result.is_ok(), "parser panicked on {input:?}");
+    }
+}
+
+#[test]
+fn printed_values_parse_back() {
+    for input in corpus().into_iter().chain(generated(5000)) {
+        let Ok((_, value)) = parse(&input) else {
+            continue;
+        };
+        let printed = value.to_string();
+
+        match parse(&printed) {
+            Ok((rest, reparsed)) => assert!(
+                rest.is_empty() && reparsed == value,
+                "{input:?} printed as {printed:?}, which reads back as {reparsed:?}"
+            ),
+            Err(e) => panic!("{input:?} printed as {printed:?}, which doesn't parse: {e}"),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+// The maximum depth is shared by the whole process, so this test has a
+// binary of its own rather than changing it under the other parser tests.
+
+use lisp_lang::parsing::{parse, set_max_depth};
+
+#[test]
+fn deep_nesting_does_not_overflow_the_stack() {
+    set_max_depth(10_000_000);
+
+    let depth = 5_000;
+    let input = format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+    let (rest, _) = parse(&input).unwrap();
+
+    assert!(rest.is_empty());
+}
@@ -0,0 +1,203 @@
+//! `--calc` mode: a quick calculator on top of the Lisp core. Input can be
+//! written infix, `1 + 2 * 3`, and is rewritten to prefix before it is
+//! parsed. Results are printed plain.
+
+use lisp_lang::{evaluation::scope::Scope, parsing::LispVal};
+
+use crate::script;
+
+/// Functions defined before the first input, on top of the builtins.
+const PRELUDE: &str = "
+(defn! abs (x) (if! (< x 0) (- 0 x) x))
+(defn! sq (x) (* x x))
+(defn! pow (base exponent) (if! (<= exponent 0) 1 (* base (pow base (- exponent 1)))))
+(defn! gcd (a b) (if! (= b 0) (abs a) (gcd b (% a b))))
+";
+
+pub fn load_prelude(scope: Scope) -> Scope {
+    script::run_source(scope.clone(), "<calc>", PRELUDE).unwrap_or(scope)
+}
+
+/// Results without colors, and strings without quotes.
+pub fn plain(value: &LispVal) -> String {
+    match value {
+        LispVal::String(s) => s.to_string(),
+        value => value.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A number, a name or a parenthesized group, kept as written.
+    Operand(String),
+    Operator(char),
+}
+
+const OPERATORS: &str = "+-*/%";
+
+fn precedence(operator: char) -> u8 {
+    match operator {
+        '+' | '-' => 1,
+        _ => 2,
+    }
+}
+
+/// Splits infix input into operands and operators. Names run until
+/// whitespace or a parenthesis, so `x-1` is one name while `1-x` is three
+/// tokens; groups keep their parentheses. A sign at the start of a word is
+/// part of a number, so `abs -2` is a call while `1-2` and `1 - 2` subtract.
+/// Numbers keep their fraction and exponent, as in `2.5e-3`.
+fn tokenize(input: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let after_operand = matches!(tokens.last(), Some(Token::Operand(_)));
+        let is_sign = "+-".contains(c)
+            && chars.get(i + 1).is_some_and(char::is_ascii_digit)
+            && (i == 0 || chars[i - 1].is_whitespace());
+        let start = i;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        } else if c == '(' {
+            let mut depth = 0;
+            let mut in_string = false;
+            while i < chars.len() {
+                match chars[i] {
+                    '\\' if in_string => i += 1,
+                    '"' => in_string = !in_string,
+                    '(' if !in_string => depth += 1,
+                    ')' if !in_string => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            if i == chars.len() {
+                return None;
+            }
+            i += 1;
+        } else if OPERATORS.contains(c) && after_operand && !is_sign {
+            tokens.push(Token::Operator(c));
+            i += 1;
+            continue;
+        } else if c.is_ascii_digit() || OPERATORS.contains(c) {
+            i += 1;
+            let is_digit = |i: usize| chars.get(i).is_some_and(char::is_ascii_digit);
+            let is_fraction = |i: usize| chars[i] == '.' && is_digit(i + 1);
+            let exponent = |i: usize| match chars[i] {
+                'e' | 'E' if is_digit(i + 1) => Some(1),
+                'e' | 'E' if chars.get(i + 1).is_some_and(|c| "+-".contains(*c)) && is_digit(i + 2) => Some(2),
+                _ => None,
+            };
+            while i < chars.len() {
+                if chars[i].is_ascii_digit() || is_fraction(i) {
+                    i += 1;
+                } else if let Some(length) = exponent(i) {
+                    i += length + 1;
+                } else {
+                    break;
+                }
+            }
+        } else {
+            while i < chars.len() && !chars[i].is_whitespace() && !"()".contains(chars[i]) {
+                i += 1;
+            }
+        }
+
+        tokens.push(Token::Operand(chars[start..i].iter().collect()));
+    }
+
+    Some(tokens)
+}
+
+/// Converts an operand to prefix, recursing into groups written infix.
+fn operand(text: &str) -> String {
+    match text.strip_prefix('(').and_then(|inner| inner.strip_suffix(')')) {
+        Some(inner) => infix(inner).unwrap_or_else(|| text.to_string()),
+        None => text.to_string(),
+    }
+}
+
+/// Precedence climbing over `tokens`, left associative.
+fn expression(tokens: &[Token], position: &mut usize, min_precedence: u8) -> Option<String> {
+    let Some(Token::Operand(first)) = tokens.get(*position) else {
+        return None;
+    };
+    let mut left = operand(first);
+    *position += 1;
+
+    while let Some(Token::Operator(operator)) = tokens.get(*position) {
+        let operator_precedence = precedence(*operator);
+        if operator_precedence < min_precedence {
+            break;
+        }
+        *position += 1;
+        let right = expression(tokens, position, operator_precedence + 1)?;
+        left = format!("({operator} {left} {right})");
+    }
+
+    Some(left)
+}
+
+/// The prefix form of `input` when it is an infix expression with at least
+/// one operator, like `(1 + 2) * 3`.
+fn infix(input: &str) -> Option<String> {
+    let tokens = tokenize(input)?;
+    if !tokens.iter().any(|token| matches!(token, Token::Operator(_))) {
+        return None;
+    }
+
+    let mut position = 0;
+    let prefix = expression(&tokens, &mut position, 0)?;
+
+    (position == tokens.len()).then_some(prefix)
+}
+
+/// Rewrites infix input to prefix, leaving anything else, like `(+ 1 2)`,
+/// as it was.
+pub fn to_prefix(input: &str) -> String {
+    infix(input).unwrap_or_else(|| input.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use lisp_lang::{evaluation::scope::Scope, parsing::LispVal};
+
+    use super::{load_prelude, to_prefix};
+    use crate::repl::evaluate;
+
+    #[test]
+    fn test_infix_is_rewritten() {
+        assert_eq!(to_prefix("1 + 2 * 3"), "(+ 1 (* 2 3))");
+        assert_eq!(to_prefix("(1 + 2) * 3"), "(* (+ 1 2) 3)");
+        assert_eq!(to_prefix("10 - 4 - 3"), "(- (- 10 4) 3)");
+        assert_eq!(to_prefix("1+-2"), "(+ 1 -2)");
+        assert_eq!(to_prefix("(sq 3) % x"), "(% (sq 3) x)");
+        assert_eq!(to_prefix("1.5 * 2 - 0.25"), "(- (* 1.5 2) 0.25)");
+        assert_eq!(to_prefix("2.5e-3 * 1e3-1"), "(- (* 2.5e-3 1e3) 1)");
+    }
+
+    #[test]
+    fn test_prefix_is_kept() {
+        for input in ["(+ 1 2)", "(max 1 2)", "x", "-3", "(list 1 \"a)\")", "1 +", "(abs -2)"] {
+            assert_eq!(to_prefix(input), input);
+        }
+    }
+
+    #[test]
+    fn test_prelude() {
+        let scope = load_prelude(Scope::default());
+        let (_, result) = evaluate(scope, &to_prefix("(pow 2 10) - (gcd 12 18) * (abs -2)")).unwrap();
+
+        assert_eq!(result.value, LispVal::Number(1012));
+    }
+}
@@ -0,0 +1,280 @@
+use colored::Colorize;
+use lisp_lang::{
+    builder::{call, sym},
+    error_code::ErrorCode,
+    evaluation::{error::EvalError, eval, inference::infer, scope::Scope},
+    interpreter,
+    parsing::LispVal,
+};
+
+use crate::config::{Preferences, WIDTH_BINDING};
+use crate::display::ColoredLispVal;
+use crate::repl::{parse_expression, REPLError};
+
+const COMMANDS: &[&str] = &[":pin", ":pins", ":width", ":type", ":dry-run", ":explain", ":ast"];
+
+/// Meta-commands start with `:` and are handled by the REPL instead of
+/// being evaluated. A single word that isn't a command, like `:name`, is a
+/// keyword and is evaluated.
+pub fn is_command(input: &str) -> bool {
+    let input = input.trim();
+    match input.split_once(' ') {
+        Some((command, _)) => command.starts_with(':'),
+        None => COMMANDS.contains(&input),
+    }
+}
+
+/// What commands remember between inputs, besides the scope.
+#[derive(Debug, Default)]
+pub struct Session {
+    /// The last value printed by the REPL.
+    pub last_result: Option<LispVal>,
+    /// Names bound by `:pin`, in the order they were first pinned.
+    pub pins: Vec<String>,
+}
+
+pub fn run(scope: &mut Scope, session: &mut Session, input: &str) -> Result<String, REPLError> {
+    let input = input.trim();
+    let (command, argument) = input.split_once(' ').unwrap_or((input, ""));
+
+    match command {
+        ":pin" => pin(scope, session, argument),
+        ":pins" => Ok(pins(scope, session)),
+        ":width" => width(scope, argument),
+        ":type" => type_of(scope, argument),
+        ":dry-run" => dry_run(scope, argument),
+        ":explain" => explain(argument),
+        ":ast" => ast(argument),
+        _ => Err(REPLError::UnknownCommand {
+            name: command.to_string(),
+        }),
+    }
+}
+
+/// Binds the last printed result to `name`, like `(def! name 'result)`.
+fn pin(scope: &mut Scope, session: &mut Session, name: &str) -> Result<String, REPLError> {
+    let name = parse_expression(name)?
+        .as_symbol()
+        .map_err(EvalError::from_arg(0, ":pin"))
+        .map_err(REPLError::Evaluation)?
+        .to_string();
+    let value = session.last_result.clone().ok_or(REPLError::NothingToPin)?;
+
+    let definition = call("def!", [sym(&name), value.to_unevaluated()]);
+    let (pinned, _) = eval(scope.clone(), &definition).map_err(REPLError::Evaluation)?;
+    *scope = pinned;
+
+    let output = format!("{} = {}", name.bright_blue(), ColoredLispVal::new(value));
+    if !session.pins.contains(&name) {
+        session.pins.push(name);
+    }
+
+    Ok(output)
+}
+
+/// Lists the pinned names with the values they are bound to now.
+fn pins(scope: &Scope, session: &Session) -> String {
+    if session.pins.is_empty() {
+        return "No pinned values".to_string();
+    }
+
+    session
+        .pins
+        .iter()
+        .filter_map(|name| {
+            let value = scope.get(name)?.clone();
+            Some(format!("{} = {}", name.bright_blue(), ColoredLispVal::new(value)))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Shows or sets the columns printed results are elided to. `0` turns
+/// elision off.
+fn width(scope: &mut Scope, input: &str) -> Result<String, REPLError> {
+    if !input.trim().is_empty() {
+        let columns = i64::try_from(parse_expression(input)?)
+            .map_err(EvalError::from_arg(0, ":width"))
+            .map_err(REPLError::Evaluation)?;
+        *scope = scope.bind(WIDTH_BINDING.to_string(), LispVal::Number(columns.max(0)));
+    }
+
+    Ok(match Preferences::from_scope(scope).width {
+        Some(columns) => format!("Results are elided to {columns} columns"),
+        None => "Results are printed in full".to_string(),
+    })
+}
+
+fn type_of(scope: &Scope, input: &str) -> Result<String, REPLError> {
+    let expr = parse_expression(input)?;
+
+    Ok(infer(scope, &expr).to_string().bright_yellow().to_string())
+}
+
+/// Prints the parse tree of an expression without evaluating it, with the
+/// kind of every node. Unquoted lists headed by a symbol are marked as calls.
+fn ast(input: &str) -> Result<String, REPLError> {
+    let expr = parse_expression(input)?;
+    let mut lines = Vec::new();
+    ast_lines(&expr, false, "", "", &mut lines);
+
+    Ok(lines.join("\n"))
+}
+
+fn ast_lines(expr: &LispVal, quoted: bool, branch: &str, indent: &str, lines: &mut Vec<String>) {
+    let (kind, children): (&str, &[LispVal]) = match expr {
+        LispVal::List(values) if values.is_empty() => ("list", &[]),
+        LispVal::List(values) if !quoted && matches!(values[0], LispVal::Symbol(_)) => {
+            ("call", values)
+        }
+        LispVal::List(values) => ("list", values),
+        LispVal::Unevaluated(value) => ("quote", std::slice::from_ref(value)),
+        value => {
+            let kind = value.to_type().to_string();
+            let leaf = ColoredLispVal::new(value.clone());
+            lines.push(format!("{branch}{} {leaf}", kind.bright_yellow()));
+            return;
+        }
+    };
+
+    let label = if children.is_empty() { "list ()" } else { kind };
+    lines.push(format!("{branch}{}", label.bright_yellow()));
+
+    let quoted = quoted || matches!(expr, LispVal::Unevaluated(_));
+    for (i, child) in children.iter().enumerate() {
+        let last = i == children.len() - 1;
+        let (branch, next) = if last { ("└── ", "    ") } else { ("├── ", "│   ") };
+        ast_lines(child, quoted, &format!("{indent}{branch}"), &format!("{indent}{next}"), lines);
+    }
+}
+
+/// Prints the long description of an error code such as `E0004`.
+pub fn explain(code: &str) -> Result<String, REPLError> {
+    let code = code.trim();
+    let code = ErrorCode::parse(code).ok_or_else(|| REPLError::UnknownErrorCode {
+        code: code.to_string(),
+    })?;
+
+    Ok(code.explain().to_string())
+}
+
+/// Evaluates without committing definitions or printing, listing what the
+/// expression would have done instead.
+fn dry_run(scope: &Scope, input: &str) -> Result<String, REPLError> {
+    let expr = parse_expression(input)?;
+    let (value, effects) = interpreter::dry_run(scope.clone(), &expr)
+        .map_err(REPLError::Evaluation)?;
+
+    let lines: Vec<String> = effects
+        .iter()
+        .map(|effect| effect.to_string().bright_yellow().to_string())
+        .chain(std::iter::once(ColoredLispVal::new(value).to_string()))
+        .collect();
+
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use lisp_lang::{error_code::ErrorCode, evaluation::scope::Scope, parsing::LispVal};
+
+    use crate::repl::REPLError;
+
+    use super::Session;
+
+    fn run(scope: &Scope, input: &str) -> Result<String, REPLError> {
+        super::run(&mut scope.clone(), &mut Session::default(), input)
+    }
+
+    #[test]
+    fn test_type_command() {
+        colored::control::set_override(false);
+
+        assert_eq!(run(&Scope::default(), ":type (map (+ 1) '(1 2))").unwrap(), "list of number");
+        assert!(run(&Scope::default(), ":unknown").is_err());
+    }
+
+    #[test]
+    fn test_keywords_are_not_commands() {
+        assert!(super::is_command(":pins"));
+        assert!(super::is_command(" :type 1 "));
+        assert!(super::is_command(":typo 1"));
+        assert!(!super::is_command(":name"));
+        assert!(!super::is_command("(list :type)"));
+    }
+
+    #[test]
+    fn test_dry_run_command() {
+        colored::control::set_override(false);
+        let scope = Scope::default();
+
+        assert_eq!(
+            run(&scope, ":dry-run (list (def! x 1) (print \"hi\"))").unwrap(),
+            "would bind `x`\nwould print \"hi\"\n(void void)"
+        );
+        assert_eq!(scope.get("x"), None);
+    }
+
+    #[test]
+    fn test_ast_command() {
+        colored::control::set_override(false);
+
+        assert_eq!(
+            run(&Scope::default(), ":ast (+ 1 '(a \"b\"))").unwrap(),
+            [
+                "call",
+                "├── symbol +",
+                "├── number 1",
+                "└── quote",
+                "    └── list",
+                "        ├── symbol a",
+                "        └── string \"b\"",
+            ]
+            .join("\n")
+        );
+        assert_eq!(run(&Scope::default(), ":ast ()").unwrap(), "list ()");
+    }
+
+    #[test]
+    fn test_pin_command() {
+        colored::control::set_override(false);
+        let mut scope = Scope::default();
+        let mut session = Session::default();
+
+        let error = super::run(&mut scope, &mut session, ":pin answer").unwrap_err();
+        assert_eq!(error.code(), Some(ErrorCode::NothingToPin));
+
+        session.last_result = Some(LispVal::Number(42));
+        assert_eq!(super::run(&mut scope, &mut session, ":pin answer").unwrap(), "answer = 42");
+        assert_eq!(scope.get("answer"), Some(&LispVal::Number(42)));
+
+        session.last_result = Some(vec![LispVal::Number(1)].into());
+        super::run(&mut scope, &mut session, ":pin ones").unwrap();
+        super::run(&mut scope, &mut session, ":pin answer").unwrap();
+        assert_eq!(super::run(&mut scope, &mut session, ":pins").unwrap(), "answer = (1)\nones = (1)");
+        assert!(super::run(&mut scope, &mut session, ":pin 1").is_err());
+    }
+
+    #[test]
+    fn test_width_command() {
+        let mut scope = Scope::default();
+        let mut session = Session::default();
+
+        assert_eq!(
+            super::run(&mut scope, &mut session, ":width 40").unwrap(),
+            "Results are elided to 40 columns"
+        );
+        assert_eq!(scope.get("repl-width"), Some(&LispVal::Number(40)));
+        assert_eq!(super::run(&mut scope, &mut session, ":width 0").unwrap(), "Results are printed in full");
+        assert!(super::run(&mut scope, &mut session, ":width wide").is_err());
+    }
+
+    #[test]
+    fn test_explain_command() {
+        for code in ErrorCode::ALL {
+            let explanation = run(&Scope::default(), &format!(":explain {code}")).unwrap();
+            assert!(explanation.starts_with(&format!("{code}: ")));
+        }
+        assert!(run(&Scope::default(), ":explain E9999").is_err());
+    }
+}
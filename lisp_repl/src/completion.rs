@@ -0,0 +1,120 @@
+use lisp_lang::{
+    evaluation::{builtin_names, scope::Scope, suggestion::similar},
+    parsing::is_complete,
+};
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Helper};
+
+pub struct ReplHelper {
+    pub fuzzy: bool,
+    names: Vec<String>,
+}
+
+impl ReplHelper {
+    pub fn new(fuzzy: bool) -> Self {
+        Self {
+            fuzzy,
+            names: Vec::new(),
+        }
+    }
+
+    /// Refreshes the completion candidates with the builtins and the bindings of `scope`.
+    pub fn update_names(&mut self, scope: &Scope) {
+        let mut names: Vec<String> = builtin_names()
+            .map(String::from)
+            .chain(scope.names())
+            .collect();
+        names.sort();
+        names.dedup();
+
+        self.names = names;
+    }
+
+    fn candidates(&self, word: &str) -> Vec<String> {
+        let mut candidates: Vec<String> = self
+            .names
+            .iter()
+            .filter(|name| name.starts_with(word))
+            .cloned()
+            .collect();
+
+        if self.fuzzy {
+            let lowercase = word.to_lowercase();
+            let prefixed = self
+                .names
+                .iter()
+                .filter(|name| name.to_lowercase().starts_with(&lowercase))
+                .map(String::as_str);
+            let similar = similar(word, self.names.iter().map(String::as_str));
+
+            for name in prefixed.chain(similar) {
+                if !candidates.iter().any(|c| c == name) {
+                    candidates.push(name.to_string());
+                }
+            }
+        }
+
+        candidates
+    }
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || "()'\"".contains(c)
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| is_delimiter(*c))
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(0);
+        let word = &line[start..pos];
+
+        if word.is_empty() {
+            return Ok((pos, Vec::new()));
+        }
+
+        let pairs = self
+            .candidates(word)
+            .into_iter()
+            .map(|name| Pair {
+                display: name.clone(),
+                replacement: name,
+            })
+            .collect();
+
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+/// Keeps reading lines while a bracket or string is left open, so a form
+/// can span several lines.
+impl Validator for ReplHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        if is_complete(ctx.input()) {
+            Ok(ValidationResult::Valid(None))
+        } else {
+            Ok(ValidationResult::Incomplete)
+        }
+    }
+}
+
+impl Helper for ReplHelper {}
@@ -0,0 +1,345 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use lisp_lang::{
+    evaluation::{
+        builtin_names,
+        module::{ChainResolver, FileResolver, ModuleResolver},
+        scope::{Scope, Settings},
+        version::LanguageVersion,
+    },
+    parsing::LispVal,
+};
+
+use crate::repl::REPLError;
+use crate::script;
+
+/// User-level script evaluated when the REPL starts, from the home directory.
+const RC_FILE: &str = ".flowrc";
+/// Project script, looked up in the working directory and its ancestors.
+const PROJECT_RC_FILE: &str = ".flowrc.lisp";
+/// Project manifest listing prelude scripts under `[repl] prelude` and
+/// module directories under `[modules] paths`.
+const PROJECT_MANIFEST: &str = "flow.toml";
+
+/// Binding the rc script (or the session) can set to change the prompt.
+const PROMPT_BINDING: &str = "repl-prompt";
+/// Binding that turns colored output off when set to `false`.
+const COLOR_BINDING: &str = "repl-color";
+/// Binding with the columns results are elided to, `0` for no limit.
+pub const WIDTH_BINDING: &str = "repl-width";
+/// Binding with `(alias builtin)` pairs, like `'((≤ <=) (× *))`.
+const ALIASES_BINDING: &str = "repl-aliases";
+/// Binding with the language version the session evaluates with, like `2`.
+const VERSION_BINDING: &str = "repl-lang-version";
+/// Binding with the directories whose projects load from below them, like
+/// `'("/home/me/app")`. Only `~/.flowrc` can usefully set it.
+const TRUSTED_PROJECTS_BINDING: &str = "repl-trusted-projects";
+
+const DEFAULT_PROMPT: &str = ">";
+
+/// REPL preferences, read from bindings so they can be set from Lisp.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preferences {
+    pub prompt: String,
+    pub color: bool,
+    /// Columns printed results fit in, by default the terminal's width.
+    pub width: Option<usize>,
+}
+
+impl Preferences {
+    pub fn from_scope(scope: &Scope) -> Self {
+        let prompt = match scope.get(PROMPT_BINDING) {
+            Some(LispVal::String(prompt)) => prompt.to_string(),
+            _ => DEFAULT_PROMPT.to_string(),
+        };
+        let color = !matches!(scope.get(COLOR_BINDING), Some(LispVal::Boolean(false)));
+        let width = match scope.get(WIDTH_BINDING) {
+            Some(LispVal::Number(width)) if *width > 0 => Some(*width as usize),
+            Some(LispVal::Number(_)) => None,
+            _ => terminal_width(),
+        };
+
+        Self { prompt, color, width }
+    }
+}
+
+/// The width of the terminal, or `None` when output isn't going to one.
+fn terminal_width() -> Option<usize> {
+    termion::terminal_size().ok().map(|(columns, _)| columns as usize)
+}
+
+/// Registers the aliases the rc scripts listed, returning why any entry
+/// was skipped.
+pub fn load_aliases(scope: &Scope) -> Vec<String> {
+    let Some(entries) = scope.get(ALIASES_BINDING) else {
+        return Vec::new();
+    };
+    let LispVal::List(entries) = entries else {
+        return vec![format!("`{ALIASES_BINDING}` must be a list of (alias builtin) pairs")];
+    };
+
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let builtin = match entry {
+                LispVal::List(pair) => match pair.as_slice() {
+                    [LispVal::Symbol(alias), LispVal::Symbol(target)] => {
+                        builtin_names().find(|name| name == target).map(|builtin| (alias, builtin))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            match builtin {
+                Some((alias, builtin)) => {
+                    scope.runtime.set_alias(alias.to_string(), builtin);
+                    None
+                }
+                None => Some(format!("`{entry}` in `{ALIASES_BINDING}` is not an alias of a builtin")),
+            }
+        })
+        .collect()
+}
+
+/// Switches to the language version the rc scripts set `repl-lang-version`
+/// to, by name or number. Without one, the scope is kept as it is.
+pub fn load_version(scope: Scope) -> Result<Scope, REPLError> {
+    let version = match scope.get(VERSION_BINDING) {
+        None => return Ok(scope),
+        Some(LispVal::String(name) | LispVal::Symbol(name)) => name.to_string(),
+        Some(value) => value.to_string(),
+    };
+    let version = LanguageVersion::parse(&version).ok_or(REPLError::UnknownLanguageVersion { version })?;
+
+    Ok(scope.with_settings(Settings { version, ..scope.settings }))
+}
+
+fn rc_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| Path::new(&home).join(RC_FILE))
+}
+
+/// Runs `~/.flowrc` if it exists. A failing rc script is reported and
+/// skipped, so a typo in it never keeps the REPL from starting.
+pub fn load_rc(scope: Scope) -> Scope {
+    match rc_path() {
+        Some(path) => load(scope, &path),
+        None => scope,
+    }
+}
+
+/// Whether `dir` holds a project rc script or manifest.
+fn is_project(dir: &Path) -> bool {
+    dir.join(PROJECT_RC_FILE).is_file() || dir.join(PROJECT_MANIFEST).is_file()
+}
+
+/// Whether the rc scripts listed `dir` in `repl-trusted-projects`.
+fn is_trusted(scope: &Scope, dir: &Path) -> bool {
+    let Some(LispVal::List(entries)) = scope.get(TRUSTED_PROJECTS_BINDING) else {
+        return false;
+    };
+    let Ok(dir) = dir.canonicalize() else {
+        return false;
+    };
+
+    entries.iter().any(|entry| match entry {
+        LispVal::String(path) => Path::new(path.as_str()).canonicalize().is_ok_and(|path| path == dir),
+        _ => false,
+    })
+}
+
+/// The nearest project at or above `start`. A project in an ancestor is
+/// only used when it's trusted, so starting the REPL somewhere below an
+/// untrusted checkout doesn't run its code.
+fn find_project(scope: &Scope, start: &Path) -> Option<PathBuf> {
+    let root = start.ancestors().find(|dir| is_project(dir))?;
+
+    (root == start || is_trusted(scope, root)).then(|| root.to_path_buf())
+}
+
+/// The paths listed under `key` of `section` in a manifest, relative to
+/// `root`.
+fn manifest_paths(table: &toml::Table, section: &str, key: &str, root: &Path) -> Result<Vec<PathBuf>, String> {
+    let Some(entries) = table.get(section).and_then(|section| section.get(key)) else {
+        return Ok(Vec::new());
+    };
+
+    entries
+        .as_array()
+        .ok_or(format!("`{section}.{key}` must be a list of paths"))?
+        .iter()
+        .map(|entry| {
+            entry
+                .as_str()
+                .map(|path| root.join(path))
+                .ok_or_else(|| format!("`{entry}` in `{section}.{key}` is not a path"))
+        })
+        .collect()
+}
+
+/// Prelude scripts and module directories listed in a `flow.toml`,
+/// relative to its directory.
+fn read_manifest(manifest: &Path) -> Result<(Vec<PathBuf>, Vec<PathBuf>), String> {
+    let text = std::fs::read_to_string(manifest).map_err(|e| e.to_string())?;
+    let table: toml::Table = text.parse().map_err(|e: toml::de::Error| e.to_string())?;
+    let root = manifest.parent().unwrap_or(Path::new("."));
+
+    Ok((
+        manifest_paths(&table, "repl", "prelude", root)?,
+        manifest_paths(&table, "modules", "paths", root)?,
+    ))
+}
+
+/// Loads the project found from `start`: `load` and `require` also look in
+/// the module paths from its `flow.toml`, then its prelude scripts run,
+/// then its `.flowrc.lisp`.
+pub fn load_project(scope: Scope, start: &Path) -> Scope {
+    let Some(root) = find_project(&scope, start) else {
+        return scope;
+    };
+
+    let manifest_path = root.join(PROJECT_MANIFEST);
+    let (scripts, module_paths) = if manifest_path.is_file() {
+        read_manifest(&manifest_path).unwrap_or_else(|err| {
+            eprintln!("{}: {}", manifest_path.display(), err);
+            Default::default()
+        })
+    } else {
+        Default::default()
+    };
+
+    if !module_paths.is_empty() {
+        let resolvers = scope
+            .runtime
+            .resolver()
+            .into_iter()
+            .chain(module_paths.into_iter().map(|path| Arc::new(FileResolver::new(path)) as Arc<dyn ModuleResolver>))
+            .collect();
+        scope.runtime.set_resolver(Arc::new(ChainResolver { resolvers }));
+    }
+
+    scripts
+        .iter()
+        .chain(std::iter::once(&root.join(PROJECT_RC_FILE)))
+        .fold(scope, |scope, path| load(scope, path))
+}
+
+fn load(scope: Scope, path: &Path) -> Scope {
+    if !path.is_file() {
+        return scope;
+    }
+
+    match script::run_file(scope.clone(), &path.to_string_lossy()) {
+        Ok(scope) => scope,
+        Err(err) => {
+            eprintln!("{}", err);
+            scope
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lisp_lang::{
+        evaluation::{scope::Scope, version::LanguageVersion},
+        parsing::LispVal,
+    };
+
+    use super::{load, load_aliases, load_project, load_version, Preferences};
+
+    /// A path in the temp directory no other test run uses.
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("lisp_repl_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_preferences_from_bindings() {
+        let scope = Scope::default()
+            .bind("repl-prompt".into(), LispVal::String("λ".into()))
+            .bind("repl-color".into(), LispVal::Boolean(false))
+            .bind("repl-width".into(), LispVal::Number(60));
+
+        assert_eq!(
+            Preferences::from_scope(&scope),
+            Preferences {
+                prompt: "λ".into(),
+                color: false,
+                width: Some(60),
+            }
+        );
+        assert!(Preferences::from_scope(&Scope::default()).color);
+    }
+
+    #[test]
+    fn test_language_version_from_binding() {
+        let version = |value: LispVal| {
+            load_version(Scope::default().bind("repl-lang-version".into(), value)).map(|scope| scope.settings.version)
+        };
+
+        assert_eq!(load_version(Scope::default()).unwrap().settings.version, LanguageVersion::LATEST);
+        assert_eq!(version(LispVal::Number(2)).unwrap(), LanguageVersion::V2);
+        assert_eq!(version(LispVal::Symbol("v2".into())).unwrap(), LanguageVersion::V2);
+        assert!(version(LispVal::Number(3)).is_err());
+    }
+
+    #[test]
+    fn test_aliases() {
+        let path = temp_path("aliases.flowrc");
+        std::fs::write(&path, "(def! repl-aliases '((≤ <=) (÷ /) (≈ approx)))").unwrap();
+
+        let scope = load(Scope::default(), &path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(load_aliases(&scope), ["`(≈ approx)` in `repl-aliases` is not an alias of a builtin"]);
+        assert_eq!(scope.runtime.alias("≤"), Some("<="));
+        assert_eq!(scope.runtime.alias("÷"), Some("/"));
+    }
+
+    #[test]
+    fn test_load_rc_script() {
+        let path = temp_path("rc.flowrc");
+        std::fs::write(&path, "(defn! sq (x) (* x x))\n(def! repl-prompt \"λ\")").unwrap();
+
+        let scope = load(Scope::default(), &path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(scope.get("sq").is_some());
+        assert_eq!(Preferences::from_scope(&scope).prompt, "λ");
+        // A missing file leaves the scope as it was
+        assert!(load(Scope::default(), &path).get("sq").is_none());
+    }
+
+    #[test]
+    fn test_project_discovery() {
+        let root = temp_path("project");
+        let nested = root.join("src").join("deep");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir_all(root.join("modules")).unwrap();
+        std::fs::write(
+            root.join("flow.toml"),
+            "[repl]\nprelude = [\"lib.lisp\"]\n\n[modules]\npaths = [\"modules\"]",
+        )
+        .unwrap();
+        std::fs::write(root.join("lib.lisp"), "(def! base 10)").unwrap();
+        std::fs::write(root.join(".flowrc.lisp"), "(require \"util\")\n(def! derived (+ base 1))").unwrap();
+        std::fs::write(root.join("modules").join("util.lisp"), "(def! util 1)").unwrap();
+
+        let scope = load_project(Scope::default(), &root);
+        // Projects in ancestors only load when the rc scripts trust them
+        let untrusted = load_project(Scope::default(), &nested);
+        let trusted = Scope::default().bind(
+            "repl-trusted-projects".into(),
+            vec![LispVal::String(root.to_string_lossy().into())].into(),
+        );
+        let trusted = load_project(trusted, &nested);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(scope.get("derived"), Some(&LispVal::Number(11)));
+        assert_eq!(scope.get("util"), Some(&LispVal::Number(1)));
+        assert!(untrusted.get("base").is_none());
+        assert_eq!(trusted.get("derived"), Some(&LispVal::Number(11)));
+    }
+}
@@ -1,74 +1,149 @@
-use colored::Colorize;
-use lisp_lang::parsing::*;
-use regex::Regex;
-
-fn transform_single_quoted_text<F: Fn(&str) -> String>(transform: F) -> impl Fn(&str) -> String {
-    move |s| {
-        let re = Regex::new(r#"`(?:[^`\\]|\\.)*`"#).unwrap();
-        let mut result = String::new();
-        let mut last_match_end = 0;
-
-        for capture in re.captures_iter(s) {
-            let quoted_text = &capture[0];
-            let start = capture.get(0).unwrap().start();
-            let end = capture.get(0).unwrap().end();
-
-            result.push_str(&s[last_match_end..start]);
-            result.push_str(&transform(&quoted_text[1..quoted_text.len() - 1]));
-            last_match_end = end;
-        }
+use std::sync::OnceLock;
 
-        result.push_str(&s[last_match_end..]);
-        result
-    }
-}
+use colored::Colorize;
+use lisp_lang::{
+    catalog::Language,
+    evaluation::error::{EvalError, MessagePart},
+    parsing::*,
+};
 
-fn colorize_quoted_expressions(s: &str) -> String {
-    let transform = |s: &str| -> String {
-        parse(s)
-            .ok()
-            .map(|(_, v)| ColoredLispVal::new(v).to_string())
-            .unwrap_or(s.to_string())
-    };
+/// The language errors are shown in, set once from `--lang`.
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
 
-    transform_single_quoted_text(transform)(s)
+pub fn set_language(language: Language) {
+    let _ = LANGUAGE.set(language);
 }
 
 pub struct ColoredLispVal {
     pub value: LispVal,
+    /// Columns the value should fit in. Longer lists and strings have their
+    /// middle elided.
+    width: Option<usize>,
 }
 
 impl ColoredLispVal {
     pub fn new(value: LispVal) -> Self {
-        Self { value }
+        Self { value, width: None }
+    }
+
+    pub fn with_width(self, width: Option<usize>) -> Self {
+        Self { width, ..self }
+    }
+
+    /// Writes `(1 2 3 … 998 999)`, keeping elements from both ends while
+    /// they fit in `width`.
+    fn fmt_elided_list(&self, f: &mut std::fmt::Formatter<'_>, values: &[LispVal], width: usize) -> std::fmt::Result {
+        let widths: Vec<usize> = values.iter().map(|v| v.to_string().chars().count()).collect();
+        // Parentheses and the ellipsis
+        let mut used = 3;
+        let (mut head, mut tail) = (0, 0);
+        while head + tail < values.len() {
+            let next = if head <= tail { head } else { values.len() - 1 - tail };
+            if used + widths[next] + 1 > width {
+                break;
+            }
+            used += widths[next] + 1;
+            if head <= tail { head += 1 } else { tail += 1 }
+        }
+
+        let shown = |values: &[LispVal]| values.iter().map(|v| ColoredLispVal::new(v.clone()).to_string()).collect::<Vec<_>>();
+        let parts: Vec<String> = shown(&values[..head])
+            .into_iter()
+            .chain(std::iter::once("…".dimmed().to_string()))
+            .chain(shown(&values[values.len() - tail..]))
+            .collect();
+        write!(f, "({})", parts.join(" "))
+    }
+
+    /// Writes `"abc…xyz"`, keeping characters from both ends.
+    fn fmt_elided_string(&self, f: &mut std::fmt::Formatter<'_>, escaped: &str, width: usize) -> std::fmt::Result {
+        let chars: Vec<char> = escaped.chars().collect();
+        // Quotes and the ellipsis
+        let kept = width.saturating_sub(3);
+        let head: String = chars[..kept - kept / 2].iter().collect();
+        let tail: String = chars[chars.len() - kept / 2..].iter().collect();
+        write!(
+            f,
+            "{}{}{}{}{}",
+            "\"".bright_green().italic(),
+            head.bright_green(),
+            "…".dimmed(),
+            tail.bright_green(),
+            "\"".bright_green().italic()
+        )
     }
 }
 
+/// Highlights the names, types and values an [`EvalError`] is about.
 #[derive(Debug)]
-pub struct ColoredError<T: std::error::Error> {
-    error: T,
+pub struct ColoredError<'a> {
+    error: &'a EvalError,
 }
 
-impl<T: std::error::Error> ColoredError<T> {
-    pub fn new(error: T) -> Self {
+impl<'a> ColoredError<'a> {
+    pub fn new(error: &'a EvalError) -> Self {
         Self { error }
     }
 }
 
-impl<T: std::error::Error> std::fmt::Display for ColoredError<T> {
+impl std::fmt::Display for ColoredError<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            colorize_quoted_expressions(&self.error.to_string())
-        )
+        let language = LANGUAGE.get().copied().unwrap_or_default();
+
+        self.error.message_in(language).into_iter().try_for_each(|part| match part {
+            MessagePart::Text(text) => write!(f, "{text}"),
+            MessagePart::Name(name) => write!(f, "{}", name.bright_blue()),
+            MessagePart::Type(lisp_type) => write!(f, "{}", lisp_type.to_string().bright_blue()),
+            MessagePart::Value(value) => write!(f, "{}", ColoredLispVal::new(value)),
+        })
+    }
+}
+
+/// A line of source with a caret under the character at `column`:
+///
+/// ```text
+///   (+ 1 2]
+///         ^
+/// ```
+#[derive(Debug)]
+pub struct Caret<'a> {
+    line: &'a str,
+    column: usize,
+}
+
+impl<'a> Caret<'a> {
+    pub fn new(line: &'a str, column: usize) -> Self {
+        Self { line, column }
+    }
+}
+
+impl std::fmt::Display for Caret<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Tabs are kept so the caret lines up however wide they are shown
+        let padding: String = self
+            .line
+            .chars()
+            .take(self.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        write!(f, "  {}\n  {padding}{}", self.line, "^".red().bold())
     }
 }
 
 impl std::fmt::Display for ColoredLispVal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(width) = self.width.filter(|width| self.value.to_string().chars().count() > *width) {
+            match &self.value {
+                LispVal::List(values) => return self.fmt_elided_list(f, values, width),
+                LispVal::String(s) => return self.fmt_elided_string(f, &escape(s), width),
+                _ => {}
+            }
+        }
+
         match &self.value {
             LispVal::Void() => write!(f, "{}", "void".bright_blue()),
+            LispVal::Nil => write!(f, "{}", "nil".bright_blue()),
             LispVal::Symbol(atom) => write!(
                 f,
                 "{}",
@@ -78,25 +153,42 @@ impl std::fmt::Display for ColoredLispVal {
                     atom.bright_blue()
                 }
             ),
+            LispVal::Keyword(_) => write!(f, "{}", self.value.to_string().bright_magenta()),
             LispVal::Number(n) => write!(f, "{}", n.to_string().bright_green()),
+            LispVal::Float(_) | LispVal::Complex(..) => write!(f, "{}", self.value.to_string().bright_green()),
             LispVal::Boolean(b) => write!(f, "{}", b.to_string().bright_yellow()),
-            LispVal::String(s) => write!(
-                f,
-                "{}{}{}",
-                "\"".bright_green().italic(),
-                s.bright_green(),
-                "\"".bright_green().italic()
-            ),
+            LispVal::String(s) => {
+                write!(
+                    f,
+                    "{}{}{}",
+                    "\"".bright_green().italic(),
+                    escape(s).bright_green(),
+                    "\"".bright_green().italic()
+                )
+            }
             LispVal::Unevaluated(expr) => write!(
                 f,
                 "{}{}",
                 "'".bright_blue().italic(),
                 ColoredLispVal::new(*expr.clone())
             ),
+            LispVal::Function { applied, .. } if !applied.is_empty() => {
+                let remaining = self.value.remaining_arity().unwrap_or_default();
+                write!(
+                    f,
+                    "{}{}: {} {} remaining{}",
+                    "#<fn ".bright_red(),
+                    ColoredLispVal::new(self.value.partial_body().unwrap()),
+                    remaining.to_string().bright_green(),
+                    if remaining == 1 { "arg" } else { "args" },
+                    ">".bright_red()
+                )
+            }
             LispVal::Function {
                 parameters,
                 body,
                 applied,
+                ..
             } => write!(
                 f,
                 "({} {} [{}])",
@@ -113,14 +205,124 @@ impl std::fmt::Display for ColoredLispVal {
                     .collect::<Vec<String>>()
                     .join(", "),
             ),
-            LispVal::List(values) => {
+            LispVal::Record { name, fields } => write!(
+                f,
+                "{}{} {}{}",
+                "#<".bright_red(),
+                name.bright_red(),
+                fields
+                    .iter()
+                    .map(|(field, value)| format!(
+                        "{}: {}",
+                        field.bright_blue(),
+                        ColoredLispVal::new(value.clone())
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                ">".bright_red()
+            ),
+            LispVal::MultiMethod { name, methods, .. } => write!(
+                f,
+                "{}{} ({} methods){}",
+                "#<multimethod ".bright_red(),
+                name.bright_blue(),
+                methods.len().to_string().bright_green(),
+                ">".bright_red()
+            ),
+            LispVal::Heap { entries, .. } => write!(
+                f,
+                "{}{}{}",
+                "#<heap".bright_red(),
+                entries
+                    .iter()
+                    .map(|((priority, _), value)| format!(
+                        " {}: {}",
+                        ColoredLispVal::new(value.clone()),
+                        priority.to_string().bright_green()
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(","),
+                ">".bright_red()
+            ),
+            LispVal::Deque(values) => write!(
+                f,
+                "{}{}{}",
+                "#<deque".bright_red(),
+                values
+                    .iter()
+                    .map(|value| format!(" {}", ColoredLispVal::new(value.clone())))
+                    .collect::<String>(),
+                ">".bright_red()
+            ),
+            LispVal::Map(entries) => write!(
+                f,
+                "{{{}}}",
+                sorted_entries(entries)
+                    .iter()
+                    .map(|(key, value)| format!(
+                        "{} {}",
+                        ColoredLispVal::new((*key).clone()),
+                        ColoredLispVal::new((*value).clone())
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            LispVal::Set(elements) => write!(
+                f,
+                "#{{{}}}",
+                sorted_elements(elements)
+                    .iter()
+                    .map(|element| ColoredLispVal::new((*element).clone()).to_string())
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            ),
+            LispVal::Pair(..) => {
+                let (heads, tail) = self.value.pair_parts();
+                let heads: Vec<String> = heads.iter().map(|head| ColoredLispVal::new((*head).clone()).to_string()).collect();
+                write!(f, "({} . {})", heads.join(" "), ColoredLispVal::new(tail.clone()))
+            }
+            LispVal::List(values) | LispVal::Vector(values) => {
                 let inner_values = values
                     .iter()
                     .map(|v| ColoredLispVal::new(v.clone()).to_string())
                     .collect::<Vec<String>>()
                     .join(" ");
-                write!(f, "({})", inner_values)
+                match &self.value {
+                    LispVal::Vector(_) => write!(f, "[{}]", inner_values),
+                    _ => write!(f, "({})", inner_values),
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use lisp_lang::parsing::LispVal;
+
+    use super::{Caret, ColoredLispVal};
+
+    fn shown(value: LispVal, width: usize) -> String {
+        colored::control::set_override(false);
+        ColoredLispVal::new(value).with_width(Some(width)).to_string()
+    }
+
+    #[test]
+    fn test_long_values_are_elided() {
+        let numbers: LispVal = (1..=999).map(LispVal::Number).collect::<Vec<_>>().into();
+        assert_eq!(shown(numbers, 20), "(1 2 3 … 998 999)");
+
+        let short: LispVal = vec![LispVal::Number(1), LispVal::Number(2)].into();
+        assert_eq!(shown(short, 20), "(1 2)");
+
+        assert_eq!(shown(LispVal::String("abcdefghijklmnop".into()), 9), "\"abc…nop\"");
+    }
+
+    #[test]
+    fn test_caret() {
+        colored::control::set_override(false);
+
+        assert_eq!(Caret::new("(+ 1 2]", 7).to_string(), "  (+ 1 2]\n        ^");
+        assert_eq!(Caret::new("\t(λ x", 6).to_string(), "  \t(λ x\n  \t    ^");
+    }
+}
@@ -1,33 +1,233 @@
-use repl::{evaluate, read, REPLError};
-use lisp_lang::evaluation::scope::INITIAL_SCOPE;
+use completion::ReplHelper;
+use std::sync::Arc;
 
+use repl::{evaluate_in_background, install_panic_hook, print_warnings, read, split_forms, REPLError};
+use lisp_lang::catalog::Language;
+use lisp_lang::display::with_float_precision;
+use lisp_lang::evaluation::{module::{FileResolver, ModuleResolver}, runtime::Runtime, scope::{Settings, INITIAL_SCOPE}, version::LanguageVersion};
+
+mod calc;
+mod commands;
+mod completion;
+mod config;
 mod display;
+mod record;
 mod repl;
+mod script;
 
 const HISTORY_PATH: &str = ".flow_history";
 
 fn main() {
+    // `--fuzzy` enables typo-tolerant completion and case-insensitive builtins
+    let fuzzy = std::env::args().any(|arg| arg == "--fuzzy");
+    // `--keep-going` reports every failing form of a script instead of stopping at the first
+    let keep_going = std::env::args().any(|arg| arg == "--keep-going");
+    // `--no-rc` skips the user's `~/.flowrc` and the project's `.flowrc.lisp` and `flow.toml`
+    let no_rc = std::env::args().any(|arg| arg == "--no-rc");
+    // `--calc` accepts infix arithmetic and prints plain results, with a few math functions loaded
+    let calc = std::env::args().any(|arg| arg == "--calc");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // `--record session.log` logs inputs and module sources; `--replay session.log` runs them again
+    let option = |name: &str| {
+        let position = args.iter().position(|arg| arg == name)?;
+        args.get(position + 1).cloned()
+    };
+    let recording = option("--record");
+    // `--lang pt-BR` shows evaluation errors in Brazilian Portuguese
+    let lang = option("--lang");
+    // `--lang-version 1` evaluates with the semantics of an older language version
+    let lang_version = option("--lang-version");
+    let replaying = option("--replay");
+
+    // `--explain E0004` prints the description of an error code and exits
+    if let Some(position) = args.iter().position(|arg| arg == "--explain") {
+        let code = args.get(position + 1).map_or("", String::as_str);
+        match commands::explain(code) {
+            Ok(explanation) => println!("{}", explanation),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let option_values = [recording.as_ref(), replaying.as_ref(), lang.as_ref(), lang_version.as_ref()];
+    let file = args
+        .iter()
+        .find(|arg| !arg.starts_with("--") && !option_values.contains(&Some(arg)))
+        .cloned();
+
+    let exit_with = |err: REPLError| -> ! {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    };
+    if let Some(tag) = lang {
+        match Language::parse(&tag) {
+            Some(language) => display::set_language(language),
+            None => exit_with(REPLError::UnknownLanguage { tag }),
+        }
+    }
+    let version = lang_version.as_ref().map(|version| {
+        LanguageVersion::parse(version)
+            .unwrap_or_else(|| exit_with(REPLError::UnknownLanguageVersion { version: version.clone() }))
+    });
+    let recorder = recording.map(|path| Arc::new(record::Recorder::create(&path).unwrap_or_else(|err| exit_with(err))));
+    let mut replay = replaying.map(|path| record::Replay::load(&path).unwrap_or_else(|err| exit_with(err)));
+
     let config = rustyline::Config::builder()
         .auto_add_history(true)
         .color_mode(rustyline::ColorMode::Enabled)
+        .completion_type(rustyline::CompletionType::List)
         .build();
 
-    let mut rl = rustyline::Editor::<()>::with_config(config).unwrap();
-    let mut scope = INITIAL_SCOPE.clone();
+    let mut rl = rustyline::Editor::<ReplHelper>::with_config(config).unwrap();
+    let runtime = Arc::new(Runtime::default());
+    // Modules load relative to the script, or to the current directory in interactive mode
+    let module_root = match &file {
+        Some(path) => std::path::Path::new(path).parent().map(|dir| dir.to_path_buf()).unwrap_or_default(),
+        None => std::env::current_dir().unwrap_or_default(),
+    };
+    let resolver: Arc<dyn ModuleResolver> = Arc::new(FileResolver::new(module_root));
+    match (&recorder, &mut replay) {
+        (_, Some(replay)) => runtime.set_resolver(Arc::new(std::mem::take(&mut replay.resolver))),
+        (Some(recorder), None) => runtime.set_resolver(Arc::new(record::RecordingResolver {
+            inner: resolver,
+            recorder: recorder.clone(),
+        })),
+        (None, None) => runtime.set_resolver(resolver),
+    }
+    let mut scope = INITIAL_SCOPE
+        .with_settings(Settings {
+            case_insensitive: fuzzy,
+            version: version.unwrap_or_default(),
+        })
+        .with_runtime(runtime.clone());
+
+    if let Some(path) = file {
+        let errors = if keep_going {
+            script::run_file_keep_going(scope, &path).1
+        } else {
+            script::run_file(scope, &path).err().into_iter().collect()
+        };
+
+        print_warnings(&runtime);
+        for err in &errors {
+            eprintln!("{}", err);
+        }
+        if !errors.is_empty() {
+            std::process::exit(1);
+        }
+        return;
+    }
 
+    // Recorded sessions don't depend on rc files, so replays see the same scope
+    if !no_rc && recorder.is_none() && replay.is_none() {
+        scope = config::load_rc(scope);
+        if let Ok(dir) = std::env::current_dir() {
+            scope = config::load_project(scope, &dir);
+        }
+        for problem in config::load_aliases(&scope) {
+            eprintln!("{}", problem);
+        }
+        // The command line wins over the rc scripts
+        if version.is_none() {
+            match config::load_version(scope.clone()) {
+                Ok(versioned) => scope = versioned,
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+        print_warnings(&runtime);
+    }
+
+    if calc {
+        scope = calc::load_prelude(scope);
+    }
+
+    install_panic_hook();
+
+    // While reading, rustyline handles Ctrl-C itself; this only fires during evaluation
+    let interrupted = runtime.clone();
+    ctrlc::set_handler(move || interrupted.interrupt()).unwrap();
+
+    let mut helper = ReplHelper::new(fuzzy);
+    helper.update_names(&scope);
+    rl.set_helper(Some(helper));
     rl.load_history(HISTORY_PATH).unwrap_or_default();
 
+    let mut session = commands::Session::default();
     loop {
-        match read(&mut rl).and_then(|input| evaluate(scope.clone(), input.as_str())) {
-            Ok((new_scope, result )) => {
-                if !result.value.is_void()  {
-                    println!("{}", result);
+        let preferences = config::Preferences::from_scope(&scope);
+        if preferences.color && !calc {
+            colored::control::unset_override();
+        } else {
+            colored::control::set_override(false);
+        }
+
+        let input = match &mut replay {
+            Some(replay) => match replay.inputs.pop_front() {
+                Some(input) => {
+                    println!("{} {}", preferences.prompt, input);
+                    Ok(input)
                 }
-                scope = new_scope;
+                None => break,
+            },
+            None => read(&mut rl, &preferences.prompt),
+        };
+        if let (Some(recorder), Ok(input)) = (&recorder, &input) {
+            recorder.input(input);
+        }
+
+        let input = match input {
+            Ok(input) if commands::is_command(&input) => {
+                match commands::run(&mut scope, &mut session, &input) {
+                    Ok(output) => println!("{}", output),
+                    Err(err) => println!("{}", err),
+                }
+                print_warnings(&runtime);
+                if let Some(helper) = rl.helper_mut() {
+                    helper.update_names(&scope);
+                }
+                continue;
             }
+            input => input,
+        };
+
+        // Pasted or continued input can hold several forms, evaluated one after the other
+        let input = input.map(|input| if calc { calc::to_prefix(&input) } else { input });
+        let forms = match input.and_then(|input| split_forms(&input)) {
+            Ok(forms) => forms,
             Err(err) => {
                 println!("{}", err);
-                if let REPLError::ReadlineError(_) = err {
+                if let REPLError::Readline(_) = err {
+                    break;
+                }
+                continue;
+            }
+        };
+
+        for form in forms {
+            match evaluate_in_background(scope.clone(), form) {
+                Ok((new_scope, result )) => {
+                    if !result.value.is_void()  {
+                        session.last_result = Some(result.value.clone());
+                        with_float_precision(new_scope.float_precision(), || {
+                            if calc {
+                                println!("{}", calc::plain(&result.value));
+                            } else {
+                                println!("{}", result.with_width(preferences.width));
+                            }
+                        });
+                    }
+                    scope = new_scope;
+                    print_warnings(&runtime);
+                    if let Some(helper) = rl.helper_mut() {
+                        helper.update_names(&scope);
+                    }
+                }
+                Err(err) => {
+                    print_warnings(&runtime);
+                    println!("{}", err);
                     break;
                 }
             }
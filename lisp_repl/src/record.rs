@@ -0,0 +1,192 @@
+//! `--record file` logs a session: every input and every module source read
+//! from disk, the only results that can differ between runs. `--replay file`
+//! runs the inputs again, serving modules from the log, so a bug report can
+//! be reproduced exactly.
+//!
+//! The log is itself Lisp, one entry per line:
+//!
+//! ```text
+//! (input "(load \"lib/math\")")
+//! (module "lib/math" "(defn! sq (x) (* x x))")
+//! (missing "lib/typo" "cannot read lib/typo.lisp: No such file or directory")
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use lisp_lang::{
+    evaluation::module::ModuleResolver,
+    parsing::{error::SyntaxError, parse, LispVal},
+};
+
+use crate::repl::REPLError;
+
+fn entry(tag: &str, values: &[&str]) -> String {
+    let values = values.iter().map(|value| LispVal::String((*value).into()).to_string());
+
+    format!("({})", std::iter::once(tag.to_string()).chain(values).collect::<Vec<_>>().join(" "))
+}
+
+/// Appends entries to the log as the session goes, so it survives a crash.
+#[derive(Debug)]
+pub struct Recorder {
+    file: Mutex<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &str) -> Result<Recorder, REPLError> {
+        let file = File::create(path).map_err(|e| REPLError::Recording {
+            path: path.to_string(),
+            reason: e.to_string(),
+        })?;
+
+        Ok(Recorder { file: Mutex::new(file) })
+    }
+
+    fn write(&self, entry: String) {
+        // A session shouldn't stop because its log can't be written
+        let _ = writeln!(self.file.lock().unwrap(), "{entry}");
+    }
+
+    pub fn input(&self, input: &str) {
+        self.write(entry("input", &[input]));
+    }
+}
+
+/// Reads modules with another resolver, logging what it returned.
+#[derive(Debug)]
+pub struct RecordingResolver {
+    pub inner: Arc<dyn ModuleResolver>,
+    pub recorder: Arc<Recorder>,
+}
+
+impl ModuleResolver for RecordingResolver {
+    fn resolve(&self, name: &str) -> Result<String, String> {
+        let result = self.inner.resolve(name);
+        match &result {
+            Ok(source) => self.recorder.write(entry("module", &[name, source])),
+            Err(reason) => self.recorder.write(entry("missing", &[name, reason])),
+        }
+
+        result
+    }
+}
+
+/// Serves modules in the order the recorded session read them, so a module
+/// loaded twice can differ between loads as it did then.
+#[derive(Debug, Default)]
+pub struct ReplayResolver {
+    modules: Mutex<HashMap<String, VecDeque<Result<String, String>>>>,
+}
+
+impl ReplayResolver {
+    fn push(&self, name: &str, result: Result<String, String>) {
+        self.modules.lock().unwrap().entry(name.to_string()).or_default().push_back(result);
+    }
+}
+
+impl ModuleResolver for ReplayResolver {
+    fn resolve(&self, name: &str) -> Result<String, String> {
+        let mut modules = self.modules.lock().unwrap();
+
+        modules
+            .get_mut(name)
+            .and_then(VecDeque::pop_front)
+            .unwrap_or_else(|| Err("it was not read in the recorded session".to_string()))
+    }
+}
+
+/// A recorded session, ready to run again.
+#[derive(Debug, Default)]
+pub struct Replay {
+    pub inputs: VecDeque<String>,
+    pub resolver: ReplayResolver,
+}
+
+impl Replay {
+    pub fn parse(path: &str, log: &str) -> Result<Replay, REPLError> {
+        let mut replay = Replay::default();
+        let mut rest = log.trim_start();
+
+        while !rest.is_empty() {
+            let line = log[..log.len() - rest.len()].matches('\n').count() + 1;
+            let invalid = |reason: String| REPLError::Recording {
+                path: path.to_string(),
+                reason: format!("line {line}: {reason}"),
+            };
+
+            let (remaining, entry) = parse(rest).map_err(|e| REPLError::Recording {
+                path: path.to_string(),
+                reason: SyntaxError::new(log, log.len() - rest.len(), &e).to_string(),
+            })?;
+            let parts = match &entry {
+                LispVal::List(parts) => parts.split_first(),
+                _ => None,
+            };
+            let Some((LispVal::Symbol(tag), values)) = parts else {
+                return Err(invalid(format!("unknown entry `{entry}`")));
+            };
+            let values: Vec<String> = values
+                .iter()
+                .map(|value| value.clone().try_into())
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid(format!("expected strings in `{entry}`")))?;
+
+            match (tag.as_str(), values.as_slice()) {
+                ("input", [input]) => replay.inputs.push_back(input.clone()),
+                ("module", [name, source]) => replay.resolver.push(name, Ok(source.clone())),
+                ("missing", [name, reason]) => replay.resolver.push(name, Err(reason.clone())),
+                _ => return Err(invalid(format!("unknown entry `{entry}`"))),
+            }
+
+            rest = remaining.trim_start();
+        }
+
+        Ok(replay)
+    }
+
+    pub fn load(path: &str) -> Result<Replay, REPLError> {
+        let log = std::fs::read_to_string(path).map_err(|source| REPLError::UnreadableFile {
+            path: path.to_string(),
+            source,
+        })?;
+
+        Replay::parse(path, &log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lisp_lang::evaluation::module::ModuleResolver;
+
+    use super::{entry, Replay};
+
+    #[test]
+    fn test_entries_read_back() {
+        let log = [
+            entry("input", &["(print \"a\\nb\")"]),
+            entry("module", &["lib", "(def! x 1)\n(def! y \"two\")"]),
+            entry("missing", &["lib", "gone"]),
+            entry("input", &[":type x"]),
+        ]
+        .join("\n");
+        let replay = Replay::parse("session.log", &log).unwrap();
+
+        assert_eq!(replay.inputs, ["(print \"a\\nb\")", ":type x"]);
+        assert_eq!(replay.resolver.resolve("lib"), Ok("(def! x 1)\n(def! y \"two\")".to_string()));
+        assert_eq!(replay.resolver.resolve("lib"), Err("gone".to_string()));
+        assert!(replay.resolver.resolve("lib").is_err());
+    }
+
+    #[test]
+    fn test_invalid_entries() {
+        for log in ["(input 1)", "(output \"3\")", "input", "(input \"x\""] {
+            let error = Replay::parse("session.log", log).unwrap_err();
+            assert!(error.to_string().contains("session.log"), "{error}");
+        }
+    }
+}
@@ -1,63 +1,384 @@
-use std::fmt;
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
 use colored::Colorize;
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
-use lisp_lang::{evaluation::{*, scope::{Scope, MAIN_CONTEXT}}, parsing::*};
+use lisp_lang::{catalog::Language, error_code::ErrorCode, evaluation::{*, error::EvalError, runtime::Runtime, scope::{Scope, MAIN_CONTEXT}}, parsing::{*, error::{ParseCompleteError, SyntaxError}}};
 
-use crate::display::{ColoredError, ColoredLispVal};
+use crate::completion::ReplHelper;
+use crate::display::{Caret, ColoredError, ColoredLispVal};
+use crate::script::snippet;
 
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum REPLError {
-    ReadlineError(String),
-    ParseError(String),
-    EvaluationError(String),
+    #[error("{}", readline_message(.0))]
+    Readline(ReadlineError),
+    /// `line` is the line of the input `error` points at, shown with a
+    /// caret under the offending character. Errors in files start with the
+    /// `file:line` of the form.
+    #[error(
+        "{} {}{error}\n{}",
+        header("Parse Error", self.code()),
+        .location.as_ref().map(|location| format!("{}: ", location.bold())).unwrap_or_default(),
+        Caret::new(.line, .error.position.column)
+    )]
+    InvalidSyntax { error: SyntaxError, line: String, location: Option<String> },
+    #[error("{} Unexpected input at column {}: {rest}", header("Parse Error", self.code()), .offset + 1)]
+    UnexpectedInput { rest: String, offset: usize },
+    #[error("{} {}", header("Evaluation Error", self.code()), ColoredError::new(.0))]
+    Evaluation(EvalError),
+    #[error("{} Unknown command {name}", header("Command Error", self.code()))]
+    UnknownCommand { name: String },
+    #[error("{} Cannot read {path}: {source}", header("Error", self.code()))]
+    UnreadableFile { path: String, source: std::io::Error },
+    #[error("{} evaluation thread stopped unexpectedly", header("Error", self.code()))]
+    EvaluationThread,
+    #[error("{} Unknown error code {code}", header("Command Error", self.code()))]
+    UnknownErrorCode { code: String },
+    /// The interpreter panicked. The session keeps the scope it had before the input.
+    #[error(
+        "{} The interpreter crashed while evaluating {}\n  {message}\n\
+         This is a bug, please report it with the input above at {ISSUES_URL}\n\
+         Definitions made by this input were discarded.",
+        header("Internal Error", self.code()),
+        .input.bright_yellow()
+    )]
+    Internal { input: String, message: String },
+    #[error("{} There is no result to pin yet", header("Command Error", self.code()))]
+    NothingToPin,
+    #[error("{} Cannot use the recording {path}: {reason}", header("Error", self.code()))]
+    Recording { path: String, reason: String },
+    #[error("{} Unknown language {tag}, use one of: {}", header("Error", self.code()), language_tags())]
+    UnknownLanguage { tag: String },
+    #[error("{} Unknown language version {version}, use one of: {}", header("Error", self.code()), version_names())]
+    UnknownLanguageVersion { version: String },
+    /// An error raised while evaluating `expr`, a part of the input that
+    /// starts at `position`.
+    #[error("{error}\n  at {position}: {}", snippet(.expr).bright_yellow())]
+    Located {
+        error: Box<REPLError>,
+        position: Position,
+        expr: LispVal,
+    },
+    /// An error raised by a top-level form of a script.
+    #[error("{}: in {}\n  {error}", format!("{path}:{line}").bold(), snippet(.form).bright_yellow())]
+    Script {
+        path: String,
+        line: usize,
+        form: Box<LispVal>,
+        error: Box<REPLError>,
+    },
 }
 
-impl std::error::Error for REPLError {}
-
-impl fmt::Display for REPLError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl REPLError {
+    /// The documented code for this error. Readline errors are how the
+    /// user leaves the REPL, so they have none.
+    pub fn code(&self) -> Option<ErrorCode> {
         match self {
-            REPLError::ReadlineError(s) => write!(f, "{}", s),
-            REPLError::ParseError(s) => write!(f, "{} {}", "Parse Error:".red(), s),
-            REPLError::EvaluationError(s) => write!(f, "{} {}", "Evaluation Error: ".red(), s),
+            REPLError::Readline(_) => None,
+            REPLError::InvalidSyntax { .. } => Some(ErrorCode::InvalidSyntax),
+            REPLError::UnexpectedInput { .. } => Some(ErrorCode::UnexpectedInput),
+            REPLError::Evaluation(e) => Some(e.code()),
+            REPLError::UnknownCommand { .. } => Some(ErrorCode::UnknownCommand),
+            REPLError::UnreadableFile { .. } => Some(ErrorCode::UnreadableFile),
+            REPLError::EvaluationThread => Some(ErrorCode::EvaluationThread),
+            REPLError::UnknownErrorCode { .. } => Some(ErrorCode::UnknownErrorCode),
+            REPLError::Internal { .. } => Some(ErrorCode::InternalError),
+            REPLError::NothingToPin => Some(ErrorCode::NothingToPin),
+            REPLError::Recording { .. } => Some(ErrorCode::Recording),
+            REPLError::UnknownLanguage { .. } => Some(ErrorCode::UnknownLanguage),
+            REPLError::UnknownLanguageVersion { .. } => Some(ErrorCode::UnknownLanguageVersion),
+            REPLError::Located { error, .. } | REPLError::Script { error, .. } => error.code(),
         }
     }
+
+    /// Reports `error`, found in `source`, with the line it points at.
+    pub fn invalid_syntax(source: &str, error: SyntaxError, location: Option<String>) -> REPLError {
+        REPLError::InvalidSyntax {
+            line: source.lines().nth(error.position.line - 1).unwrap_or_default().to_string(),
+            error,
+            location,
+        }
+    }
+}
+
+fn header(title: &str, code: Option<ErrorCode>) -> String {
+    match code {
+        Some(code) => format!("{} {}", title.red(), format!("[{code}]:").red()),
+        None => format!("{}", format!("{title}:").red()),
+    }
+}
+
+fn language_tags() -> String {
+    Language::ALL.map(Language::tag).join(", ")
 }
 
-fn to_readline_error(e: ReadlineError) -> REPLError {
+fn version_names() -> String {
+    version::LanguageVersion::ALL.map(version::LanguageVersion::name).join(", ")
+}
+
+fn readline_message(e: &ReadlineError) -> String {
     match e {
-        ReadlineError::Interrupted => REPLError::ReadlineError("CTRL-C".to_string()),
-        ReadlineError::Eof => REPLError::ReadlineError("CTRL-D".to_string()),
-        err => REPLError::ReadlineError(format!("Error: {:?}", err)),
+        ReadlineError::Interrupted => "CTRL-C".to_string(),
+        ReadlineError::Eof => "CTRL-D".to_string(),
+        err => format!("Error: {:?}", err),
     }
 }
 
-pub fn read(rl: &mut Editor<()>) -> Result<String, REPLError> {
-    let prompt = format!("{} ", ">".bright_blue().bold());
-    let input = rl.readline(&prompt).map_err(to_readline_error)?;
+pub fn read(rl: &mut Editor<ReplHelper>, prompt: &str) -> Result<String, REPLError> {
+    let prompt = format!("{} ", prompt.bright_blue().bold());
+    let input = rl.readline(&prompt).map_err(REPLError::Readline)?;
 
     Ok(input)
 }
 
-fn unwrap_expression(parse_result: (&str, LispVal)) -> Result<LispVal, REPLError> {
-    let (rest, expr) = parse_result;
-    if rest.is_empty() {
-        Ok(expr)
-    } else {
-        Err(REPLError::ParseError(format!("Unexpected input: {rest}")))
+pub fn parse_expression(input: &str) -> Result<LispVal, REPLError> {
+    parse_expression_spanned(input).map(|(expr, _)| expr)
+}
+
+/// Like [`parse_expression`], also returning where each part of the
+/// expression is in `input`.
+pub fn parse_expression_spanned(input: &str) -> Result<(LispVal, Spans), REPLError> {
+    parse_complete_spanned(input).map_err(|e| match e {
+        ParseCompleteError::Syntax(error) => REPLError::invalid_syntax(input, error, None),
+        ParseCompleteError::TrailingInput { rest, offset, .. } => REPLError::UnexpectedInput { rest, offset },
+    })
+}
+
+/// Splits input spanning several lines, like a pasted block, into its
+/// top-level forms. Single-line input is kept whole, so trailing input after
+/// a form is still reported.
+pub fn split_forms(input: &str) -> Result<Vec<String>, REPLError> {
+    if !input.trim().contains('\n') {
+        return Ok(vec![input.to_string()]);
+    }
+
+    let mut forms = Vec::new();
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        let (remaining, _) = parse(rest).map_err(|e| REPLError::invalid_syntax(input, SyntaxError::new(input, input.len() - rest.len(), &e), None))?;
+        forms.push(rest[..rest.len() - remaining.len()].trim_end().to_string());
+        rest = remaining.trim_start();
+    }
+
+    Ok(forms)
+}
+
+/// Points `error` at the part of `source` that failed to evaluate, found in
+/// `spans` from the expression `runtime` recorded, with the span moved by
+/// `offset` when `spans` are of a form further into `source`. Errors raised
+/// by the whole form, or by code the form doesn't contain, are kept as
+/// they are.
+pub fn locate(error: REPLError, runtime: &Runtime, spans: &Spans, source: &str, offset: usize) -> REPLError {
+    let failed = runtime.failure().and_then(|expr| Some((spans.find(&expr)?.shifted(offset), expr)));
+
+    match failed {
+        Some((span, expr)) if spans.iter().next().is_some_and(|(_, form)| form.shifted(offset) != span) => {
+            REPLError::Located {
+                error: Box::new(error),
+                position: span.position(source),
+                expr,
+            }
+        }
+        _ => error,
     }
 }
 
 pub fn evaluate(scope: Scope, input: &str) -> Result<(Scope, ColoredLispVal), REPLError> {
-    let expr = parse(input)
-        .map_err(|e| REPLError::ParseError(e.to_string()))
-        .and_then(unwrap_expression)?;
+    let (expr, spans) = parse_expression_spanned(input)?;
+    let runtime = scope.runtime.clone();
 
     eval(scope, &expr)
         .map(|(new_scope, val)| (new_scope.with_context(MAIN_CONTEXT.to_string()), ColoredLispVal::new(val)))
-        .map_err(ColoredError::new)
-        .map_err(|e| REPLError::EvaluationError(e.to_string()))
+        .map_err(|e| locate(REPLError::Evaluation(e), &runtime, &spans, input, 0))
+}
+
+const ISSUES_URL: &str = "https://github.com/luizgabriel/LispInterpreter/issues";
+
+/// The message and location of the last panic, recorded by the hook from
+/// [`install_panic_hook`] for the crash report.
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// Replaces the default panic output, which would interleave with the
+/// REPL's, by recording the panic for [`REPLError::Internal`].
+pub fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        *LAST_PANIC.lock().unwrap_or_else(|e| e.into_inner()) = Some(info.to_string());
+    }));
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(report) = LAST_PANIC.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        return report.replace('\n', "\n  ");
+    }
+
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+const SPINNER_DELAY: Duration = Duration::from_millis(200);
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const EVALUATION_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Evaluates on a worker thread so the REPL stays responsive, showing a
+/// spinner once evaluation takes longer than [`SPINNER_DELAY`]. Pressing
+/// Ctrl-C sets the runtime interruption flag, which aborts the evaluation.
+pub fn evaluate_in_background(
+    scope: Scope,
+    input: String,
+) -> Result<(Scope, ColoredLispVal), REPLError> {
+    scope.runtime.clear_interrupt();
+
+    let (sender, receiver) = mpsc::channel();
+    thread::Builder::new()
+        .stack_size(EVALUATION_STACK_SIZE)
+        .spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| evaluate(scope, &input)))
+                .unwrap_or_else(|payload| {
+                    Err(REPLError::Internal {
+                        input: input.clone(),
+                        message: panic_message(payload),
+                    })
+                });
+            // The receiver only goes away if the REPL itself is shutting down
+            let _ = sender.send(result);
+        })
+        .map_err(|_| REPLError::EvaluationThread)?;
+
+    let mut timeout = SPINNER_DELAY;
+    for frame in SPINNER_FRAMES.iter().cycle() {
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => {
+                clear_spinner(timeout != SPINNER_DELAY);
+                return result;
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                eprint!(
+                    "\r{} {}",
+                    frame.to_string().bright_blue(),
+                    "evaluating… (Ctrl-C to cancel)".dimmed()
+                );
+                std::io::stderr().flush().unwrap_or_default();
+                timeout = SPINNER_INTERVAL;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    clear_spinner(timeout != SPINNER_DELAY);
+    Err(REPLError::EvaluationThread)
+}
+
+/// Prints, in yellow, the warnings raised since they were last printed.
+pub fn print_warnings(runtime: &Runtime) {
+    for warning in runtime.take_warnings() {
+        eprintln!("{} {}", "Warning:".yellow().bold(), warning.to_string().yellow());
+    }
+}
+
+fn clear_spinner(shown: bool) {
+    if shown {
+        eprint!("\r\x1b[2K");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lisp_lang::{
+        error_code::ErrorCode,
+        evaluation::scope::Scope,
+        parsing::{register_reader_macro, LispVal},
+    };
+
+    use super::{evaluate, evaluate_in_background, parse_expression, split_forms};
+
+    #[test]
+    fn test_top_level_definitions_persist() {
+        let (scope, _) = evaluate(Scope::default(), "(def! x 10)").unwrap();
+        let (_, result) = evaluate(scope, "(+ x 1)").unwrap();
+
+        assert_eq!(result.value, LispVal::Number(11));
+    }
+
+    #[test]
+    fn test_function_definitions_do_not_leak() {
+        let (scope, _) = evaluate(Scope::default(), "(defn! f (a) (def! leaked a))").unwrap();
+        let (scope, _) = evaluate(scope, "(f 1)").unwrap();
+
+        assert!(evaluate(scope, "leaked").is_err());
+    }
+
+    #[test]
+    fn test_error_codes() {
+        colored::control::set_override(false);
+
+        let error = evaluate(Scope::default(), "(+ 1 missing)").err().unwrap();
+        assert_eq!(error.code(), Some(ErrorCode::UnknownIdentifier));
+        assert_eq!(
+            error.to_string(),
+            "Evaluation Error [E0004]: Unknown identifier missing.\n  at column 6: missing"
+        );
+
+        let error = parse_expression("(+ 1 2) 3").unwrap_err();
+        assert_eq!(error.code(), Some(ErrorCode::UnexpectedInput));
+        assert!(error.to_string().contains("column 9"));
+
+        let error = parse_expression("(+ 1 2]").unwrap_err();
+        assert_eq!(error.code(), Some(ErrorCode::InvalidSyntax));
+        assert_eq!(
+            error.to_string(),
+            "Parse Error [E0101]: expected closing `)` at column 7, found `]`, to match `(` at column 1\n  (+ 1 2]\n        ^"
+        );
+
+        let error = parse_expression("(list 1\n  \"a\\qb\")").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Parse Error [E0101]: expected escape sequence at line 2, column 6, found `q`\n    \"a\\qb\")\n       ^"
+        );
+    }
+
+    #[test]
+    fn test_error_values_are_not_reparsed() {
+        colored::control::set_override(false);
+
+        let error = evaluate(Scope::default(), "(+ 1 \"`a` (b\")").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Evaluation Error [E0001]: Invalid argument type for + at position 1, expected number, got string"
+        );
+
+        let error = evaluate(Scope::default(), "(\"`a` (b\" 1)").err().unwrap();
+        assert!(error.to_string().contains("got \"`a` (b\" of type string"));
+    }
+
+    #[test]
+    fn test_pasted_blocks_are_split_into_forms() {
+        let pasted = "(defn! double (x)\n  (* x 2))\n\n(double 4)\n";
+
+        assert_eq!(split_forms(pasted).unwrap(), ["(defn! double (x)\n  (* x 2))", "(double 4)"]);
+        assert_eq!(split_forms("(+ 1 2) 3").unwrap(), ["(+ 1 2) 3"]);
+        assert!(split_forms("(+ 1 2)\n(+ 3 4]").is_err());
+    }
+
+    #[test]
+    fn test_panics_are_reported() {
+        let (scope, _) = evaluate(Scope::default(), "(def! x 1)").unwrap();
+        register_reader_macro("boom", |_| panic!("boom"));
+
+        let error = evaluate_in_background(scope.clone(), "(+ x #boom\"now\")".to_string()).err().unwrap();
+
+        assert_eq!(error.code(), Some(ErrorCode::InternalError));
+        assert!(error.to_string().contains("(+ x #boom\"now\")"));
+        assert!(evaluate(scope, "x").is_ok());
+    }
 }
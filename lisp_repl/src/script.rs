@@ -0,0 +1,150 @@
+use lisp_lang::{
+    evaluation::{eval, scope::Scope},
+    parsing::{error::SyntaxError, parse_spanned, LispVal},
+};
+
+use crate::repl::{locate, REPLError};
+
+/// Longest form shown when reporting where a script failed.
+const SNIPPET_WIDTH: usize = 60;
+
+fn read(path: &str) -> Result<String, REPLError> {
+    std::fs::read_to_string(path).map_err(|source| REPLError::UnreadableFile {
+        path: path.to_string(),
+        source,
+    })
+}
+
+/// Evaluates every top-level form of the file at `path` in order, returning
+/// the scope left by the last one.
+pub fn run_file(scope: Scope, path: &str) -> Result<Scope, REPLError> {
+    run_source(scope, path, &read(path)?)
+}
+
+/// Like [`run_file`], but keeps going after a failing form, returning every
+/// error along with the scope left by the forms that succeeded.
+pub fn run_file_keep_going(scope: Scope, path: &str) -> (Scope, Vec<REPLError>) {
+    match read(path) {
+        Ok(source) => run_forms(scope, path, &source, true),
+        Err(err) => (scope, vec![err]),
+    }
+}
+
+/// Evaluates `source` form by form. Errors name the file, the line and the
+/// top-level form they come from, followed by the error itself and the
+/// line and column of the part of the form that failed.
+pub fn run_source(scope: Scope, path: &str, source: &str) -> Result<Scope, REPLError> {
+    let (scope, mut errors) = run_forms(scope, path, source, false);
+
+    match errors.pop() {
+        Some(err) => Err(err),
+        None => Ok(scope),
+    }
+}
+
+fn run_forms(scope: Scope, path: &str, source: &str, keep_going: bool) -> (Scope, Vec<REPLError>) {
+    let mut scope = scope;
+    let mut errors = Vec::new();
+    let mut rest = source.trim_start();
+
+    while !rest.is_empty() && (keep_going || errors.is_empty()) {
+        let offset = source.len() - rest.len();
+        let line = source[..offset].matches('\n').count() + 1;
+
+        let (remaining, (expr, spans)) = match parse_spanned(rest) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                errors.push(REPLError::invalid_syntax(source, SyntaxError::new(source, offset, &e), Some(format!("{path}:{line}"))));
+                rest = skip_to_next_form(rest);
+                continue;
+            }
+        };
+
+        scope.runtime.set_location(Some((path.to_string(), line)));
+        match eval(scope.clone(), &expr) {
+            Ok((next, _)) => scope = next,
+            Err(e) => errors.push(REPLError::Script {
+                path: path.to_string(),
+                line,
+                error: Box::new(locate(REPLError::Evaluation(e), &scope.runtime, &spans, source, offset)),
+                form: Box::new(expr),
+            }),
+        }
+
+        rest = remaining.trim_start();
+    }
+
+    scope.runtime.set_location(None);
+    (scope, errors)
+}
+
+/// Recovers from a parse error by resuming at the next line that starts
+/// with `(`, which is where top-level forms usually begin.
+fn skip_to_next_form(rest: &str) -> &str {
+    match rest.find("\n(") {
+        Some(index) => &rest[index + 1..],
+        None => "",
+    }
+}
+
+/// `expr` on a single line, cut at [`SNIPPET_WIDTH`] characters.
+pub fn snippet(expr: &LispVal) -> String {
+    let text = expr.to_string().split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if text.chars().count() > SNIPPET_WIDTH {
+        let cut: String = text.chars().take(SNIPPET_WIDTH - 1).collect();
+        format!("{cut}…")
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lisp_lang::{evaluation::scope::Scope, parsing::LispVal};
+
+    use super::{run_forms, run_source};
+
+    #[test]
+    fn test_forms_run_in_order() {
+        let source = "(def! x 1)\n\n(def! y (+ x 1))\n";
+        let scope = run_source(Scope::default(), "a.lisp", source).unwrap();
+
+        assert_eq!(scope.get("y"), Some(&LispVal::Number(2)));
+    }
+
+    #[test]
+    fn test_errors_point_at_the_form() {
+        colored::control::set_override(false);
+
+        let error = run_source(Scope::default(), "a.lisp", "(def! x 1)\n\n(+ x\n   missing)")
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("a.lisp:3: in (+ x missing)"), "{error}");
+        assert!(error.contains("at line 4, column 4: missing"), "{error}");
+    }
+
+    #[test]
+    fn test_current_location() {
+        let source = "(def! file (current-file))\n\n(def! line (current-line))";
+        let scope = run_source(Scope::default(), "a.lisp", source).unwrap();
+
+        assert_eq!(scope.get("file"), Some(&LispVal::String("a.lisp".into())));
+        assert_eq!(scope.get("line"), Some(&LispVal::Number(3)));
+        assert_eq!(scope.runtime.location(), None);
+    }
+
+    #[test]
+    fn test_keep_going_collects_every_error() {
+        let source = "(def! x 1)\n(+ x missing)\n(+ x\n(def! y 2)\n(print 1)\n(def! z 3)";
+        let (scope, errors) = run_forms(Scope::default(), "a.lisp", source, true);
+
+        let lines: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 3, "{lines:?}");
+        assert!(lines[0].contains("a.lisp:2:"));
+        assert!(lines[1].contains("a.lisp:3:"));
+        assert!(lines[2].contains("a.lisp:5:"));
+        assert!(scope.get("y").is_some() && scope.get("z").is_some());
+    }
+}